@@ -0,0 +1,208 @@
+//! Coding agents pleb starts for issues.
+//!
+//! A profile (`[agents.<name>]`, or the built-in `claude` one described by
+//! `[claude]`) says how the agent is started and handed its prompt, what
+//! reports the issue's state, and what goes into the worktree first. The
+//! interactive runner types the agent's command into the issue's window; the
+//! headless runner starts it as a child of the daemon.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::config::{AgentMode, AgentProfile, PromptStyle, SetupStep, StateSource};
+use crate::sandbox::shell_quote;
+
+/// A named agent profile
+#[derive(Debug, Clone)]
+pub struct Agent {
+    pub name: String,
+    pub profile: AgentProfile,
+}
+
+impl Agent {
+    pub fn new(name: &str, profile: AgentProfile) -> Self {
+        Self {
+            name: name.to_string(),
+            profile,
+        }
+    }
+
+    /// Whether the agent runs as a child of the daemon rather than in a window
+    pub fn is_headless(&self) -> bool {
+        self.profile.mode == AgentMode::Headless
+    }
+
+    /// What reports the issue's state
+    pub fn state_source(&self) -> StateSource {
+        self.profile.state_source()
+    }
+
+    /// Whether the prompt file is passed on standard input
+    pub fn prompt_on_stdin(&self) -> bool {
+        self.profile.prompt == PromptStyle::Stdin
+    }
+
+    /// Shell command that starts the agent in a window, with the prompt file if given
+    pub fn command_line(&self, prompt_file: Option<&Path>) -> Result<String> {
+        let mut parts = vec![self.profile.command.clone()];
+        parts.extend(self.profile.args.iter().cloned());
+        if let Some(prompt_file) = prompt_file {
+            parts.extend(self.prompt_args(prompt_file)?.iter().map(|arg| shell_quote(arg)));
+        }
+        Ok(parts.join(" "))
+    }
+
+    /// Arguments for a headless run. `resume` is None for a new session, or
+    /// the session to resume (None inside for the latest).
+    pub fn headless_args(&self, prompt_file: &Path, resume: Option<Option<&str>>) -> Result<Vec<String>> {
+        let mut args = self.profile.args.clone();
+        match resume {
+            Some(Some(session_id)) if !self.profile.resume_args.is_empty() => args.extend(
+                self.profile
+                    .resume_args
+                    .iter()
+                    .map(|arg| arg.replace("{session_id}", session_id)),
+            ),
+            Some(_) => args.extend(self.profile.continue_args.iter().cloned()),
+            None => {}
+        }
+        args.extend(self.prompt_args(prompt_file)?);
+        Ok(args)
+    }
+
+    /// What is typed into a running window to hand the agent a message
+    pub fn message_keys(&self, message_file: &Path, message: &str) -> String {
+        match self.profile.prompt {
+            PromptStyle::AtFile => format!("@{}", message_file.display()),
+            PromptStyle::Path => message_file.display().to_string(),
+            // Typed text is submitted at the first newline
+            PromptStyle::Argument | PromptStyle::Stdin => message
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+
+    /// Arguments that pass the prompt file, per the profile's prompt style
+    fn prompt_args(&self, prompt_file: &Path) -> Result<Vec<String>> {
+        Ok(match self.profile.prompt {
+            PromptStyle::AtFile => vec![format!("@{}", prompt_file.display())],
+            PromptStyle::Path => vec![prompt_file.display().to_string()],
+            PromptStyle::Argument => vec![std::fs::read_to_string(prompt_file)
+                .with_context(|| format!("Failed to read prompt file: {:?}", prompt_file))?],
+            PromptStyle::Stdin => Vec::new(),
+        })
+    }
+
+    /// Install the profile's setup steps and files in a worktree
+    pub fn install(&self, worktree: &Path) -> Result<()> {
+        for step in &self.profile.setup {
+            match step {
+                SetupStep::ClaudeHooks => crate::hooks::install_hooks(worktree)?,
+                SetupStep::ClaudeCommands => crate::commands::install_commands(worktree)?,
+            }
+        }
+
+        for (dest, source) in &self.profile.files {
+            let dest = worktree.join(dest);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+            }
+            std::fs::copy(source, &dest).with_context(|| {
+                format!("Failed to copy {} to {}", source.display(), dest.display())
+            })?;
+            tracing::debug!("Installed {} for agent {}", dest.display(), self.name);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClaudeConfig;
+
+    fn profile(toml: &str) -> AgentProfile {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_command_line() {
+        let claude: ClaudeConfig = toml::from_str("args = [\"--model\", \"opus\"]").unwrap();
+        let claude = Agent::new("claude", claude.profile());
+        assert_eq!(
+            claude.command_line(Some(Path::new("/s/12/prompt.md"))).unwrap(),
+            "claude --permission-mode plan --model opus @/s/12/prompt.md"
+        );
+        assert_eq!(claude.command_line(None).unwrap(), "claude --permission-mode plan --model opus");
+
+        let dir = std::env::temp_dir().join(format!("pleb-agent-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let prompt_file = dir.join("prompt.md");
+        std::fs::write(&prompt_file, "Fix the user's bug").unwrap();
+        let codex = Agent::new("codex", profile("command = \"codex\"\nprompt = \"argument\""));
+        assert_eq!(
+            codex.command_line(Some(&prompt_file)).unwrap(),
+            "codex 'Fix the user'\\''s bug'"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_headless_args() {
+        let claude: ClaudeConfig = toml::from_str("mode = \"headless\"\nargs = [\"--max-turns\", \"5\"]").unwrap();
+        let claude = Agent::new("claude", claude.profile());
+        let prompt = Path::new("/s/7/prompt.md");
+        assert_eq!(
+            claude.headless_args(prompt, None).unwrap(),
+            vec!["--max-turns", "5", "-p", "--output-format", "stream-json", "--verbose"]
+        );
+        assert!(claude.prompt_on_stdin());
+        assert_eq!(claude.headless_args(prompt, Some(Some("abc"))).unwrap()[6..], ["--resume", "abc"]);
+        assert_eq!(claude.headless_args(prompt, Some(None)).unwrap().last().unwrap(), "--continue");
+
+        // Without resume arguments a comment starts a fresh run
+        let stub = Agent::new("stub", profile("command = \"./stub.sh\"\nmode = \"headless\"\nargs = [\"run\"]"));
+        assert_eq!(stub.headless_args(prompt, Some(Some("abc"))).unwrap(), vec!["run", "/s/7/prompt.md"]);
+    }
+
+    #[test]
+    fn test_message_keys() {
+        let file = Path::new("/s/7/comments-9.md");
+        let message = "## New comments\n\nPlease also\nadd tests\n";
+        let keys = |style: &str| {
+            Agent::new("a", profile(&format!("command = \"a\"\nprompt = \"{}\"", style)))
+                .message_keys(file, message)
+        };
+        assert_eq!(keys("at-file"), "@/s/7/comments-9.md");
+        assert_eq!(keys("path"), "/s/7/comments-9.md");
+        assert_eq!(keys("argument"), "## New comments Please also add tests");
+    }
+
+    #[test]
+    fn test_install_copies_files() {
+        let dir = std::env::temp_dir().join(format!("pleb-agent-install-{}", std::process::id()));
+        let worktree = dir.join("worktree");
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(dir.join("aider.yml"), "auto-commits: false\n").unwrap();
+
+        let mut aider = profile("command = \"aider\"\nsetup = [\"claude-commands\"]");
+        aider
+            .files
+            .insert(".config/aider.yml".into(), dir.join("aider.yml"));
+        Agent::new("aider", aider).install(&worktree).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(worktree.join(".config/aider.yml")).unwrap(),
+            "auto-commits: false\n"
+        );
+        assert!(worktree.join(".claude/commands/pleb-shipit.md").exists());
+        assert!(!worktree.join(".claude/settings.json").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::quick::SessionKey;
+use crate::usage;
 
 #[derive(Parser)]
 #[command(name = "pleb")]
@@ -8,14 +12,30 @@ pub struct Cli {
     #[arg(long, default_value = "pleb.toml", global = true)]
     pub config: String,
 
+    /// Repository to act on ("owner/repo" or "repo") when several are configured
+    #[arg(long, global = true)]
+    pub repo: Option<String>,
+
     /// Enable verbose output (debug logging)
     #[arg(long, short, global = true)]
     pub verbose: bool,
 
+    /// Output format for status, list, restore and report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, global = true)]
+    pub format: OutputFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Table,
+    /// One JSON document on stdout; logs go to stderr
+    Json,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     #[command(about = "Start watching for issues")]
@@ -23,10 +43,23 @@ pub enum Commands {
         /// Run as a daemon in the background
         #[arg(long, short)]
         daemon: bool,
+
+        /// Serve a read-only dashboard on this address (e.g. 127.0.0.1:8080)
+        #[arg(long, value_name = "ADDR")]
+        http: Option<std::net::SocketAddr>,
+
+        /// Allow --http to bind a non-loopback address. The dashboard has no
+        /// authentication and shows agent panes and daemon logs
+        #[arg(long, requires = "http")]
+        http_public: bool,
     },
 
-    #[command(about = "List active sessions")]
-    List,
+    #[command(about = "List sessions with their tmux window, worktree and GitHub state")]
+    List {
+        /// Repair inconsistencies: clean up stale sessions and restore missing ones
+        #[arg(long)]
+        fix: bool,
+    },
 
     #[command(about = "Stop the daemon")]
     Stop,
@@ -45,18 +78,24 @@ pub enum Commands {
     #[command(about = "Attach to the pleb tmux session")]
     Attach,
 
+    #[command(about = "Interactive terminal UI for managed issues")]
+    Ui,
+
     #[command(about = "Transition issue to a new state")]
     Transition {
         /// Issue number
         issue_number: u64,
         /// Target state (ready, provisioning, waiting, working, done, none)
         state: String,
+        /// Apply the transition even if the state machine does not allow it
+        #[arg(long)]
+        force: bool,
     },
 
     #[command(about = "Show pleb state for an issue")]
     Status {
-        /// Issue number (optional - shows daemon status and all managed issues if omitted)
-        issue_number: Option<u64>,
+        /// Issue number or quick session id (optional - shows daemon status and all managed issues if omitted)
+        session: Option<SessionKey>,
     },
 
     #[command(about = "Hook invoked by Claude Code on events")]
@@ -77,14 +116,77 @@ pub enum Commands {
         action: ConfigAction,
     },
 
+    #[command(about = "Create an issue from a description and add it to the ready queue")]
+    New {
+        /// Issue title (defaults to the first line of the description)
+        #[arg(long, short)]
+        title: Option<String>,
+
+        /// Ask Claude to propose a title and acceptance criteria
+        #[arg(long)]
+        suggest: bool,
+
+        /// Don't add the ready label
+        #[arg(long)]
+        no_ready: bool,
+
+        /// Additional label (can be repeated)
+        #[arg(long = "label", short, value_name = "LABEL")]
+        labels: Vec<String>,
+
+        /// GitHub login to assign the issue to
+        #[arg(long)]
+        assignee: Option<String>,
+    },
+
     #[command(about = "Clean up a finished issue (remove worktree and kill tmux window)")]
     Cleanup {
-        /// Issue number
-        issue_number: u64,
+        /// Issue number or quick session id
+        session: SessionKey,
+    },
+
+    #[command(about = "Start an ad-hoc session on a branch, without a GitHub issue")]
+    Quick {
+        /// Branch to create (or reuse) for the session; also names the session
+        branch: String,
+
+        /// File with the initial prompt for the agent (it starts without one if omitted)
+        #[arg(long, value_name = "FILE")]
+        prompt: Option<std::path::PathBuf>,
     },
 
     #[command(about = "Verify and recreate missing tmux sessions and worktrees for managed issues")]
     Restore,
+
+    #[command(about = "Show the conversation of an issue's session, including tool calls")]
+    Transcript {
+        /// Issue number
+        issue_number: u64,
+
+        /// Print the raw terminal log of the issue's window instead
+        #[arg(long, conflicts_with = "markdown")]
+        raw: bool,
+
+        /// Render as Markdown, with tool results in collapsible blocks
+        #[arg(long)]
+        markdown: bool,
+    },
+
+    #[command(about = "Summarize usage across issues")]
+    Report {
+        #[command(subcommand)]
+        report: ReportKind,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportKind {
+    #[command(about = "Tokens and cost per issue, most expensive first")]
+    Costs {
+        /// Only count usage from this day (YYYY-MM-DD) or this long ago (e.g. 7d, 2w) on
+        #[arg(long, value_parser = usage::parse_since)]
+        since: Option<NaiveDate>,
+    },
 }
 
 #[derive(Subcommand, Clone)]
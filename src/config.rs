@@ -1,13 +1,19 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
+use crate::agent::Agent;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub github: GithubConfig,
+    #[serde(default)]
     pub labels: LabelConfig,
     pub claude: ClaudeConfig,
+    #[serde(default)]
     pub paths: PathConfig,
+    #[serde(default)]
     pub prompts: PromptsConfig,
     pub watch: WatchConfig,
     pub tmux: TmuxConfig,
@@ -15,14 +21,102 @@ pub struct Config {
     pub branch: BranchConfig,
     #[serde(default)]
     pub provision: ProvisionConfig,
+    #[serde(default)]
+    pub scheduling: SchedulingConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    #[serde(default)]
+    pub comments: CommentsConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub costs: CostsConfig,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    /// Which agent profile works on an issue
+    #[serde(default)]
+    pub agent: AgentSelection,
+    /// Agent profiles besides the built-in `claude` one
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub agents: BTreeMap<String, AgentProfile>,
+    /// Additional repositories watched by the same daemon
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repos: Vec<RepoEntry>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GithubConfig {
+    /// Repository owner. May be left empty when all repositories are listed in [[repos]].
+    #[serde(default)]
     pub owner: String,
+    /// Repository name. May be left empty when all repositories are listed in [[repos]].
+    #[serde(default)]
     pub repo: String,
     #[serde(default = "default_token_env")]
     pub token_env: String,
+    /// Pause polling when less than this percentage of the core or search
+    /// rate limit is left, until the limit resets
+    #[serde(default = "default_rate_limit_reserve_percent")]
+    pub rate_limit_reserve_percent: u8,
+    /// Base URL of the GitHub API, if not https://api.github.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+}
+
+/// A `[[repos]]` entry. Sections that are omitted inherit the top-level ones.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RepoEntry {
+    pub owner: String,
+    pub repo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<LabelConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paths: Option<PathConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompts: Option<PromptsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<BranchConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provision: Option<ProvisionConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentSelection>,
+}
+
+/// Fully resolved settings for one watched repository
+#[derive(Debug, Clone)]
+pub struct RepoConfig {
+    pub github: GithubConfig,
+    pub labels: LabelConfig,
+    pub paths: PathConfig,
+    pub prompts: PromptsConfig,
+    pub branch: BranchConfig,
+    pub provision: ProvisionConfig,
+    pub agent: AgentSelection,
+}
+
+impl RepoConfig {
+    /// "owner/repo"
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.github.owner, self.github.repo)
+    }
+
+    /// Get the state directory for this repo: ~/.pleb/{owner}-{repo}/
+    /// Holds the issue state file and per-issue directories (prompts, media).
+    pub fn state_dir(&self) -> Result<PathBuf> {
+        let home = dirs::home_dir().context("Failed to determine home directory")?;
+        let dir_name = format!("{}-{}", self.github.owner, self.github.repo);
+        Ok(home.join(".pleb").join(dir_name))
+    }
+
+    /// Get the issue state file path: ~/.pleb/{owner}-{repo}/state.json
+    pub fn state_file(&self) -> Result<PathBuf> {
+        Ok(self.state_dir()?.join("state.json"))
+    }
+
+    /// Get the quick session file path: ~/.pleb/{owner}-{repo}/quick.json
+    pub fn quick_state_file(&self) -> Result<PathBuf> {
+        Ok(self.state_dir()?.join("quick.json"))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -47,6 +141,106 @@ pub struct ClaudeConfig {
     pub command: String,
     #[serde(default = "default_claude_args")]
     pub args: Vec<String>,
+    /// How issue sessions are run
+    #[serde(default)]
+    pub mode: AgentMode,
+}
+
+/// Name of the built-in agent profile described by `[claude]`
+pub const CLAUDE_AGENT: &str = "claude";
+
+/// How an agent runs for an issue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentMode {
+    /// An interactive session in a tmux window
+    #[default]
+    Interactive,
+    /// A child process of the daemon, followed through its output or exit status
+    Headless,
+}
+
+/// Which agent profile works on an issue (`[agent]`)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AgentSelection {
+    /// Profile used when none of the issue's labels picks one
+    #[serde(default = "default_agent")]
+    pub default: String,
+    /// Issue label -> profile name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// A coding agent pleb can start for an issue (`[agents.<name>]`)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AgentProfile {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub mode: AgentMode,
+    /// How the prompt reaches the agent
+    #[serde(default)]
+    pub prompt: PromptStyle,
+    /// What reports the issue's state. Defaults to "manual" for interactive
+    /// agents and "exit" for headless ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<StateSource>,
+    /// What is installed in the worktree before the agent starts
+    #[serde(default)]
+    pub setup: Vec<SetupStep>,
+    /// Files copied into the worktree (path in the worktree -> source file)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<PathBuf, PathBuf>,
+    /// Press Enter once the agent has started, to accept a workspace trust prompt
+    #[serde(default)]
+    pub accept_trust_prompt: bool,
+    /// Headless only: arguments that resume the issue's session when a comment
+    /// arrives. `{session_id}` is replaced by the session id from stream-json output.
+    #[serde(default)]
+    pub resume_args: Vec<String>,
+    /// Headless only: arguments that continue the latest session when its id isn't known
+    #[serde(default)]
+    pub continue_args: Vec<String>,
+}
+
+/// How a prompt (or a batch of comments) is handed to an agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromptStyle {
+    /// The prompt file's path as the last argument
+    #[default]
+    Path,
+    /// The prompt file's path prefixed with '@' (Claude Code's file reference)
+    AtFile,
+    /// The prompt text itself as the last argument
+    Argument,
+    /// The prompt file on standard input (headless agents only)
+    Stdin,
+}
+
+/// What moves an issue between Working, Waiting and Done
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StateSource {
+    /// Claude Code hooks calling `pleb cc-run-hook` (interactive agents)
+    Hooks,
+    /// Claude Code's `--output-format stream-json` events (headless agents)
+    StreamJson,
+    /// The exit status: Done on success, Waiting otherwise (headless agents)
+    Exit,
+    /// Nothing; people move the issue with `pleb transition` (interactive agents)
+    Manual,
+}
+
+/// Something installed in the worktree before an agent starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SetupStep {
+    /// Claude Code hooks in .claude/settings.json, plus the slash commands
+    ClaudeHooks,
+    /// Only the pleb slash commands in .claude/commands/
+    ClaudeCommands,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -63,12 +257,157 @@ pub struct PromptsConfig {
     pub dir: PathBuf,
     #[serde(default = "default_prompt_new_issue")]
     pub new_issue: String,
+    /// Template for issue comments delivered to a running session.
+    /// The bundled template is used if the file doesn't exist.
+    #[serde(default = "default_prompt_comment")]
+    pub comment: String,
+    /// Template for the status comment pleb keeps on each issue.
+    /// The bundled template is used if the file doesn't exist.
+    #[serde(default = "default_prompt_status_comment")]
+    pub status_comment: String,
+}
+
+/// Issue comments: feeding new ones to running sessions and the status comment pleb keeps
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommentsConfig {
+    /// Type new comments on Working and Waiting issues into the session
+    #[serde(default = "default_feed_to_session")]
+    pub feed_to_session: bool,
+    /// Users whose comments are delivered, besides the authenticated user
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+    /// Keep a status comment on each issue, edited as the issue moves through its lifecycle
+    #[serde(default = "default_status_comment")]
+    pub status_comment: bool,
+}
+
+/// Prometheus metrics endpoint
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MetricsConfig {
+    /// Address to serve `GET /metrics` on, e.g. "127.0.0.1:9464". Disabled if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    /// Allow `listen` to be a non-loopback address. The endpoint has no
+    /// authentication and its labels name repos and issues.
+    #[serde(default)]
+    pub public: bool,
+}
+
+/// Token accounting: what each model costs, and where totals are shown
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CostsConfig {
+    /// Prices by model name. A key also prices every model whose name starts
+    /// with it ("claude-sonnet-4" covers "claude-sonnet-4-5-20250929"); the
+    /// longest matching key wins.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prices: BTreeMap<String, ModelPrice>,
+    /// Show each issue's tokens and cost in its status comment
+    #[serde(default)]
+    pub in_status_comment: bool,
+}
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub input: f64,
+    #[serde(default)]
+    pub output: f64,
+    /// Input tokens written to the prompt cache
+    #[serde(default)]
+    pub cache_write: f64,
+    /// Input tokens read from the prompt cache
+    #[serde(default)]
+    pub cache_read: f64,
+}
+
+impl CostsConfig {
+    /// Price of a model, by its exact name or the longest key it starts with
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices
+            .iter()
+            .filter(|(key, _)| model.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, price)| price)
+    }
+}
+
+/// Where Claude Code runs for an issue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxBackend {
+    /// Directly on the host, in the tmux window
+    #[default]
+    None,
+    /// Inside a Lima VM, entered from the tmux window with `limactl shell`
+    Lima,
+    /// Inside a rootless podman container started from the tmux window
+    Podman,
+    /// Inside a docker container started from the tmux window
+    Docker,
+}
+
+impl SandboxBackend {
+    /// Whether sessions run in a container (podman or docker)
+    pub fn is_container(&self) -> bool {
+        matches!(self, SandboxBackend::Podman | SandboxBackend::Docker)
+    }
+}
+
+/// Isolation for Claude Code sessions
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub backend: SandboxBackend,
+    /// VM and container names start with this
+    #[serde(default = "default_sandbox_instance_prefix")]
+    pub instance_prefix: String,
+    /// Environment variables passed from the tmux window into the sandbox,
+    /// besides `github.token_env` (e.g. "ANTHROPIC_API_KEY")
+    #[serde(default)]
+    pub env: Vec<String>,
+    #[serde(default)]
+    pub lima: LimaConfig,
+    #[serde(default)]
+    pub container: ContainerConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LimaConfig {
+    /// Template new instances are created from (a `template://` name, file or URL)
+    #[serde(default = "default_lima_template")]
+    pub template: String,
+    /// Share this many instances per repository between issues instead of
+    /// creating one per issue. Pooled instances are kept on cleanup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_size: Option<usize>,
+}
+
+/// Settings for the podman and docker backends
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ContainerConfig {
+    /// Image sessions run in; it must provide the `claude` command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Give containers network access. Claude Code needs it to reach its API.
+    #[serde(default = "default_container_network")]
+    pub network: bool,
+    /// CPU limit per container (e.g. 2 or 1.5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    /// Memory limit per container (e.g. "4g")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WatchConfig {
     #[serde(default = "default_poll_interval_secs")]
     pub poll_interval_secs: u64,
+    /// Maximum number of issues in provisioning/waiting/working at once.
+    /// Extra ready issues wait in a FIFO queue. Unlimited if not set.
+    #[serde(default)]
+    pub max_concurrent_sessions: Option<usize>,
 }
 
 // Default value functions
@@ -76,6 +415,10 @@ fn default_token_env() -> String {
     "GITHUB_TOKEN".to_string()
 }
 
+fn default_rate_limit_reserve_percent() -> u8 {
+    10
+}
+
 fn default_label_ready() -> String {
     "pleb:ready".to_string()
 }
@@ -124,6 +467,22 @@ fn default_prompt_new_issue() -> String {
     "new_issue.md".to_string()
 }
 
+fn default_prompt_comment() -> String {
+    "comment.md".to_string()
+}
+
+fn default_feed_to_session() -> bool {
+    true
+}
+
+fn default_prompt_status_comment() -> String {
+    "status_comment.md".to_string()
+}
+
+fn default_status_comment() -> bool {
+    true
+}
+
 fn default_poll_interval_secs() -> u64 {
     5
 }
@@ -132,6 +491,21 @@ fn default_poll_interval_secs() -> u64 {
 pub struct TmuxConfig {
     #[serde(default = "default_session_name")]
     pub session_name: String,
+    /// Terminal multiplexer holding the session windows
+    #[serde(default)]
+    pub multiplexer: Multiplexer,
+}
+
+/// Terminal multiplexer the session windows live in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Multiplexer {
+    #[default]
+    Tmux,
+    /// Each session is a tab in a zellij session
+    Zellij,
+    /// Each session is a window in a GNU screen session
+    Screen,
 }
 
 fn default_session_name() -> String {
@@ -148,6 +522,60 @@ fn default_branch_suffix() -> String {
     "pleb".to_string()
 }
 
+impl Default for LabelConfig {
+    fn default() -> Self {
+        Self {
+            ready: default_label_ready(),
+            provisioning: default_label_provisioning(),
+            waiting: default_label_waiting(),
+            working: default_label_working(),
+            done: default_label_done(),
+            finished: default_label_finished(),
+        }
+    }
+}
+
+impl Default for PathConfig {
+    fn default() -> Self {
+        Self {
+            repo_dir: default_repo_dir(),
+            worktree_base: default_worktree_base(),
+        }
+    }
+}
+
+impl Default for PromptsConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_prompts_dir(),
+            new_issue: default_prompt_new_issue(),
+            comment: default_prompt_comment(),
+            status_comment: default_prompt_status_comment(),
+        }
+    }
+}
+
+impl Default for CommentsConfig {
+    fn default() -> Self {
+        Self {
+            feed_to_session: default_feed_to_session(),
+            allowed_users: Vec::new(),
+            status_comment: default_status_comment(),
+        }
+    }
+}
+
+impl CommentsConfig {
+    /// Whether comments by `author` may be delivered to a session
+    pub fn is_allowed(&self, author: &str, authenticated_user: &str) -> bool {
+        author.eq_ignore_ascii_case(authenticated_user)
+            || self
+                .allowed_users
+                .iter()
+                .any(|user| user.eq_ignore_ascii_case(author))
+    }
+}
+
 impl Default for BranchConfig {
     fn default() -> Self {
         Self {
@@ -156,6 +584,126 @@ impl Default for BranchConfig {
     }
 }
 
+fn default_agent() -> String {
+    CLAUDE_AGENT.to_string()
+}
+
+impl Default for AgentSelection {
+    fn default() -> Self {
+        Self {
+            default: default_agent(),
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+impl AgentSelection {
+    /// The profile for an issue with these labels: the first label that
+    /// picks one, or the default
+    pub fn select(&self, labels: &[String]) -> &str {
+        labels
+            .iter()
+            .find_map(|label| self.labels.get(label))
+            .unwrap_or(&self.default)
+    }
+
+    /// Every profile this selection can pick
+    fn names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.default).chain(self.labels.values())
+    }
+}
+
+impl ClaudeConfig {
+    /// The built-in `claude` agent profile
+    pub fn profile(&self) -> AgentProfile {
+        match self.mode {
+            AgentMode::Interactive => {
+                // --permission-mode must come before other flags
+                let mut args = vec!["--permission-mode".to_string(), "plan".to_string()];
+                args.extend(self.args.iter().cloned());
+                AgentProfile {
+                    command: self.command.clone(),
+                    args,
+                    mode: AgentMode::Interactive,
+                    prompt: PromptStyle::AtFile,
+                    state: Some(StateSource::Hooks),
+                    setup: vec![SetupStep::ClaudeHooks],
+                    files: BTreeMap::new(),
+                    accept_trust_prompt: true,
+                    resume_args: Vec::new(),
+                    continue_args: Vec::new(),
+                }
+            }
+            AgentMode::Headless => {
+                let mut args = self.args.clone();
+                args.extend(
+                    ["-p", "--output-format", "stream-json", "--verbose"]
+                        .iter()
+                        .map(|s| s.to_string()),
+                );
+                AgentProfile {
+                    command: self.command.clone(),
+                    args,
+                    mode: AgentMode::Headless,
+                    prompt: PromptStyle::Stdin,
+                    state: Some(StateSource::StreamJson),
+                    // Followed through its output, so only the slash commands
+                    setup: vec![SetupStep::ClaudeCommands],
+                    files: BTreeMap::new(),
+                    accept_trust_prompt: false,
+                    resume_args: vec!["--resume".to_string(), "{session_id}".to_string()],
+                    continue_args: vec!["--continue".to_string()],
+                }
+            }
+        }
+    }
+}
+
+impl AgentProfile {
+    /// What reports the issue's state, with the default for the mode filled in
+    pub fn state_source(&self) -> StateSource {
+        self.state.unwrap_or(match self.mode {
+            AgentMode::Interactive => StateSource::Manual,
+            AgentMode::Headless => StateSource::Exit,
+        })
+    }
+
+    /// Check that the settings fit together
+    fn validate(&self, name: &str) -> Result<()> {
+        anyhow::ensure!(
+            !self.command.is_empty(),
+            "agents.{}.command must not be empty",
+            name
+        );
+        let state = self.state_source();
+        let headless_state = matches!(state, StateSource::StreamJson | StateSource::Exit);
+        anyhow::ensure!(
+            headless_state == (self.mode == AgentMode::Headless),
+            "agents.{}.state = {:?} doesn't work with mode = {:?}",
+            name,
+            state,
+            self.mode
+        );
+        anyhow::ensure!(
+            self.prompt != PromptStyle::Stdin || self.mode == AgentMode::Headless,
+            "agents.{}.prompt = \"stdin\" needs mode = \"headless\"",
+            name
+        );
+        for path in self.files.keys() {
+            anyhow::ensure!(
+                path.is_relative()
+                    && path
+                        .components()
+                        .all(|c| matches!(c, std::path::Component::Normal(_))),
+                "agents.{}.files must name paths inside the worktree, got '{}'",
+                name,
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct ProvisionConfig {
     /// Shell commands to run after window creation, before Claude starts.
@@ -164,6 +712,131 @@ pub struct ProvisionConfig {
     pub on_provision: Vec<String>,
 }
 
+/// A rule used to rank ready issues before they are provisioned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RankRule {
+    /// Issues with a higher `priority_labels` entry go first
+    Priority,
+    /// Issues whose milestone is due soonest go first (no milestone goes last)
+    Milestone,
+    /// Issues opened by `preferred_authors` go first, in listed order
+    Author,
+    /// Older issues go first
+    Age,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SchedulingConfig {
+    /// Rules applied in order; later rules only break ties
+    #[serde(default = "default_scheduling_order")]
+    pub order: Vec<RankRule>,
+    /// Priority labels, highest priority first
+    #[serde(default = "default_priority_labels")]
+    pub priority_labels: Vec<String>,
+    /// Authors whose issues are picked first, highest priority first
+    #[serde(default)]
+    pub preferred_authors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookConfig {
+    /// Address for the GitHub webhook listener (e.g. "127.0.0.1:8787").
+    /// Webhooks are disabled if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    /// Environment variable holding the webhook secret used to check X-Hub-Signature-256
+    #[serde(default = "default_webhook_secret_env")]
+    pub secret_env: String,
+    /// Poll interval used as a fallback while the webhook listener is running
+    #[serde(default = "default_fallback_poll_interval_secs")]
+    pub fallback_poll_interval_secs: u64,
+}
+
+fn default_webhook_secret_env() -> String {
+    "PLEB_WEBHOOK_SECRET".to_string()
+}
+
+fn default_fallback_poll_interval_secs() -> u64 {
+    300
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            secret_env: default_webhook_secret_env(),
+            fallback_poll_interval_secs: default_fallback_poll_interval_secs(),
+        }
+    }
+}
+
+fn default_lima_template() -> String {
+    "template://default".to_string()
+}
+
+fn default_sandbox_instance_prefix() -> String {
+    "pleb".to_string()
+}
+
+fn default_container_network() -> bool {
+    true
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            backend: SandboxBackend::default(),
+            instance_prefix: default_sandbox_instance_prefix(),
+            env: Vec::new(),
+            lima: LimaConfig::default(),
+            container: ContainerConfig::default(),
+        }
+    }
+}
+
+impl Default for LimaConfig {
+    fn default() -> Self {
+        Self {
+            template: default_lima_template(),
+            pool_size: None,
+        }
+    }
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            image: None,
+            network: default_container_network(),
+            cpus: None,
+            memory: None,
+        }
+    }
+}
+
+fn default_scheduling_order() -> Vec<RankRule> {
+    vec![RankRule::Priority, RankRule::Milestone, RankRule::Age]
+}
+
+fn default_priority_labels() -> Vec<String> {
+    vec![
+        "priority:high".to_string(),
+        "priority:medium".to_string(),
+        "priority:low".to_string(),
+    ]
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            order: default_scheduling_order(),
+            priority_labels: default_priority_labels(),
+            preferred_authors: Vec::new(),
+        }
+    }
+}
+
 /// Describes where a config file was found
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigLocation {
@@ -200,6 +873,115 @@ impl Config {
         self.paths.repo_dir = resolve(&self.paths.repo_dir);
         self.paths.worktree_base = resolve(&self.paths.worktree_base);
         self.prompts.dir = resolve(&self.prompts.dir);
+
+        for profile in self.agents.values_mut() {
+            for source in profile.files.values_mut() {
+                *source = resolve(source);
+            }
+        }
+
+        for entry in &mut self.repos {
+            if let Some(paths) = &mut entry.paths {
+                paths.repo_dir = resolve(&paths.repo_dir);
+                paths.worktree_base = resolve(&paths.worktree_base);
+            }
+            if let Some(prompts) = &mut entry.prompts {
+                prompts.dir = resolve(&prompts.dir);
+            }
+        }
+    }
+
+    /// All repositories watched by this config.
+    /// The top-level [github] repo comes first (if set), followed by [[repos]] entries.
+    pub fn repos(&self) -> Vec<RepoConfig> {
+        let mut repos = Vec::new();
+
+        if !self.github.owner.is_empty() || !self.github.repo.is_empty() {
+            repos.push(RepoConfig {
+                github: self.github.clone(),
+                labels: self.labels.clone(),
+                paths: self.paths.clone(),
+                prompts: self.prompts.clone(),
+                branch: self.branch.clone(),
+                provision: self.provision.clone(),
+                agent: self.agent.clone(),
+            });
+        }
+
+        for entry in &self.repos {
+            repos.push(RepoConfig {
+                github: GithubConfig {
+                    owner: entry.owner.clone(),
+                    repo: entry.repo.clone(),
+                    token_env: self.github.token_env.clone(),
+                    rate_limit_reserve_percent: self.github.rate_limit_reserve_percent,
+                    api_url: self.github.api_url.clone(),
+                },
+                labels: entry.labels.clone().unwrap_or_else(|| self.labels.clone()),
+                paths: entry.paths.clone().unwrap_or_else(|| self.paths.clone()),
+                prompts: entry.prompts.clone().unwrap_or_else(|| self.prompts.clone()),
+                branch: entry.branch.clone().unwrap_or_else(|| self.branch.clone()),
+                provision: entry.provision.clone().unwrap_or_else(|| self.provision.clone()),
+                agent: entry.agent.clone().unwrap_or_else(|| self.agent.clone()),
+            });
+        }
+
+        repos
+    }
+
+    /// Look up an agent profile by name. `claude` is built from `[claude]`
+    /// unless an `[agents.claude]` section replaces it.
+    pub fn agent(&self, name: &str) -> Result<Agent> {
+        let profile = match self.agents.get(name) {
+            Some(profile) => profile.clone(),
+            None if name == CLAUDE_AGENT => self.claude.profile(),
+            None => anyhow::bail!("Agent '{}' is not configured (add an [agents.{}] section)", name, name),
+        };
+        Ok(Agent::new(name, profile))
+    }
+
+    /// Names of the profiles issues can be given, across all repositories
+    pub fn selectable_agents(&self) -> BTreeSet<String> {
+        self.repos()
+            .iter()
+            .flat_map(|repo| repo.agent.names().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// Whether any issue could get an interactive agent, and so a window
+    pub fn uses_interactive_agents(&self) -> bool {
+        self.selectable_agents()
+            .iter()
+            .any(|name| self.agent(name).is_ok_and(|agent| !agent.is_headless()))
+    }
+
+    /// Pick the repository a CLI command applies to.
+    ///
+    /// `selector` may be "owner/repo" or just "repo". Without a selector the
+    /// only configured repo is used, or the repo whose worktree base or clone
+    /// contains the current directory.
+    pub fn select_repo(&self, selector: Option<&str>) -> Result<RepoConfig> {
+        let repos = self.repos();
+
+        if let Some(selector) = selector {
+            return repos
+                .into_iter()
+                .find(|r| r.full_name() == selector || r.github.repo == selector)
+                .with_context(|| format!("Repository '{}' is not configured", selector));
+        }
+
+        if repos.len() == 1 {
+            return Ok(repos.into_iter().next().unwrap());
+        }
+
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        repos
+            .into_iter()
+            .filter(|r| {
+                path_within(&cwd, &r.paths.worktree_base) || path_within(&cwd, &r.paths.repo_dir)
+            })
+            .max_by_key(|r| r.paths.worktree_base.components().count())
+            .context("Multiple repositories are configured; pass --repo owner/repo")
     }
 
     /// Find and load configuration, searching up to 2 parent directories.
@@ -252,10 +1034,15 @@ impl Config {
         )
     }
 
-    /// Get the daemon directory for this repo: ~/.pleb/{owner}-{repo}/
+    /// Get the daemon directory: ~/.pleb/{owner}-{repo}/ for the [github] repo,
+    /// or ~/.pleb/{tmux.session_name}/ when every repo is listed in [[repos]]
     pub fn daemon_dir(&self) -> Result<PathBuf> {
         let home = dirs::home_dir().context("Failed to determine home directory")?;
-        let dir_name = format!("{}-{}", self.github.owner, self.github.repo);
+        let dir_name = if self.github.owner.is_empty() && self.github.repo.is_empty() {
+            self.tmux.session_name.clone()
+        } else {
+            format!("{}-{}", self.github.owner, self.github.repo)
+        };
         Ok(home.join(".pleb").join(dir_name))
     }
 
@@ -277,20 +1064,27 @@ impl Config {
 
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
-        // Validate GitHub config
-        anyhow::ensure!(
-            !self.github.owner.is_empty(),
-            "github.owner must not be empty"
-        );
-        anyhow::ensure!(
-            !self.github.repo.is_empty(),
-            "github.repo must not be empty"
-        );
+        // Validate GitHub config: either [github] names a repo or [[repos]] lists some
+        if self.repos.is_empty() || !self.github.owner.is_empty() || !self.github.repo.is_empty() {
+            anyhow::ensure!(
+                !self.github.owner.is_empty(),
+                "github.owner must not be empty"
+            );
+            anyhow::ensure!(
+                !self.github.repo.is_empty(),
+                "github.repo must not be empty"
+            );
+        }
         anyhow::ensure!(
             !self.github.token_env.is_empty(),
             "github.token_env must not be empty"
         );
 
+        anyhow::ensure!(
+            self.github.rate_limit_reserve_percent < 100,
+            "github.rate_limit_reserve_percent must be less than 100"
+        );
+
         // Validate that the GitHub token environment variable exists and is non-empty
         let token = std::env::var(&self.github.token_env).ok();
         anyhow::ensure!(
@@ -301,6 +1095,152 @@ impl Config {
             self.github.token_env
         );
 
+        let repos = self.repos();
+        for (i, repo) in repos.iter().enumerate() {
+            for other in repos.iter().skip(i + 1) {
+                anyhow::ensure!(
+                    repo.full_name() != other.full_name(),
+                    "Repository {} is configured more than once",
+                    repo.full_name()
+                );
+                anyhow::ensure!(
+                    repo.paths.repo_dir != other.paths.repo_dir
+                        && repo.paths.worktree_base != other.paths.worktree_base,
+                    "Repositories {} and {} must use different paths.repo_dir and paths.worktree_base",
+                    repo.full_name(),
+                    other.full_name()
+                );
+            }
+            repo.validate()?;
+        }
+
+        // Validate watch config
+        anyhow::ensure!(
+            self.watch.poll_interval_secs > 0,
+            "watch.poll_interval_secs must be greater than 0"
+        );
+        anyhow::ensure!(
+            self.watch.max_concurrent_sessions != Some(0),
+            "watch.max_concurrent_sessions must be greater than 0 (omit it for no limit)"
+        );
+
+        if let Some(listen) = &self.metrics.listen {
+            let addr = listen.parse::<std::net::SocketAddr>().with_context(|| {
+                format!("metrics.listen must be an address like 127.0.0.1:9464, got '{}'", listen)
+            })?;
+            crate::dashboard::check_listen(addr, self.metrics.public, "set metrics.public = true")
+                .context("Invalid metrics.listen")?;
+        }
+
+        for (model, price) in &self.costs.prices {
+            anyhow::ensure!(
+                [price.input, price.output, price.cache_write, price.cache_read]
+                    .iter()
+                    .all(|p| p.is_finite() && *p >= 0.0),
+                "costs.prices.\"{}\" must not be negative",
+                model
+            );
+        }
+
+        // Validate agent profiles, and that every profile an issue can be given exists
+        for (name, profile) in &self.agents {
+            profile.validate(name)?;
+        }
+        for name in self.selectable_agents() {
+            let agent = self.agent(&name)?;
+            // Headless runs are children of the daemon, not commands typed into a window
+            anyhow::ensure!(
+                !agent.is_headless() || self.sandbox.backend == SandboxBackend::None,
+                "Agent '{}' runs headless, which can't be combined with a sandbox backend",
+                name
+            );
+        }
+
+        // Validate sandbox config (Lima instance names are lowercase letters, digits and '-')
+        if self.sandbox.backend != SandboxBackend::None {
+            let prefix = &self.sandbox.instance_prefix;
+            anyhow::ensure!(
+                !prefix.is_empty()
+                    && prefix.starts_with(|c: char| c.is_ascii_lowercase())
+                    && prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'),
+                "sandbox.instance_prefix must start with a lowercase letter and contain \
+                 only lowercase letters, digits and '-', got '{}'",
+                prefix
+            );
+            for name in &self.sandbox.env {
+                anyhow::ensure!(
+                    !name.is_empty()
+                        && !name.starts_with(|c: char| c.is_ascii_digit())
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                    "sandbox.env must list environment variable names, got '{}'",
+                    name
+                );
+            }
+        }
+        if self.sandbox.backend == SandboxBackend::Lima {
+            anyhow::ensure!(
+                self.sandbox.lima.pool_size != Some(0),
+                "sandbox.lima.pool_size must be greater than 0 (omit it for one instance per issue)"
+            );
+        }
+        if self.sandbox.backend.is_container() {
+            let container = &self.sandbox.container;
+            anyhow::ensure!(
+                container.image.as_ref().is_some_and(|image| !image.is_empty()),
+                "sandbox.container.image must be set for the {:?} backend",
+                self.sandbox.backend
+            );
+            anyhow::ensure!(
+                container.cpus.is_none_or(|cpus| cpus > 0.0),
+                "sandbox.container.cpus must be greater than 0"
+            );
+            anyhow::ensure!(
+                container.memory.as_ref().is_none_or(|memory| !memory.is_empty()),
+                "sandbox.container.memory must not be empty (omit it for no limit)"
+            );
+        }
+
+        // Validate webhook config (only when the listener is enabled)
+        if let Some(listen) = &self.webhook.listen {
+            listen.parse::<std::net::SocketAddr>().with_context(|| {
+                format!("webhook.listen must be an address like 127.0.0.1:8787, got '{}'", listen)
+            })?;
+            let secret = std::env::var(&self.webhook.secret_env).ok();
+            anyhow::ensure!(
+                secret.as_ref().is_some_and(|s| !s.is_empty()),
+                "Webhook secret not found or empty in environment variable '{}'. \
+                 Set it to the secret configured for the GitHub webhook.",
+                self.webhook.secret_env
+            );
+            anyhow::ensure!(
+                self.webhook.fallback_poll_interval_secs > 0,
+                "webhook.fallback_poll_interval_secs must be greater than 0"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns true if `path` is inside `base` (comparing canonical paths where possible)
+pub fn path_within(path: &Path, base: &Path) -> bool {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let base = base.canonicalize().unwrap_or_else(|_| base.to_path_buf());
+    path.starts_with(base)
+}
+
+impl RepoConfig {
+    /// Validate the per-repository settings
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(
+            !self.github.owner.is_empty(),
+            "repos.owner must not be empty"
+        );
+        anyhow::ensure!(
+            !self.github.repo.is_empty(),
+            "repos.repo must not be empty"
+        );
+
         // Validate labels don't conflict
         let labels = [
             &self.labels.ready,
@@ -356,12 +1296,6 @@ impl Config {
             new_issue_path.display()
         );
 
-        // Validate watch config
-        anyhow::ensure!(
-            self.watch.poll_interval_secs > 0,
-            "watch.poll_interval_secs must be greater than 0"
-        );
-
         Ok(())
     }
 }
@@ -401,6 +1335,7 @@ finished = "custom:finished"
 [claude]
 command = "/usr/local/bin/claude"
 args = ["--verbose", "--no-cache"]
+mode = "headless"
 
 [paths]
 repo_dir = "/custom/repo"
@@ -412,12 +1347,19 @@ new_issue = "custom_new.md"
 
 [watch]
 poll_interval_secs = 30
+max_concurrent_sessions = 4
 
 [tmux]
 session_name = "custom-session"
+multiplexer = "zellij"
 
 [branch]
 suffix = "custom-suffix"
+
+[scheduling]
+order = ["author", "priority"]
+priority_labels = ["p0", "p1"]
+preferred_authors = ["alice"]
 "#;
 
     // ===================
@@ -443,8 +1385,14 @@ suffix = "custom-suffix"
         assert_eq!(config.claude.args, vec!["--verbose", "--no-cache"]);
         assert_eq!(config.paths.repo_dir, PathBuf::from("/custom/repo"));
         assert_eq!(config.watch.poll_interval_secs, 30);
+        assert_eq!(config.watch.max_concurrent_sessions, Some(4));
         assert_eq!(config.tmux.session_name, "custom-session");
+        assert_eq!(config.tmux.multiplexer, Multiplexer::Zellij);
+        assert_eq!(config.claude.mode, AgentMode::Headless);
         assert_eq!(config.branch.suffix, "custom-suffix");
+        assert_eq!(config.scheduling.order, vec![RankRule::Author, RankRule::Priority]);
+        assert_eq!(config.scheduling.priority_labels, vec!["p0", "p1"]);
+        assert_eq!(config.scheduling.preferred_authors, vec!["alice"]);
     }
 
     #[test]
@@ -491,7 +1439,7 @@ finished = "custom:finished"
 
     #[test]
     fn test_parse_missing_required_field() {
-        // Has [github] but missing owner
+        // Has [github] but missing owner (owner may only be omitted when [[repos]] is used)
         let missing_owner = r#"
 [github]
 repo = "testrepo"
@@ -503,8 +1451,9 @@ repo = "testrepo"
 [watch]
 [tmux]
 "#;
-        let result = Config::from_str(missing_owner);
+        let result = Config::from_str(missing_owner).and_then(|c| c.validate());
         assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("github.owner"));
     }
 
     // ===================
@@ -517,6 +1466,7 @@ repo = "testrepo"
 
         // GitHub defaults
         assert_eq!(config.github.token_env, "GITHUB_TOKEN");
+        assert_eq!(config.github.rate_limit_reserve_percent, 10);
 
         // Label defaults
         assert_eq!(config.labels.ready, "pleb:ready");
@@ -537,18 +1487,36 @@ repo = "testrepo"
         // Prompts defaults
         assert_eq!(config.prompts.dir, PathBuf::from("./prompts"));
         assert_eq!(config.prompts.new_issue, "new_issue.md");
+        assert_eq!(config.prompts.comment, "comment.md");
+
+        // Comment defaults
+        assert!(config.comments.feed_to_session);
+        assert!(config.comments.allowed_users.is_empty());
+        assert!(config.comments.status_comment);
+        assert_eq!(config.prompts.status_comment, "status_comment.md");
 
         // Watch defaults
         assert_eq!(config.watch.poll_interval_secs, 5);
+        assert_eq!(config.watch.max_concurrent_sessions, None);
 
         // Tmux defaults
         assert_eq!(config.tmux.session_name, "pleb");
+        assert_eq!(config.tmux.multiplexer, Multiplexer::Tmux);
+        assert_eq!(config.claude.mode, AgentMode::Interactive);
 
         // Branch defaults
         assert_eq!(config.branch.suffix, "pleb");
 
         // Provision defaults
         assert!(config.provision.on_provision.is_empty());
+
+        // Scheduling defaults
+        assert_eq!(
+            config.scheduling.order,
+            vec![RankRule::Priority, RankRule::Milestone, RankRule::Age]
+        );
+        assert_eq!(config.scheduling.priority_labels[0], "priority:high");
+        assert!(config.scheduling.preferred_authors.is_empty());
     }
 
     #[test]
@@ -693,6 +1661,34 @@ poll_interval_secs = 0
             .contains("poll_interval_secs"));
     }
 
+    #[test]
+    fn test_validate_zero_max_concurrent_sessions() {
+        // Set token so we reach the watch validation
+        std::env::set_var("GITHUB_TOKEN", "test-token");
+
+        let toml = r#"
+[github]
+owner = "testowner"
+repo = "testrepo"
+
+[labels]
+[claude]
+[paths]
+[prompts]
+[watch]
+max_concurrent_sessions = 0
+
+[tmux]
+"#;
+        let config = Config::from_str(toml).expect("Should parse");
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("max_concurrent_sessions"));
+    }
+
     #[test]
     fn test_validate_missing_token_env_var() {
         // Use a unique env var name that definitely doesn't exist
@@ -748,6 +1744,323 @@ token_env = "PLEB_TEST_NONEXISTENT_TOKEN_VAR"
         assert!(pid_file.to_string_lossy().ends_with("pleb.pid"));
     }
 
+    #[test]
+    fn test_state_file_construction() {
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        let repo = &config.repos()[0];
+        let state_file = repo.state_file().expect("Should get state file");
+
+        // The [github] repo keeps its state next to the daemon files
+        assert_eq!(state_file, config.daemon_dir().unwrap().join("state.json"));
+    }
+
+    #[test]
+    fn test_webhook_config() {
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        assert_eq!(config.webhook.listen, None);
+        assert_eq!(config.webhook.secret_env, "PLEB_WEBHOOK_SECRET");
+        assert_eq!(config.webhook.fallback_poll_interval_secs, 300);
+
+        std::env::set_var("GITHUB_TOKEN", "test-token");
+        std::env::set_var("TEST_WEBHOOK_SECRET", "s3cret");
+
+        let toml = format!(
+            "{}\n[webhook]\nlisten = \"127.0.0.1:8787\"\nsecret_env = \"TEST_WEBHOOK_SECRET\"\n",
+            MINIMAL_CONFIG
+        );
+        let config = Config::from_str(&toml).expect("Should parse");
+        assert_eq!(config.webhook.listen.as_deref(), Some("127.0.0.1:8787"));
+        config.validate().expect("Webhook config should be valid");
+
+        // The listener needs a secret to check signatures
+        let toml = toml.replace("TEST_WEBHOOK_SECRET", "TEST_WEBHOOK_SECRET_UNSET");
+        let config = Config::from_str(&toml).expect("Should parse");
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("TEST_WEBHOOK_SECRET_UNSET"));
+    }
+
+    #[test]
+    fn test_sandbox_config() {
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        assert_eq!(config.sandbox.backend, SandboxBackend::None);
+        assert_eq!(config.sandbox.lima.template, "template://default");
+        assert_eq!(config.sandbox.lima.pool_size, None);
+
+        let toml = format!(
+            "{}\n[sandbox]\nbackend = \"lima\"\n\n[sandbox.lima]\ntemplate = \"template://ubuntu\"\npool_size = 2\n",
+            MINIMAL_CONFIG
+        );
+        let config = Config::from_str(&toml).expect("Should parse");
+        assert_eq!(config.sandbox.backend, SandboxBackend::Lima);
+        assert_eq!(config.sandbox.lima.template, "template://ubuntu");
+        assert_eq!(config.sandbox.instance_prefix, "pleb");
+        assert_eq!(config.sandbox.lima.pool_size, Some(2));
+
+        let toml = format!(
+            "{}\n[sandbox]\nbackend = \"podman\"\nenv = [\"ANTHROPIC_API_KEY\"]\n\n\
+             [sandbox.container]\nimage = \"localhost/claude:latest\"\nnetwork = false\ncpus = 1.5\nmemory = \"4g\"\n",
+            MINIMAL_CONFIG
+        );
+        let config = Config::from_str(&toml).expect("Should parse");
+        assert_eq!(config.sandbox.backend, SandboxBackend::Podman);
+        assert!(config.sandbox.backend.is_container());
+        assert_eq!(config.sandbox.env, vec!["ANTHROPIC_API_KEY"]);
+        assert_eq!(config.sandbox.container.image.as_deref(), Some("localhost/claude:latest"));
+        assert!(!config.sandbox.container.network);
+        assert_eq!(config.sandbox.container.cpus, Some(1.5));
+        assert_eq!(config.sandbox.container.memory.as_deref(), Some("4g"));
+    }
+
+    #[test]
+    fn test_comments_allowed_users() {
+        let toml = format!(
+            "{}\n[comments]\nallowed_users = [\"Alice\", \"bob\"]\n",
+            MINIMAL_CONFIG
+        );
+        let config = Config::from_str(&toml).expect("Should parse");
+
+        assert!(config.comments.is_allowed("alice", "pleb-bot"));
+        assert!(config.comments.is_allowed("BOB", "pleb-bot"));
+        assert!(config.comments.is_allowed("pleb-bot", "pleb-bot"));
+        assert!(!config.comments.is_allowed("mallory", "pleb-bot"));
+    }
+
+    #[test]
+    fn test_metrics_listen() {
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        assert!(config.metrics.listen.is_none());
+
+        let toml = format!("{}\n[metrics]\nlisten = \"127.0.0.1:9464\"\n", MINIMAL_CONFIG);
+        let config = Config::from_str(&toml).expect("Should parse");
+        assert_eq!(config.metrics.listen.as_deref(), Some("127.0.0.1:9464"));
+
+        // Off loopback only when explicitly allowed
+        std::env::set_var("GITHUB_TOKEN", "test-token");
+        let toml = format!("{}\n[metrics]\nlisten = \"0.0.0.0:9464\"\n", MINIMAL_CONFIG);
+        let err = Config::from_str(&toml).unwrap().validate().unwrap_err();
+        assert!(format!("{:#}", err).contains("set metrics.public = true"));
+        let toml = format!("{}\n[metrics]\nlisten = \"0.0.0.0:9464\"\npublic = true\n", MINIMAL_CONFIG);
+        let config = Config::from_str(&toml).expect("Should parse");
+        assert!(config.metrics.public);
+        config.validate().expect("Should validate");
+    }
+
+    #[test]
+    fn test_costs_prices() {
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        assert!(config.costs.prices.is_empty());
+        assert!(!config.costs.in_status_comment);
+
+        let toml = format!(
+            "{}\n[costs]\nin_status_comment = true\n\
+             [costs.prices.claude-sonnet-4]\ninput = 3.0\noutput = 15.0\n\
+             [costs.prices.claude-sonnet-4-5-fast]\ninput = 6.0\n",
+            MINIMAL_CONFIG
+        );
+        let config = Config::from_str(&toml).expect("Should parse");
+        assert!(config.costs.in_status_comment);
+        let price = |model| config.costs.price(model).map(|p| p.input);
+        assert_eq!(price("claude-sonnet-4-5-20250929"), Some(3.0));
+        assert_eq!(price("claude-sonnet-4-5-fast-1"), Some(6.0));
+        assert_eq!(price("claude-opus-4-1"), None);
+        assert_eq!(config.costs.prices["claude-sonnet-4"].cache_read, 0.0);
+    }
+
+    #[test]
+    fn test_agent_profiles() {
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        let repo = &config.repos()[0];
+        assert_eq!(repo.agent.select(&["bug".to_string()]), CLAUDE_AGENT);
+        let claude = config.agent(CLAUDE_AGENT).unwrap();
+        assert!(!claude.is_headless());
+        assert_eq!(claude.profile.prompt, PromptStyle::AtFile);
+        assert_eq!(claude.profile.state_source(), StateSource::Hooks);
+        assert_eq!(claude.profile.args[..2], ["--permission-mode", "plan"]);
+        assert!(config.agent("aider").is_err());
+
+        let toml = format!(
+            "{}\n[agent]\ndefault = \"aider\"\n\n[agent.labels]\n\"agent:stub\" = \"stub\"\n\n\
+             [agents.aider]\ncommand = \"aider\"\nargs = [\"--message-file\"]\n\
+             files = {{ \".aider.conf.yml\" = \"agents/aider.yml\" }}\n\n\
+             [agents.stub]\ncommand = \"./stub.sh\"\nmode = \"headless\"\nprompt = \"stdin\"\n",
+            MINIMAL_CONFIG
+        );
+        let mut config = Config::from_str(&toml).expect("Should parse");
+        config.resolve_paths_relative_to(Path::new("/etc/pleb"));
+        let repo = &config.repos()[0];
+        assert_eq!(repo.agent.select(&[]), "aider");
+        assert_eq!(repo.agent.select(&["bug".to_string(), "agent:stub".to_string()]), "stub");
+
+        let aider = config.agent("aider").unwrap();
+        assert_eq!(aider.profile.prompt, PromptStyle::Path);
+        assert_eq!(aider.profile.state_source(), StateSource::Manual);
+        assert_eq!(
+            aider.profile.files[Path::new(".aider.conf.yml")],
+            PathBuf::from("/etc/pleb/agents/aider.yml")
+        );
+        let stub = config.agent("stub").unwrap();
+        assert!(stub.is_headless());
+        assert_eq!(stub.profile.state_source(), StateSource::Exit);
+        assert!(config.uses_interactive_agents());
+    }
+
+    #[test]
+    fn test_validate_agent_profiles() {
+        std::env::set_var("GITHUB_TOKEN", "test-token");
+        let invalid = |extra: &str| {
+            let config = Config::from_str(&format!("{}\n{}", MINIMAL_CONFIG, extra)).expect("Should parse");
+            config.validate().unwrap_err().to_string()
+        };
+
+        assert!(invalid("[agent]\ndefault = \"missing\"\n").contains("Agent 'missing' is not configured"));
+        assert!(invalid("[agents.x]\ncommand = \"x\"\nstate = \"hooks\"\nmode = \"headless\"\n")
+            .contains("agents.x.state"));
+        assert!(invalid("[agents.x]\ncommand = \"x\"\nprompt = \"stdin\"\n").contains("agents.x.prompt"));
+        assert!(invalid("[agents.x]\ncommand = \"x\"\nfiles = { \"../x\" = \"x\" }\n").contains("agents.x.files"));
+        assert!(invalid("[agents.claude]\ncommand = \"claude\"\nmode = \"headless\"\n[sandbox]\nbackend = \"lima\"\n")
+            .contains("can't be combined with a sandbox backend"));
+
+        let config = Config::from_str(&format!(
+            "{}\n[agent.labels]\nquick = \"stub\"\n\n[agents.stub]\ncommand = \"./stub.sh\"\nmode = \"headless\"\n",
+            MINIMAL_CONFIG
+        ))
+        .expect("Should parse");
+        config.validate().unwrap();
+    }
+
+    // ===================
+    // Multiple Repos Tests
+    // ===================
+
+    const MULTI_REPO_CONFIG: &str = r#"
+[github]
+
+[labels]
+ready = "shared:ready"
+
+[claude]
+[watch]
+
+[tmux]
+session_name = "fleet"
+
+[[repos]]
+owner = "acme"
+repo = "api"
+
+[repos.paths]
+repo_dir = "./api"
+worktree_base = "./api-worktrees"
+
+[[repos]]
+owner = "acme"
+repo = "web"
+
+[repos.paths]
+repo_dir = "./web"
+worktree_base = "./web-worktrees"
+
+[repos.labels]
+ready = "web:ready"
+
+[repos.branch]
+suffix = "web"
+"#;
+
+    #[test]
+    fn test_parse_multiple_repos() {
+        let config = Config::from_str(MULTI_REPO_CONFIG).expect("Should parse");
+        let repos = config.repos();
+
+        assert_eq!(repos.len(), 2);
+        assert_eq!(repos[0].full_name(), "acme/api");
+        assert_eq!(repos[1].full_name(), "acme/web");
+
+        // Omitted sections inherit the top-level ones
+        assert_eq!(repos[0].labels.ready, "shared:ready");
+        assert_eq!(repos[0].branch.suffix, "pleb");
+        assert_eq!(repos[0].github.token_env, "GITHUB_TOKEN");
+
+        // Per-repo sections override them
+        assert_eq!(repos[1].labels.ready, "web:ready");
+        assert_eq!(repos[1].labels.working, "pleb:working");
+        assert_eq!(repos[1].branch.suffix, "web");
+        assert_eq!(repos[1].paths.worktree_base, PathBuf::from("./web-worktrees"));
+    }
+
+    #[test]
+    fn test_github_repo_comes_first() {
+        let toml = MULTI_REPO_CONFIG.replacen(
+            "[github]\n",
+            "[github]\nowner = \"acme\"\nrepo = \"core\"\n",
+            1,
+        );
+        let config = Config::from_str(&toml).expect("Should parse");
+        let names: Vec<String> = config.repos().iter().map(|r| r.full_name()).collect();
+
+        assert_eq!(names, vec!["acme/core", "acme/api", "acme/web"]);
+        assert!(config.daemon_dir().unwrap().ends_with("acme-core"));
+    }
+
+    #[test]
+    fn test_multi_repo_daemon_dir_uses_session_name() {
+        let config = Config::from_str(MULTI_REPO_CONFIG).expect("Should parse");
+
+        assert!(config.daemon_dir().unwrap().ends_with(".pleb/fleet"));
+        assert!(config.repos()[1].state_dir().unwrap().ends_with(".pleb/acme-web"));
+    }
+
+    #[test]
+    fn test_validate_multi_repo() {
+        std::env::set_var("GITHUB_TOKEN", "test-token");
+
+        let config = Config::from_str(MULTI_REPO_CONFIG).expect("Should parse");
+        config.validate().expect("Multi-repo config should be valid");
+
+        // Sharing a worktree base would make issues ambiguous
+        let toml = MULTI_REPO_CONFIG.replace("./web-worktrees", "./api-worktrees");
+        let config = Config::from_str(&toml).expect("Should parse");
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("different paths"));
+
+        // The same repo twice
+        let toml = MULTI_REPO_CONFIG.replace("repo = \"web\"", "repo = \"api\"");
+        let config = Config::from_str(&toml).expect("Should parse");
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("more than once"));
+    }
+
+    #[test]
+    fn test_select_repo() {
+        let config = Config::from_str(MULTI_REPO_CONFIG).expect("Should parse");
+
+        assert_eq!(config.select_repo(Some("acme/web")).unwrap().full_name(), "acme/web");
+        assert_eq!(config.select_repo(Some("api")).unwrap().full_name(), "acme/api");
+        assert!(config.select_repo(Some("acme/unknown")).is_err());
+
+        // With a single repo no selector is needed
+        let config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
+        assert_eq!(config.select_repo(None).unwrap().full_name(), "testowner/testrepo");
+    }
+
+    #[test]
+    fn test_serialized_config_roundtrip() {
+        // Worktrees receive the loaded config serialized back to TOML
+        let mut config = Config::from_str(MULTI_REPO_CONFIG).expect("Should parse");
+        config.resolve_paths_relative_to(Path::new("/project"));
+
+        let toml_str = toml::to_string_pretty(&config).expect("Should serialize");
+        let reloaded = Config::from_str(&toml_str).expect("Should parse serialized config");
+        let repos = reloaded.repos();
+
+        assert_eq!(repos.len(), 2);
+        assert_eq!(repos[1].labels.ready, "web:ready");
+        assert_eq!(
+            repos[1].paths.worktree_base,
+            PathBuf::from("/project/./web-worktrees")
+        );
+    }
+
     #[test]
     fn test_resolve_paths_relative_to() {
         let mut config = Config::from_str(MINIMAL_CONFIG).expect("Should parse");
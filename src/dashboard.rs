@@ -0,0 +1,328 @@
+//! Read-only web dashboard for `pleb watch --http`.
+//!
+//! Lists the managed issues of every watched repository with their state, PR
+//! and worktree, plus a snapshot of each issue's tmux pane and the daemon log
+//! lines that mention it. Everything is read from the state files, tmux and the
+//! log file on each request, the same way `pleb status` does, so the dashboard
+//! never changes anything.
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use regex::Regex;
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::RepoConfig;
+use crate::github::IssueRef;
+use crate::state::{self, IssueTracker};
+use crate::session::SessionBackend;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Lines of pane scrollback included in a snapshot
+const PANE_LINES: u32 = 200;
+
+/// Log lines shown per issue
+const LOG_LINES: usize = 100;
+
+/// Only the end of the log file is searched for an issue's lines
+const LOG_SCAN_BYTES: u64 = 4 * 1024 * 1024;
+
+/// One managed issue as shown on the dashboard
+#[derive(Debug, Serialize)]
+pub struct IssueSummary {
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub state: String,
+    /// How long the issue has been in its current state, e.g. "1h 5m"
+    pub state_age: String,
+    pub pr_url: Option<String>,
+    pub branch_name: Option<String>,
+    pub worktree_path: Option<String>,
+}
+
+/// Summarize the issues tracked for one repository, lowest issue number first
+pub fn summarize(repo: &RepoConfig, tracker: &IssueTracker, now: u64) -> Vec<IssueSummary> {
+    let mut issues: Vec<_> = tracker
+        .all()
+        .into_iter()
+        .map(|tracked| IssueSummary {
+            owner: repo.github.owner.clone(),
+            repo: repo.github.repo.clone(),
+            number: tracked.issue_number,
+            title: tracked.title.clone(),
+            html_url: tracked.html_url.clone(),
+            state: tracked.state.name().to_string(),
+            state_age: state::format_duration(std::time::Duration::from_secs(
+                now.saturating_sub(tracked.entered_current_state_at()),
+            )),
+            pr_url: tracked.pr_url.clone(),
+            branch_name: tracked.branch_name.clone(),
+            worktree_path: tracked
+                .worktree_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+        })
+        .collect();
+    issues.sort_by_key(|issue| issue.number);
+    issues
+}
+
+/// Whether a log line mentions an issue ("acme/api#42", but not "acme/api#421")
+pub fn mentions_issue(line: &str, issue: &IssueRef) -> bool {
+    let needle = issue.to_string();
+    line.match_indices(&needle).any(|(start, _)| {
+        !line[start + needle.len()..]
+            .starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// The last `lines` lines of the log file that mention an issue, without color codes
+pub fn tail_issue_log(log_file: &std::path::Path, issue: &IssueRef, lines: usize) -> Result<Vec<String>> {
+    let mut file = std::fs::File::open(log_file)
+        .with_context(|| format!("Failed to open log file: {}", log_file.display()))?;
+    let len = file.metadata()?.len();
+    let offset = len.saturating_sub(LOG_SCAN_BYTES);
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read log file: {}", log_file.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    let ansi = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+    let mut matching: Vec<String> = text
+        .lines()
+        // The first line is probably cut off when reading from the middle of the file
+        .skip(if offset > 0 { 1 } else { 0 })
+        .map(|line| ansi.replace_all(line, "").into_owned())
+        .filter(|line| mentions_issue(line, issue))
+        .collect();
+
+    let skip = matching.len().saturating_sub(lines);
+    Ok(matching.split_off(skip))
+}
+
+struct DashboardState {
+    repos: Vec<RepoConfig>,
+    /// None when every agent runs headless
+    tmux: Option<Arc<dyn SessionBackend>>,
+    log_file: PathBuf,
+}
+
+impl DashboardState {
+    /// Look up a watched repository by owner and name
+    fn repo(&self, owner: &str, repo: &str) -> Option<&RepoConfig> {
+        self.repos
+            .iter()
+            .find(|r| r.github.owner == owner && r.github.repo == repo)
+    }
+}
+
+/// Check that an unauthenticated listener (the dashboard, or the metrics
+/// endpoint) only listens off loopback when explicitly allowed. `opt_in` says
+/// how to allow it, e.g. "pass --http-public".
+pub fn check_listen(listen: SocketAddr, public: bool, opt_in: &str) -> Result<()> {
+    if !listen.ip().is_loopback() && !public {
+        anyhow::bail!(
+            "Refusing to listen on non-loopback address {} without authentication; {} to allow it",
+            listen,
+            opt_in
+        );
+    }
+    Ok(())
+}
+
+/// HTTP listener serving the dashboard
+pub struct DashboardServer {
+    listen: SocketAddr,
+    state: Arc<DashboardState>,
+}
+
+impl DashboardServer {
+    /// Create a new dashboard server (doesn't start listening yet)
+    pub fn new(listen: SocketAddr, repos: Vec<RepoConfig>, tmux: Option<Arc<dyn SessionBackend>>, log_file: PathBuf) -> Self {
+        Self {
+            listen,
+            state: Arc::new(DashboardState {
+                repos,
+                tmux,
+                log_file,
+            }),
+        }
+    }
+
+    /// Start listening and return the bound address
+    pub async fn start(&self) -> Result<SocketAddr> {
+        let listener = tokio::net::TcpListener::bind(self.listen)
+            .await
+            .with_context(|| format!("Failed to bind dashboard listener to {}", self.listen))?;
+        let addr = listener
+            .local_addr()
+            .context("Failed to get dashboard listener address")?;
+
+        let app = Router::new()
+            .route("/", get(|| async { Html(PAGE) }))
+            .route("/api/issues", get(list_issues))
+            .route("/api/issues/:owner/:repo/:number/pane", get(pane))
+            .route("/api/issues/:owner/:repo/:number/log", get(log))
+            .with_state(self.state.clone());
+
+        tracing::info!("Dashboard on http://{}/", addr);
+        if !addr.ip().is_loopback() {
+            tracing::warn!(
+                "Dashboard is reachable from the network on {} with no authentication; \
+                 anyone who can connect sees agent panes and daemon logs",
+                addr
+            );
+        }
+
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("Dashboard listener stopped: {}", e);
+            }
+        });
+
+        Ok(addr)
+    }
+}
+
+/// Turn a failed request into a 500 with the error as plain text
+fn internal_error(e: anyhow::Error) -> Response {
+    tracing::debug!("Dashboard request failed: {:#}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+}
+
+async fn list_issues(State(state): State<Arc<DashboardState>>) -> Response {
+    let now = state::unix_now();
+    let mut issues = Vec::new();
+    for repo in &state.repos {
+        let tracker = match repo.state_file().and_then(|path| IssueTracker::load(&path)) {
+            Ok(tracker) => tracker,
+            Err(e) => return internal_error(e),
+        };
+        issues.extend(summarize(repo, &tracker, now));
+    }
+    Json(issues).into_response()
+}
+
+async fn pane(
+    State(state): State<Arc<DashboardState>>,
+    Path((owner, repo, number)): Path<(String, String, u64)>,
+) -> Response {
+    if state.repo(&owner, &repo).is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let issue = IssueRef::new(owner, repo, number);
+    let pane = match &state.tmux {
+        Some(tmux) => tmux.capture_pane(&issue, PANE_LINES).await,
+        None => Ok(None),
+    };
+    match pane {
+        Ok(Some(text)) => text.into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, format!("No tmux window for {}", issue)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn log(
+    State(state): State<Arc<DashboardState>>,
+    Path((owner, repo, number)): Path<(String, String, u64)>,
+) -> Response {
+    if state.repo(&owner, &repo).is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let issue = IssueRef::new(owner, repo, number);
+    if !state.log_file.exists() {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No log file at {} (is pleb running as a daemon?)", state.log_file.display()),
+        )
+            .into_response();
+    }
+
+    match tail_issue_log(&state.log_file, &issue, LOG_LINES) {
+        Ok(lines) => lines.join("\n").into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PlebState;
+
+    #[test]
+    fn test_mentions_issue() {
+        let issue = IssueRef::new("acme", "api", 42);
+        assert!(mentions_issue("INFO Processing issue acme/api#42: Add invoices", &issue));
+        assert!(mentions_issue("issue acme/api#421 and acme/api#42", &issue));
+        assert!(!mentions_issue("INFO Processing issue acme/api#421: Other", &issue));
+        assert!(!mentions_issue("INFO Processing issue acme/web#42: Other", &issue));
+    }
+
+    #[test]
+    fn test_check_listen() {
+        let check = |listen: &str, public| check_listen(listen.parse().unwrap(), public, "pass --http-public");
+        assert!(check("127.0.0.1:8080", false).is_ok());
+        assert!(check("[::1]:8080", false).is_ok());
+        let err = check("0.0.0.0:8080", false).unwrap_err();
+        assert!(err.to_string().ends_with("pass --http-public to allow it"));
+        assert!(check("0.0.0.0:8080", true).is_ok());
+    }
+
+    #[test]
+    fn test_tail_issue_log() {
+        let dir = std::env::temp_dir().join(format!("pleb-dashboard-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log_file = dir.join("pleb.log");
+        let lines: Vec<String> = (0..10)
+            .map(|i| format!("\x1b[32m INFO\x1b[0m step {} for acme/api#{}", i, 40 + i % 3))
+            .collect();
+        std::fs::write(&log_file, lines.join("\n")).unwrap();
+
+        let issue = IssueRef::new("acme", "api", 42);
+        let tail = tail_issue_log(&log_file, &issue, 2).unwrap();
+        assert_eq!(
+            tail,
+            vec![" INFO step 5 for acme/api#42", " INFO step 8 for acme/api#42"]
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_summarize() {
+        let config = crate::config::Config::from_str(
+            "[github]\nowner = \"acme\"\nrepo = \"api\"\n\
+             [labels]\n[claude]\n[paths]\n[prompts]\n[watch]\n[tmux]\n[branch]\n",
+        )
+        .unwrap();
+        let repo = &config.repos()[0];
+
+        let mut tracker = IssueTracker::new();
+        let tracked = tracker.track(7, PlebState::Waiting);
+        tracked.title = "Add invoices".to_string();
+        tracked.pr_url = Some("https://github.com/acme/api/pull/8".to_string());
+        let now = tracked.state_entered_at + 3900;
+        tracker.track(3, PlebState::Working);
+
+        let issues = summarize(repo, &tracker, now);
+        assert_eq!(issues.iter().map(|i| i.number).collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(issues[1].state, "waiting");
+        assert_eq!(issues[1].state_age, "1h 5m");
+        assert_eq!(issues[1].pr_url.as_deref(), Some("https://github.com/acme/api/pull/8"));
+    }
+}
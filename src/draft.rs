@@ -0,0 +1,219 @@
+//! Drafting new issues for `pleb new`.
+//!
+//! The description is written in `$EDITOR` (or piped on stdin). A draft's first
+//! line is the title and the rest is the body, like a commit message. Claude
+//! can be asked, headlessly, to propose a title and acceptance criteria, which
+//! are shown in the editor again before the issue is created.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config::ClaudeConfig;
+
+/// Everything from this line down is removed from a draft, like `git commit`'s scissors
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+const PROPOSAL_PROMPT: &str = "\
+You are helping write a GitHub issue for a coding agent to implement.
+Read the description below and propose a short imperative title (at most 72
+characters) and a list of concrete, testable acceptance criteria.
+
+Reply with only a JSON object and nothing else:
+{\"title\": \"...\", \"acceptance_criteria\": [\"...\", \"...\"]}
+
+Description:
+";
+
+/// A title and acceptance criteria suggested by Claude
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Proposal {
+    pub title: String,
+    #[serde(default)]
+    pub acceptance_criteria: Vec<String>,
+}
+
+/// Text shown under the scissors line in the editor
+fn instructions(hint: &str) -> String {
+    format!(
+        "\n\n{}\n# Do not modify or remove the line above.\n# Everything below it will be ignored.\n#\n# {}\n",
+        SCISSORS, hint
+    )
+}
+
+/// Drop the scissors line and everything after it
+pub fn strip_instructions(text: &str) -> &str {
+    match text.find(SCISSORS) {
+        Some(index) => &text[..index],
+        None => text,
+    }
+}
+
+/// Split a draft into its title (first non-empty line) and body.
+/// Returns None if the draft is empty.
+pub fn parse_draft(text: &str) -> Option<(String, String)> {
+    let text = strip_instructions(text).trim();
+    if text.is_empty() {
+        return None;
+    }
+    let (title, body) = text.split_once('\n').unwrap_or((text, ""));
+    // Accept a markdown heading as the title
+    let title = title.trim().trim_start_matches('#').trim();
+    Some((title.to_string(), body.trim().to_string()))
+}
+
+/// Compose a draft from a proposal and the original description
+pub fn compose(proposal: &Proposal, description: &str) -> String {
+    let mut draft = format!("{}\n\n{}\n", proposal.title.trim(), description.trim());
+    if !proposal.acceptance_criteria.is_empty() {
+        draft.push_str("\n## Acceptance criteria\n\n");
+        for criterion in &proposal.acceptance_criteria {
+            draft.push_str(&format!("- [ ] {}\n", criterion.trim()));
+        }
+    }
+    draft
+}
+
+/// Rewrite a draft with a proposal: the proposed title replaces the draft's
+/// first line, and the rest of the draft stays as the description
+pub fn revise(proposal: &Proposal, draft: &str) -> String {
+    let description = parse_draft(draft).map(|(_, body)| body).unwrap_or_default();
+    compose(proposal, &description)
+}
+
+/// Extract the proposal from Claude's reply, tolerating text around the JSON
+pub fn parse_proposal(reply: &str) -> Result<Proposal> {
+    let start = reply.find('{').context("Claude's reply contains no JSON object")?;
+    let end = reply.rfind('}').context("Claude's reply contains no JSON object")?;
+    let proposal: Proposal = serde_json::from_str(&reply[start..=end])
+        .with_context(|| format!("Failed to parse Claude's proposal: {}", reply.trim()))?;
+    if proposal.title.trim().is_empty() {
+        anyhow::bail!("Claude proposed an empty title");
+    }
+    Ok(proposal)
+}
+
+/// Open `$VISUAL` or `$EDITOR` (falling back to vi) on `initial` and return the edited text
+pub fn edit(initial: &str, hint: &str) -> Result<String> {
+    let path = std::env::temp_dir().join(format!("pleb-new-issue-{}.md", std::process::id()));
+    std::fs::write(&path, format!("{}{}", initial, instructions(hint)))
+        .with_context(|| format!("Failed to write draft file: {}", path.display()))?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Run through the shell so editors configured with arguments ("code --wait") work
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", editor));
+
+    let text = std::fs::read_to_string(&path);
+    std::fs::remove_file(&path).ok();
+
+    if !status?.success() {
+        anyhow::bail!("Editor '{}' exited with an error", editor);
+    }
+    let text = text.with_context(|| format!("Failed to read draft file: {}", path.display()))?;
+    Ok(strip_instructions(&text).to_string())
+}
+
+/// Ask a headless Claude run to propose a title and acceptance criteria
+pub async fn propose(config: &ClaudeConfig, description: &str) -> Result<Proposal> {
+    tracing::info!("Asking Claude to propose a title and acceptance criteria...");
+
+    let mut child = Command::new(&config.command)
+        .args(&config.args)
+        .arg("--print")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", config.command))?;
+
+    let mut stdin = child.stdin.take().context("Failed to open Claude's stdin")?;
+    stdin
+        .write_all(format!("{}{}", PROPOSAL_PROMPT, description.trim()).as_bytes())
+        .await
+        .context("Failed to send the description to Claude")?;
+    drop(stdin);
+
+    let output = child
+        .wait_with_output()
+        .await
+        .context("Failed to wait for Claude")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Claude exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    parse_proposal(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_draft() {
+        let text = format!(
+            "# Add invoice export\n\nUsers want CSV.\n\nMore detail.\n{}",
+            instructions("hint")
+        );
+        assert_eq!(
+            parse_draft(&text),
+            Some((
+                "Add invoice export".to_string(),
+                "Users want CSV.\n\nMore detail.".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_draft("\n\nFix login\n"),
+            Some(("Fix login".to_string(), String::new()))
+        );
+        assert_eq!(parse_draft(&instructions("hint")), None);
+        assert_eq!(parse_draft("  \n"), None);
+    }
+
+    #[test]
+    fn test_proposal() {
+        let reply = "Here you go:\n{\"title\": \"Export invoices as CSV\", \
+                     \"acceptance_criteria\": [\"A CSV download exists\", \"Totals match\"]}\n";
+        let proposal = parse_proposal(reply).unwrap();
+        assert_eq!(proposal.title, "Export invoices as CSV");
+
+        let draft = compose(&proposal, "Users want CSV.\n");
+        assert_eq!(
+            draft,
+            "Export invoices as CSV\n\nUsers want CSV.\n\n## Acceptance criteria\n\n\
+             - [ ] A CSV download exists\n- [ ] Totals match\n"
+        );
+        assert_eq!(
+            parse_draft(&draft).unwrap().0,
+            "Export invoices as CSV"
+        );
+
+        // Without --title the draft's first line was the user's title; it isn't kept in the body
+        let draft = revise(&proposal, "invoices csv\n\nUsers want CSV.\n");
+        assert_eq!(
+            parse_draft(&draft),
+            Some((
+                "Export invoices as CSV".to_string(),
+                "Users want CSV.\n\n## Acceptance criteria\n\n\
+                 - [ ] A CSV download exists\n- [ ] Totals match"
+                    .to_string()
+            ))
+        );
+
+        assert!(parse_proposal("I can't help with that").is_err());
+        assert!(parse_proposal("{\"title\": \"\"}").is_err());
+    }
+}
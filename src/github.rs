@@ -1,8 +1,19 @@
 use anyhow::{Context, Result};
+use http::header::{self, HeaderMap, HeaderValue};
+use http::StatusCode;
 use octocrab::Octocrab;
+use std::collections::HashMap;
+use std::sync::Mutex;
 
 use crate::config::{GithubConfig, LabelConfig};
+use crate::ratelimit::{self, RateLimit};
 use crate::state::PlebState;
+use crate::telemetry;
+
+/// Count a GitHub API request ("rest" or "graphql") for the metrics endpoint
+fn count_api_call(api: &'static str) {
+    metrics::counter!(telemetry::GITHUB_API_CALLS, "api" => api).increment(1);
+}
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -13,6 +24,36 @@ pub struct Issue {
     pub labels: Vec<String>,
     pub state: IssueState,
     pub html_url: String,
+    /// Login of the user who opened the issue
+    pub author: String,
+    /// Unix timestamp (seconds) when the issue was opened
+    pub created_at: u64,
+    /// Unix timestamp (seconds) of the issue milestone's due date, if any
+    pub milestone_due_on: Option<u64>,
+}
+
+/// Identifies an issue across all watched repositories
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IssueRef {
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+}
+
+impl IssueRef {
+    pub fn new(owner: impl Into<String>, repo: impl Into<String>, number: u64) -> Self {
+        Self {
+            owner: owner.into(),
+            repo: repo.into(),
+            number,
+        }
+    }
+}
+
+impl std::fmt::Display for IssueRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}#{}", self.owner, self.repo, self.number)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -21,6 +62,180 @@ pub enum IssueState {
     Closed,
 }
 
+/// A comment on an issue
+#[derive(Debug, Clone)]
+pub struct IssueComment {
+    pub id: u64,
+    /// Login of the comment author
+    pub author: String,
+    pub body: String,
+    pub html_url: String,
+    /// Unix timestamp
+    pub created_at: u64,
+}
+
+impl From<octocrab::models::issues::Comment> for IssueComment {
+    fn from(comment: octocrab::models::issues::Comment) -> Self {
+        IssueComment {
+            id: comment.id.into_inner(),
+            author: comment.user.login,
+            body: comment.body.unwrap_or_default(),
+            html_url: comment.html_url.to_string(),
+            created_at: comment.created_at.timestamp().max(0) as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullRequestState {
+    Open,
+    Closed,
+    Merged,
+}
+
+/// A pull request associated with an issue
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub url: String,
+    /// Name of the head branch
+    pub head_ref: String,
+    pub state: PullRequestState,
+    /// Unix timestamp (seconds) when the PR was opened
+    pub created_at: u64,
+}
+
+impl PullRequest {
+    /// Parse a GraphQL `PullRequest` node selected with
+    /// `{ number url headRefName state createdAt }`
+    fn from_graphql(node: &serde_json::Value) -> Option<Self> {
+        Some(PullRequest {
+            number: node["number"].as_u64()?,
+            url: node["url"].as_str()?.to_string(),
+            head_ref: node["headRefName"].as_str().unwrap_or_default().to_string(),
+            state: match node["state"].as_str()? {
+                "OPEN" => PullRequestState::Open,
+                "MERGED" => PullRequestState::Merged,
+                _ => PullRequestState::Closed,
+            },
+            created_at: node["createdAt"]
+                .as_str()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.timestamp().max(0) as u64)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Whether a branch follows pleb's `{issue_number}-...` naming for the given issue
+pub fn branch_matches_issue(head_ref: &str, issue_number: u64) -> bool {
+    head_ref
+        .strip_prefix(&issue_number.to_string())
+        .is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// Drop comments made before `since`. GitHub's `since` filters on the update
+/// time, so an older comment edited since comes back too.
+fn created_since(mut comments: Vec<IssueComment>, since: u64) -> Vec<IssueComment> {
+    comments.retain(|c| c.created_at >= since);
+    comments
+}
+
+/// GitHub API used unless `github.api_url` is set
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Comments fetched per page (GitHub's maximum)
+const COMMENTS_PER_PAGE: usize = 100;
+
+/// Issues looked up per merged-PR query, to keep each query's cost bounded
+const MERGED_PRS_BATCH: usize = 50;
+
+/// Fields `PullRequest::from_graphql` reads
+const PR_FIELDS: &str = "number url headRefName state createdAt";
+
+/// One query for the PRs of several issues (number and branch, if known):
+/// the PRs linked as closing each issue (aliased `i{number}`) and the PRs
+/// from its branch (aliased `b{number}`). Without a known branch, the PRs whose
+/// branch starts with the issue number are searched for instead, as
+/// `GitHubClient::find_pull_requests` does.
+fn merged_prs_query(repo: &str, issues: &[(u64, Option<&str>)]) -> String {
+    let mut fields = String::new();
+    for (number, branch) in issues {
+        fields.push_str(&format!(
+            "    i{0}: issue(number: {0}) {{ closedByPullRequestsReferences(first: 20, includeClosedPrs: true) {{ nodes {{ {1} }} }} }}\n",
+            number, PR_FIELDS
+        ));
+        match branch {
+            // A JSON string is also a valid GraphQL string literal
+            Some(branch) => fields.push_str(&format!(
+                "    b{}: pullRequests(headRefName: {}, first: 5) {{ nodes {{ {} }} }}\n",
+                number,
+                serde_json::Value::from(*branch),
+                PR_FIELDS
+            )),
+            // `head:` matches branches starting with the given word
+            None => fields.push_str(&format!(
+                "    b{0}: search(query: {1}, type: ISSUE, first: 20) {{ nodes {{ ... on PullRequest {{ {2} }} }} }}\n",
+                number,
+                serde_json::Value::from(format!("repo:{} is:pr head:{}-", repo, number)),
+                PR_FIELDS
+            )),
+        }
+    }
+    format!(
+        "query($owner: String!, $repo: String!) {{\n  repository(owner: $owner, name: $repo) {{\n{}  }}\n}}\n",
+        fields
+    )
+}
+
+/// For each issue that has PRs, whether one of them is merged, from the
+/// response to `merged_prs_query`
+fn merged_from_batch(data: &serde_json::Value, issues: &[(u64, Option<&str>)]) -> HashMap<u64, bool> {
+    prs_from_batch(data, issues, false)
+        .into_iter()
+        .map(|(number, prs)| (number, prs.iter().any(|pr| pr.state == PullRequestState::Merged)))
+        .collect()
+}
+
+/// The PRs of each issue that has any (open ones only if `open_only`), from
+/// the response to `merged_prs_query`. Linked PRs count first; the branch's
+/// PRs only if none are linked.
+fn prs_from_batch(
+    data: &serde_json::Value,
+    issues: &[(u64, Option<&str>)],
+    open_only: bool,
+) -> HashMap<u64, Vec<PullRequest>> {
+    let repository = &data["repository"];
+    let prs = |connection: &serde_json::Value| -> Vec<PullRequest> {
+        connection["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(PullRequest::from_graphql)
+            .collect()
+    };
+
+    let wanted = |pr: &PullRequest| !open_only || pr.state == PullRequestState::Open;
+
+    let mut found = HashMap::new();
+    for (number, branch) in issues {
+        let mut prs_of_issue = prs(&repository[format!("i{}", number)]["closedByPullRequestsReferences"]);
+        prs_of_issue.retain(wanted);
+        if prs_of_issue.is_empty() {
+            prs_of_issue = prs(&repository[format!("b{}", number)]);
+            prs_of_issue.retain(wanted);
+            // Search is looser than pleb's branch naming (e.g. "421-..." for #42)
+            if branch.is_none() {
+                prs_of_issue.retain(|pr| branch_matches_issue(&pr.head_ref, *number));
+            }
+        }
+        if !prs_of_issue.is_empty() {
+            found.insert(*number, prs_of_issue);
+        }
+    }
+    found
+}
+
 impl From<octocrab::models::issues::Issue> for Issue {
     fn from(issue: octocrab::models::issues::Issue) -> Self {
         Issue {
@@ -38,6 +253,12 @@ impl From<octocrab::models::issues::Issue> for Issue {
                 _ => IssueState::Open, // Default to Open for unknown states
             },
             html_url: issue.html_url.to_string(),
+            author: issue.user.login,
+            created_at: issue.created_at.timestamp().max(0) as u64,
+            milestone_due_on: issue
+                .milestone
+                .and_then(|m| m.due_on)
+                .map(|due| due.timestamp().max(0) as u64),
         }
     }
 }
@@ -47,6 +268,10 @@ pub struct GitHubClient {
     client: Octocrab,
     owner: String,
     repo: String,
+    /// Base URL of the GitHub API
+    api_url: String,
+    /// ETag and body of earlier responses, keyed by route
+    etags: Mutex<HashMap<String, (String, serde_json::Value)>>,
 }
 
 #[allow(dead_code)]
@@ -63,7 +288,10 @@ impl GitHubClient {
         })?;
 
         // Create octocrab instance with personal token authentication
+        let api_url = config.api_url.as_deref().unwrap_or(DEFAULT_API_URL).trim_end_matches('/');
         let client = Octocrab::builder()
+            .base_uri(api_url)
+            .context("Invalid GitHub API URL")?
             .personal_token(token)
             .build()
             .context("Failed to build GitHub client")?;
@@ -72,12 +300,20 @@ impl GitHubClient {
             client,
             owner: config.owner.clone(),
             repo: config.repo.clone(),
+            api_url: api_url.to_string(),
+            etags: Mutex::new(HashMap::new()),
         })
     }
 
+    /// Identify an issue in this client's repository
+    pub fn issue_ref(&self, number: u64) -> IssueRef {
+        IssueRef::new(&self.owner, &self.repo, number)
+    }
+
     /// Verify that the client can connect to GitHub and access the repository
     pub async fn verify_connection(&self) -> Result<()> {
         // Fetch repository information to verify token works and repo is accessible
+        count_api_call("rest");
         self.client
             .repos(&self.owner, &self.repo)
             .get()
@@ -101,15 +337,17 @@ impl GitHubClient {
 
     /// Fetch all open issues with the specified label
     pub async fn get_issues_with_label(&self, label: &str) -> Result<Vec<Issue>> {
-        let label_vec = vec![label.to_string()];
-        let issues = self
-            .client
-            .issues(&self.owner, &self.repo)
-            .list()
-            .state(octocrab::params::State::Open)
-            .labels(&label_vec)
-            .send()
+        let url = reqwest::Url::parse_with_params(
+            &format!("https://api.github.com/repos/{}/{}/issues", self.owner, self.repo),
+            &[("state", "open"), ("labels", label), ("per_page", "100")],
+        )
+        .context("Failed to build issues URL")?;
+        let route = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+
+        let issues: Vec<octocrab::models::issues::Issue> = self
+            .get_json(&route)
             .await
+            .and_then(|json| serde_json::from_value(json).context("Failed to parse issues"))
             .with_context(|| {
                 format!(
                     "Failed to fetch issues with label '{}' from {}/{}",
@@ -133,11 +371,11 @@ impl GitHubClient {
 
     /// Fetch a single issue by number
     pub async fn get_issue(&self, number: u64) -> Result<Issue> {
-        let issue = self
-            .client
-            .issues(&self.owner, &self.repo)
-            .get(number)
+        let route = format!("/repos/{}/{}/issues/{}", self.owner, self.repo, number);
+        let issue: octocrab::models::issues::Issue = self
+            .get_json(&route)
             .await
+            .and_then(|json| serde_json::from_value(json).context("Failed to parse issue"))
             .with_context(|| {
                 format!(
                     "Failed to fetch issue #{} from {}/{}",
@@ -155,8 +393,192 @@ impl GitHubClient {
         Ok(Issue::from(issue))
     }
 
+    /// Fetch comments on an issue created since a Unix timestamp, oldest first.
+    ///
+    /// Keeping `since` fixed (e.g. when tracking started) keeps the request
+    /// cacheable, so polling an issue without new comments is free.
+    pub async fn get_issue_comments(&self, number: u64, since: u64) -> Result<Vec<IssueComment>> {
+        let created_after = since;
+        let since = chrono::DateTime::from_timestamp(since as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+        // Follow the pages until a short one; each page is cached on its own
+        let mut comments = Vec::new();
+        for page in 1.. {
+            let route = format!(
+                "/repos/{}/{}/issues/{}/comments?since={}&per_page={}&page={}",
+                self.owner, self.repo, number, since, COMMENTS_PER_PAGE, page
+            );
+
+            let batch: Vec<octocrab::models::issues::Comment> = self
+                .get_json(&route)
+                .await
+                .and_then(|json| serde_json::from_value(json).context("Failed to parse comments"))
+                .with_context(|| {
+                    format!(
+                        "Failed to fetch comments on issue #{} from {}/{} (page {})",
+                        number, self.owner, self.repo, page
+                    )
+                })?;
+
+            let last_page = batch.len() < COMMENTS_PER_PAGE;
+            comments.extend(batch.into_iter().map(IssueComment::from));
+            if last_page {
+                break;
+            }
+        }
+
+        Ok(created_since(comments, created_after))
+    }
+
+    /// Post a comment on an issue and return its id
+    pub async fn create_comment(&self, issue_number: u64, body: &str) -> Result<u64> {
+        count_api_call("rest");
+        let comment = self
+            .client
+            .issues(&self.owner, &self.repo)
+            .create_comment(issue_number, body)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to comment on issue #{} in {}/{}",
+                    issue_number, self.owner, self.repo
+                )
+            })?;
+
+        Ok(comment.id.into_inner())
+    }
+
+    /// Open a new issue with the given labels and assignees
+    pub async fn create_issue(
+        &self,
+        title: &str,
+        body: &str,
+        labels: &[String],
+        assignees: &[String],
+    ) -> Result<Issue> {
+        count_api_call("rest");
+        let issue = self
+            .client
+            .issues(&self.owner, &self.repo)
+            .create(title)
+            .body(body)
+            .labels(labels.to_vec())
+            .assignees(assignees.to_vec())
+            .send()
+            .await
+            .with_context(|| format!("Failed to create issue in {}/{}", self.owner, self.repo))?;
+
+        tracing::debug!("Created issue #{} in {}/{}", issue.number, self.owner, self.repo);
+
+        Ok(Issue::from(issue))
+    }
+
+    /// Replace the body of an existing issue comment
+    pub async fn update_comment(&self, comment_id: u64, body: &str) -> Result<()> {
+        count_api_call("rest");
+        self.client
+            .issues(&self.owner, &self.repo)
+            .update_comment(octocrab::models::CommentId(comment_id), body)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to update comment {} in {}/{}",
+                    comment_id, self.owner, self.repo
+                )
+            })?;
+
+        Ok(())
+    }
+
+    /// GET a REST route as JSON, revalidating earlier responses with `If-None-Match`.
+    ///
+    /// A 304 Not Modified answer doesn't count against the rate limit, so the
+    /// cached body is returned instead. Rate-limit headers are recorded for
+    /// every response.
+    async fn get_json(&self, route: &str) -> Result<serde_json::Value> {
+        let cached = self
+            .etags
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(route)
+            .cloned();
+
+        let mut headers = HeaderMap::new();
+        if let Some((etag, _)) = &cached {
+            if let Ok(value) = HeaderValue::from_str(etag) {
+                headers.insert(header::IF_NONE_MATCH, value);
+            }
+        }
+
+        count_api_call("rest");
+        let response = self.client._get_with_headers(route, Some(headers)).await?;
+        ratelimit::record_headers(response.headers());
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some((_, body)) = cached {
+                tracing::trace!("Not modified: {}", route);
+                return Ok(body);
+            }
+        }
+
+        let response = octocrab::map_github_error(response).await?;
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = self.client.body_to_string(response).await?;
+        let json: serde_json::Value =
+            serde_json::from_str(&body).context("Failed to parse GitHub response")?;
+
+        if let Some(etag) = etag {
+            self.etags
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(route.to_string(), (etag, json.clone()));
+        }
+
+        Ok(json)
+    }
+
+    /// Refresh the rate-limit budget for every resource.
+    ///
+    /// The `/rate_limit` endpoint doesn't count against the limit, and it also
+    /// reflects quota used by other tools sharing the token.
+    pub async fn refresh_rate_budget(&self) -> Result<()> {
+        count_api_call("rest");
+        let limits = self
+            .client
+            .ratelimit()
+            .get()
+            .await
+            .context("Failed to fetch GitHub rate limit")?;
+
+        let resources = limits.resources;
+        let record = |resource: &str, rate: &octocrab::models::Rate| {
+            ratelimit::record(
+                resource,
+                RateLimit {
+                    limit: rate.limit as u64,
+                    remaining: rate.remaining as u64,
+                    reset: rate.reset,
+                },
+            );
+        };
+        record("core", &resources.core);
+        record("search", &resources.search);
+        if let Some(graphql) = &resources.graphql {
+            record("graphql", graphql);
+        }
+
+        Ok(())
+    }
+
     /// Add a label to an issue
     pub async fn add_label(&self, issue_number: u64, label: &str) -> Result<()> {
+        count_api_call("rest");
         self.client
             .issues(&self.owner, &self.repo)
             .add_labels(issue_number, &[label.to_string()])
@@ -182,6 +604,7 @@ impl GitHubClient {
     /// Remove a label from an issue
     pub async fn remove_label(&self, issue_number: u64, label: &str) -> Result<()> {
         // Attempt to remove the label, but don't fail if it doesn't exist
+        count_api_call("rest");
         match self
             .client
             .issues(&self.owner, &self.repo)
@@ -259,8 +682,8 @@ impl GitHubClient {
             .await?;
 
         tracing::info!(
-            "Transitioned issue #{} from {:?} to {:?}",
-            issue_number,
+            "Transitioned issue {} from {:?} to {:?}",
+            self.issue_ref(issue_number),
             from,
             to
         );
@@ -269,7 +692,7 @@ impl GitHubClient {
     }
 
     /// Convert a PlebState to the corresponding label string from config
-    fn state_to_label(&self, state: PlebState, config: &LabelConfig) -> String {
+    pub fn state_to_label(&self, state: PlebState, config: &LabelConfig) -> String {
         match state {
             PlebState::Ready => config.ready.clone(),
             PlebState::Provisioning => config.provisioning.clone(),
@@ -305,6 +728,7 @@ impl GitHubClient {
 
     /// Get the username of the authenticated user
     pub async fn get_authenticated_user(&self) -> Result<String> {
+        count_api_call("rest");
         let user = self
             .client
             .current()
@@ -315,147 +739,93 @@ impl GitHubClient {
         Ok(user.login)
     }
 
-    /// Find an open pull request associated with an issue number.
-    ///
-    /// Searches for PRs whose head branch starts with `{issue_number}-` which
-    /// matches pleb's branch naming convention: `{issue_number}-{slug}_{user}_{suffix}`.
-    /// Returns the PR URL if found.
+    /// Find pull requests for an issue (and its branch, if known) in one query.
     ///
-    /// Uses `gh` CLI which has its own authentication.
-    pub async fn get_pull_request_for_issue(&self, issue_number: u64) -> Result<Option<String>> {
-        use std::process::Command;
-
-        // Use gh CLI to list PRs and filter by branch prefix
-        // gh pr list --repo owner/repo --state open --json headRefName,url
-        let output = Command::new("gh")
-            .args([
-                "pr",
-                "list",
-                "--repo",
-                &format!("{}/{}", self.owner, self.repo),
-                "--state",
-                "open",
-                "--json",
-                "headRefName,url",
-                "--limit",
-                "200",
-            ])
-            .output()
-            .context("Failed to execute gh command")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("gh pr list failed: {}", stderr);
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let prs: Vec<serde_json::Value> =
-            serde_json::from_str(&stdout).context("Failed to parse gh output")?;
-
-        let branch_prefix = format!("{}-", issue_number);
+    /// Uses the PRs GitHub links as closing the issue ("Fixes #42" or linked
+    /// manually). If none are linked, falls back to the PRs from the issue's
+    /// branch, or without one, those whose head branch follows pleb's naming
+    /// convention: `{issue_number}-{slug}_{user}_{suffix}`.
+    pub async fn find_pull_requests(
+        &self,
+        issue_number: u64,
+        branch: Option<&str>,
+        open_only: bool,
+    ) -> Result<Vec<PullRequest>> {
+        let variables = serde_json::json!({
+            "owner": self.owner,
+            "repo": self.repo,
+        });
+        let issues = [(issue_number, branch)];
+        let data = self
+            .graphql(&merged_prs_query(&format!("{}/{}", self.owner, self.repo), &issues), &variables)
+            .await
+            .with_context(|| format!("Failed to look up PRs for issue {}", self.issue_ref(issue_number)))?;
 
-        for pr in prs {
-            if let (Some(head_ref), Some(url)) = (
-                pr.get("headRefName").and_then(|v| v.as_str()),
-                pr.get("url").and_then(|v| v.as_str()),
-            ) {
-                if head_ref.starts_with(&branch_prefix) {
-                    return Ok(Some(url.to_string()));
-                }
-            }
-        }
+        Ok(prs_from_batch(&data, &issues, open_only)
+            .remove(&issue_number)
+            .unwrap_or_default())
+    }
 
-        Ok(None)
+    /// Find an open pull request associated with an issue number.
+    /// Returns the PR URL if found.
+    pub async fn get_pull_request_for_issue(&self, issue_number: u64, branch: Option<&str>) -> Result<Option<String>> {
+        let prs = self.find_pull_requests(issue_number, branch, true).await?;
+        Ok(prs.into_iter().next().map(|pr| pr.url))
     }
 
-    /// Check if a PR associated with an issue has been merged.
-    ///
-    /// Searches for PRs whose head branch starts with `{issue_number}-` which
-    /// matches pleb's branch naming convention.
+    /// Check which of the given issues (number and branch, if known) have a
+    /// merged PR. The issues' PRs are looked up together, `MERGED_PRS_BATCH`
+    /// issues per query, so a poll costs the same however old the issues are.
     ///
-    /// Returns:
-    /// - Ok(None) if no PR found for this issue
-    /// - Ok(Some(true)) if PR exists and is merged
-    /// - Ok(Some(false)) if PR exists but not merged
-    ///
-    /// Uses `gh` CLI which has its own authentication.
-    /// Handles errors gracefully (gh not installed, network issues) - logs warning, returns Ok(None)
-    pub async fn check_pr_merged(&self, issue_number: u64) -> Result<Option<bool>> {
-        use std::process::Command;
-
-        // Use gh CLI to list PRs (all states) and filter by branch prefix
-        // gh pr list --repo owner/repo --state all --json headRefName,state,mergedAt
-        let output = Command::new("gh")
-            .args([
-                "pr",
-                "list",
-                "--repo",
-                &format!("{}/{}", self.owner, self.repo),
-                "--state",
-                "all",
-                "--json",
-                "headRefName,state,mergedAt",
-                "--limit",
-                "200",
-            ])
-            .output();
-
-        // Handle gh command errors gracefully
-        let output = match output {
-            Ok(output) => output,
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to execute gh command for issue #{}: {}. Assuming no PR or gh not available.",
-                    issue_number,
-                    e
-                );
-                return Ok(None);
-            }
-        };
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            tracing::warn!(
-                "gh pr list failed for issue #{}: {}. Assuming no PR or gh not available.",
-                issue_number,
-                stderr
-            );
-            return Ok(None);
+    /// Returns, for each issue that has PRs, whether one of them is merged.
+    pub async fn check_prs_merged(&self, issues: &[(u64, Option<&str>)]) -> Result<HashMap<u64, bool>> {
+        let variables = serde_json::json!({
+            "owner": self.owner,
+            "repo": self.repo,
+        });
+
+        let mut merged = HashMap::new();
+        for batch in issues.chunks(MERGED_PRS_BATCH) {
+            let data = self
+                .graphql(&merged_prs_query(&format!("{}/{}", self.owner, self.repo), batch), &variables)
+                .await
+                .with_context(|| format!("Failed to look up PRs in {}/{}", self.owner, self.repo))?;
+            merged.extend(merged_from_batch(&data, batch));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let prs: Vec<serde_json::Value> = match serde_json::from_str(&stdout) {
-            Ok(prs) => prs,
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to parse gh output for issue #{}: {}",
-                    issue_number,
-                    e
-                );
-                return Ok(None);
-            }
-        };
-
-        let branch_prefix = format!("{}-", issue_number);
-
-        for pr in prs {
-            if let Some(head_ref) = pr.get("headRefName").and_then(|v| v.as_str()) {
-                if head_ref.starts_with(&branch_prefix) {
-                    // Found a matching PR, check if it's merged
-                    let state = pr.get("state").and_then(|v| v.as_str());
-                    let merged_at = pr.get("mergedAt");
+        tracing::debug!(
+            "Looked up PRs for {} issues in {}/{}, {} have PRs",
+            issues.len(),
+            self.owner,
+            self.repo,
+            merged.len()
+        );
 
-                    // A PR is merged if state is "MERGED" or mergedAt is not null
-                    let is_merged = state == Some("MERGED")
-                        || (merged_at.is_some() && !merged_at.unwrap().is_null());
+        Ok(merged)
+    }
 
-                    return Ok(Some(is_merged));
-                }
-            }
+    /// POST a GraphQL query and return its `data`, recording the rate-limit headers
+    async fn graphql(
+        &self,
+        query: &str,
+        variables: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let payload = serde_json::json!({ "query": query, "variables": variables });
+        count_api_call("graphql");
+        let response = self.client._post("/graphql", Some(&payload)).await?;
+        ratelimit::record_headers(response.headers());
+
+        let response = octocrab::map_github_error(response).await?;
+        let body = self.client.body_to_string(response).await?;
+        let mut json: serde_json::Value =
+            serde_json::from_str(&body).context("Failed to parse GraphQL response")?;
+
+        if let Some(errors) = json["errors"].as_array().filter(|e| !e.is_empty()) {
+            let messages: Vec<&str> = errors.iter().filter_map(|e| e["message"].as_str()).collect();
+            anyhow::bail!("GraphQL query failed: {}", messages.join("; "));
         }
 
-        // No PR found for this issue
-        Ok(None)
+        Ok(json["data"].take())
     }
 
     /// Fetch the issue body_html which contains signed URLs for private attachments.
@@ -468,10 +838,11 @@ impl GitHubClient {
     /// custom Accept headers per-request.
     pub async fn get_issue_body_html(&self, issue_number: u64, github_token: &str) -> Result<String> {
         let url = format!(
-            "https://api.github.com/repos/{}/{}/issues/{}",
-            self.owner, self.repo, issue_number
+            "{}/repos/{}/{}/issues/{}",
+            self.api_url, self.owner, self.repo, issue_number
         );
 
+        count_api_call("rest");
         let client = reqwest::Client::new();
         let response = client
             .get(&url)
@@ -515,3 +886,103 @@ impl GitHubClient {
         Ok(body_html)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_matches_issue() {
+        assert!(branch_matches_issue("42-add-invoices-table_alice_pleb", 42));
+        assert!(branch_matches_issue("42-x", 42));
+        assert!(!branch_matches_issue("420-add-invoices_alice_pleb", 42));
+        assert!(!branch_matches_issue("142-add-invoices_alice_pleb", 42));
+        assert!(!branch_matches_issue("42", 42));
+        assert!(!branch_matches_issue("feature/42-thing", 42));
+    }
+
+    #[test]
+    fn test_pull_request_from_graphql() {
+        let node = serde_json::json!({
+            "number": 57,
+            "url": "https://github.com/acme/api/pull/57",
+            "headRefName": "42-add-invoices-table_alice_pleb",
+            "state": "MERGED"
+        });
+        assert_eq!(
+            PullRequest::from_graphql(&node),
+            Some(PullRequest {
+                number: 57,
+                url: "https://github.com/acme/api/pull/57".to_string(),
+                head_ref: "42-add-invoices-table_alice_pleb".to_string(),
+                state: PullRequestState::Merged,
+                created_at: 0,
+            })
+        );
+
+        let node = serde_json::json!({ "number": 58, "url": "u", "headRefName": "b", "state": "CLOSED" });
+        assert_eq!(PullRequest::from_graphql(&node).unwrap().state, PullRequestState::Closed);
+
+        // Nodes the token can't see come back as null
+        assert_eq!(PullRequest::from_graphql(&serde_json::Value::Null), None);
+    }
+
+    fn fixture(name: &str) -> serde_json::Value {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/graphql")
+            .join(name);
+        let json = std::fs::read_to_string(&path).expect("fixture should exist");
+        serde_json::from_str(&json).expect("fixture should be valid JSON")
+    }
+
+    #[test]
+    fn test_merged_prs_query() {
+        let query = merged_prs_query("acme/api", &[(42, Some("42-say-\"hi\"_alice_pleb")), (43, None)]);
+        assert!(query.contains("i42: issue(number: 42) { closedByPullRequestsReferences("));
+        assert!(query.contains(r#"b42: pullRequests(headRefName: "42-say-\"hi\"_alice_pleb", first: 5)"#));
+        assert!(query.contains("i43: issue(number: 43)"));
+        assert!(query.contains(r#"b43: search(query: "repo:acme/api is:pr head:43-", type: ISSUE, first: 20)"#));
+    }
+
+    #[test]
+    fn test_merged_from_recorded_batch() {
+        let data = fixture("merged_prs_batch.json");
+        let issues = [
+            (42, Some("42-add-invoices-table_alice_pleb")),
+            (43, Some("43-fix-login_bob_pleb")),
+            (44, None),
+            (45, None),
+            (46, None),
+        ];
+        let status = merged_from_batch(&data["data"], &issues);
+        // Linked with "Fixes #42" and merged
+        assert_eq!(status.get(&42), Some(&true));
+        // Only found by branch name, still open
+        assert_eq!(status.get(&43), Some(&false));
+        // One linked PR was closed unmerged, a later one was merged
+        assert_eq!(status.get(&44), Some(&true));
+        // No PR yet; the search only found another issue's branch
+        assert_eq!(status.get(&45), None);
+        // No worktree or tracked branch, found by the "46-" prefix
+        assert_eq!(status.get(&46), Some(&true));
+
+        // The status comment only wants open PRs
+        let open = prs_from_batch(&data["data"], &issues, true);
+        assert_eq!(open.keys().copied().collect::<Vec<_>>(), [43]);
+        assert_eq!(open[&43][0].url, "https://github.com/acme/api/pull/60");
+    }
+
+    #[test]
+    fn test_edited_old_comment_is_not_new() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/rest/issue_comments.json");
+        let json = std::fs::read_to_string(&path).expect("fixture should exist");
+        let comments: Vec<octocrab::models::issues::Comment> = serde_json::from_str(&json).unwrap();
+        let comments: Vec<IssueComment> = comments.into_iter().map(IssueComment::from).collect();
+        assert_eq!(comments.len(), 2);
+
+        // The session started on 2024-06-02; bob's comment is older but was edited after
+        let started_at = 1_717_286_400;
+        let new = created_since(comments, started_at);
+        assert_eq!(new.iter().map(|c| c.author.as_str()).collect::<Vec<_>>(), ["alice"]);
+    }
+}
@@ -0,0 +1,411 @@
+//! Headless sessions (agents with `mode = "headless"`).
+//!
+//! The agent runs as a child of the daemon, e.g. Claude Code as
+//! `claude -p --output-format stream-json` with the prompt on stdin. Each line
+//! of its output is appended to the issue's transcript, and the events that
+//! matter for the issue's state are sent back to the orchestrator: the session
+//! starting, and the run ending in Done (finished its work) or Waiting (needs
+//! a person). With stream-json output those come from Claude's events, and
+//! comments resume the same session with `--resume`; otherwise the exit status
+//! decides.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+
+use crate::agent::Agent;
+use crate::config::StateSource;
+use crate::github::IssueRef;
+use crate::state::{PlebState, TrackedIssue};
+use crate::transcript::TRANSCRIPT_FILE;
+
+/// Output of agents that don't produce stream-json, in the issue directory
+pub const OUTPUT_FILE: &str = "output.log";
+
+/// Something that happened in an issue's headless run
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeadlessEvent {
+    /// The agent started (or resumed) the session; only stream-json output names it
+    Started { session_id: Option<String> },
+    /// The run is over and the issue should move to `state`
+    Stopped { state: PlebState, reason: String },
+}
+
+/// A headless event for an issue, sent to the orchestrator
+#[derive(Debug, Clone)]
+pub struct HeadlessUpdate {
+    pub issue: IssueRef,
+    pub event: HeadlessEvent,
+}
+
+/// The stream-json events pleb looks at; everything else only goes to the transcript
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    System {
+        #[serde(default)]
+        subtype: String,
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    Result(RunResult),
+    #[serde(other)]
+    Other,
+}
+
+/// The final `result` event of a run
+#[derive(Debug, Deserialize)]
+struct RunResult {
+    subtype: String,
+    #[serde(default)]
+    is_error: bool,
+    #[serde(default)]
+    permission_denials: Vec<PermissionDenial>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PermissionDenial {
+    tool_name: String,
+}
+
+/// Starts headless runs and reports their events on a channel
+pub struct HeadlessRunner {
+    updates: mpsc::UnboundedSender<HeadlessUpdate>,
+    /// Issues with a run in progress
+    running: Arc<Mutex<HashSet<IssueRef>>>,
+}
+
+impl HeadlessRunner {
+    /// Create a runner and the channel its events arrive on
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<HeadlessUpdate>) {
+        let (updates, rx) = mpsc::unbounded_channel();
+        let runner = Self {
+            updates,
+            running: Arc::default(),
+        };
+        (runner, rx)
+    }
+
+    /// Whether a run for the issue is still in progress
+    pub fn is_running(&self, issue: &IssueRef) -> bool {
+        self.running.lock().unwrap().contains(issue)
+    }
+
+    /// Start a new session for the issue in `working_dir`. The prompt is
+    /// written to the issue directory like an interactive session's.
+    pub fn invoke(
+        &self,
+        agent: &Agent,
+        issue: &IssueRef,
+        prompt: &str,
+        state_dir: &Path,
+        working_dir: &Path,
+    ) -> Result<()> {
+        let prompt_file = write_prompt(issue, prompt, state_dir, "prompt.md")?;
+        self.spawn(agent, issue, &prompt_file, working_dir, None)
+    }
+
+    /// Resume the issue's session with a message in its worktree, or its most
+    /// recent conversation there if the session id isn't known. Agents
+    /// without resume arguments start a fresh run with the message.
+    pub fn send_message(
+        &self,
+        agent: &Agent,
+        issue: &IssueRef,
+        tracked: &TrackedIssue,
+        message: &str,
+        state_dir: &Path,
+        file_name: &str,
+    ) -> Result<()> {
+        let working_dir = tracked
+            .worktree_path
+            .as_deref()
+            .with_context(|| format!("Issue {} has no worktree to resume in", issue))?;
+        let message_file = write_prompt(issue, message, state_dir, file_name)?;
+        tracing::info!("Resuming the headless session for issue {} with {}", issue, file_name);
+        self.spawn(agent, issue, &message_file, working_dir, Some(tracked.session_id.as_deref()))
+    }
+
+    /// `resume` is None for a new session, or the session to resume (None inside for the latest)
+    fn spawn(
+        &self,
+        agent: &Agent,
+        issue: &IssueRef,
+        prompt_file: &Path,
+        working_dir: &Path,
+        resume: Option<Option<&str>>,
+    ) -> Result<()> {
+        let stdin = match agent.prompt_on_stdin() {
+            true => Stdio::from(
+                std::fs::File::open(prompt_file)
+                    .with_context(|| format!("Failed to open prompt file: {:?}", prompt_file))?,
+            ),
+            false => Stdio::null(),
+        };
+
+        let command = &agent.profile.command;
+        let args = agent.headless_args(prompt_file, resume)?;
+        tracing::info!(
+            "Starting headless agent {} for {} in {}: {} {}",
+            agent.name,
+            issue,
+            working_dir.display(),
+            command,
+            args.join(" ")
+        );
+
+        let child = Command::new(command)
+            .args(&args)
+            .current_dir(working_dir)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", command))?;
+
+        self.running.lock().unwrap().insert(issue.clone());
+
+        let stream_json = agent.state_source() == StateSource::StreamJson;
+        let transcript = prompt_file.with_file_name(if stream_json { TRANSCRIPT_FILE } else { OUTPUT_FILE });
+        let issue = issue.clone();
+        let updates = self.updates.clone();
+        let running = self.running.clone();
+        if !stream_json {
+            // Nothing in the output says the session started
+            let _ = updates.send(HeadlessUpdate {
+                issue: issue.clone(),
+                event: HeadlessEvent::Started { session_id: None },
+            });
+        }
+        tokio::spawn(async move {
+            let event = match supervise(&issue, child, &transcript, stream_json, &updates).await {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::error!("Lost track of the headless run for issue {}: {:#}", issue, e);
+                    HeadlessEvent::Stopped {
+                        state: PlebState::Waiting,
+                        reason: "Lost track of the run".to_string(),
+                    }
+                }
+            };
+            running.lock().unwrap().remove(&issue);
+            let _ = updates.send(HeadlessUpdate { issue, event });
+        });
+
+        Ok(())
+    }
+}
+
+fn write_prompt(issue: &IssueRef, prompt: &str, state_dir: &Path, file_name: &str) -> Result<PathBuf> {
+    let issue_dir = state_dir.join(issue.number.to_string());
+    std::fs::create_dir_all(&issue_dir)
+        .with_context(|| format!("Failed to create issue directory: {:?}", issue_dir))?;
+
+    let prompt_file = issue_dir.join(file_name);
+    std::fs::write(&prompt_file, prompt)
+        .with_context(|| format!("Failed to write prompt file: {:?}", prompt_file))?;
+    Ok(prompt_file)
+}
+
+/// Follow a run to the end, copying its output to the transcript. With
+/// stream-json output, `Started` is sent as it happens and the result event
+/// ends the run; otherwise the exit status does. Returns the event that ends the run.
+async fn supervise(
+    issue: &IssueRef,
+    mut child: Child,
+    transcript: &Path,
+    stream_json: bool,
+    updates: &mpsc::UnboundedSender<HeadlessUpdate>,
+) -> Result<HeadlessEvent> {
+    let stdout = child.stdout.take().context("Missing stdout")?;
+    let mut stderr = child.stderr.take().context("Missing stderr")?;
+    let stderr_task = tokio::spawn(async move {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text).await;
+        text
+    });
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(transcript)
+        .await
+        .with_context(|| format!("Failed to open transcript: {:?}", transcript))?;
+
+    let mut stopped = None;
+    let mut lines = BufReader::new(stdout).lines();
+    while let Some(line) = lines.next_line().await? {
+        file.write_all(line.as_bytes()).await?;
+        file.write_all(b"\n").await?;
+        if !stream_json {
+            continue;
+        }
+
+        match serde_json::from_str::<StreamEvent>(&line) {
+            Ok(StreamEvent::System { subtype, session_id: Some(session_id) }) if subtype == "init" => {
+                tracing::debug!("Headless session {} started for issue {}", session_id, issue);
+                let _ = updates.send(HeadlessUpdate {
+                    issue: issue.clone(),
+                    event: HeadlessEvent::Started { session_id: Some(session_id) },
+                });
+            }
+            Ok(StreamEvent::Result(result)) => stopped = Some(stop_for(&result)),
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Unrecognised output from headless run for {}: {}", issue, e),
+        }
+    }
+    file.flush().await?;
+
+    let status = child.wait().await.context("Failed to wait for the agent")?;
+    let stderr = stderr_task.await.unwrap_or_default();
+    tracing::info!("Headless run for issue {} exited with {}", issue, status);
+
+    if !stream_json && status.success() {
+        return Ok(HeadlessEvent::Stopped {
+            state: PlebState::Done,
+            reason: "Finished its run".to_string(),
+        });
+    }
+    Ok(stopped.unwrap_or_else(|| exited_early(status, &stderr)))
+}
+
+/// Where a finished run leaves the issue
+fn stop_for(result: &RunResult) -> HeadlessEvent {
+    let (state, reason) = if result.is_error || result.subtype != "success" {
+        let reason = match result.subtype.as_str() {
+            "error_max_turns" => "Ran out of turns".to_string(),
+            "success" => "Finished with an error".to_string(),
+            other => format!("Stopped with an error ({})", other),
+        };
+        (PlebState::Waiting, reason)
+    } else if let Some(denial) = result.permission_denials.first() {
+        // It carried on without the tool, but someone should look
+        (PlebState::Waiting, format!("Needs permission to use {}", denial.tool_name))
+    } else {
+        (PlebState::Done, "Finished its run".to_string())
+    };
+    HeadlessEvent::Stopped { state, reason }
+}
+
+/// A run that ended without a result event (crashed, killed or failed to start)
+fn exited_early(status: ExitStatus, stderr: &str) -> HeadlessEvent {
+    let mut reason = format!("Exited ({}) before finishing", status);
+    if let Some(last) = stderr.lines().rev().find(|line| !line.trim().is_empty()) {
+        reason = format!("{}: {}", reason, last.trim());
+    }
+    HeadlessEvent::Stopped {
+        state: PlebState::Waiting,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClaudeConfig;
+
+    fn result(line: &str) -> HeadlessEvent {
+        match serde_json::from_str::<StreamEvent>(line).unwrap() {
+            StreamEvent::Result(result) => stop_for(&result),
+            other => panic!("not a result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stream_events() {
+        let init = r#"{"type":"system","subtype":"init","session_id":"abc","tools":["Bash"]}"#;
+        assert!(matches!(
+            serde_json::from_str::<StreamEvent>(init).unwrap(),
+            StreamEvent::System { subtype, session_id: Some(id) } if subtype == "init" && id == "abc"
+        ));
+        let assistant = r#"{"type":"assistant","message":{"content":[]},"session_id":"abc"}"#;
+        assert!(matches!(
+            serde_json::from_str::<StreamEvent>(assistant).unwrap(),
+            StreamEvent::Other
+        ));
+
+        assert_eq!(
+            result(r#"{"type":"result","subtype":"success","is_error":false,"result":"Done","session_id":"abc","permission_denials":[]}"#),
+            HeadlessEvent::Stopped { state: PlebState::Done, reason: "Finished its run".to_string() }
+        );
+        assert_eq!(
+            result(r#"{"type":"result","subtype":"success","is_error":false,"permission_denials":[{"tool_name":"Bash","tool_use_id":"x","tool_input":{}}]}"#),
+            HeadlessEvent::Stopped {
+                state: PlebState::Waiting,
+                reason: "Needs permission to use Bash".to_string()
+            }
+        );
+        assert_eq!(
+            result(r#"{"type":"result","subtype":"error_max_turns","is_error":true}"#),
+            HeadlessEvent::Stopped { state: PlebState::Waiting, reason: "Ran out of turns".to_string() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_events_and_writes_transcript() {
+        let dir = std::env::temp_dir().join(format!("pleb-headless-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Stands in for claude: echoes a session start and a successful result
+        let script = dir.join("claude.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\ncat > /dev/null\n\
+             echo '{\"type\":\"system\",\"subtype\":\"init\",\"session_id\":\"s1\"}'\n\
+             echo '{\"type\":\"result\",\"subtype\":\"success\",\"is_error\":false}'\n",
+        )
+        .unwrap();
+        let config: ClaudeConfig =
+            toml::from_str(&format!("command = \"sh\"\nargs = [{:?}]\nmode = \"headless\"", script)).unwrap();
+        let agent = Agent::new("claude", config.profile());
+        let (runner, mut rx) = HeadlessRunner::new();
+        let issue = IssueRef::new("acme", "api", 7);
+
+        runner.invoke(&agent, &issue, "Fix it", &dir, &dir).unwrap();
+        let started = rx.recv().await.unwrap();
+        assert_eq!(started.event, HeadlessEvent::Started { session_id: Some("s1".to_string()) });
+        let stopped = rx.recv().await.unwrap();
+        assert!(matches!(stopped.event, HeadlessEvent::Stopped { state: PlebState::Done, .. }));
+        assert!(!runner.is_running(&issue));
+
+        let transcript = std::fs::read_to_string(dir.join("7").join(TRANSCRIPT_FILE)).unwrap();
+        assert_eq!(transcript.lines().count(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_exit_status_decides_without_stream_json() {
+        let dir = std::env::temp_dir().join(format!("pleb-headless-exit-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // A stub agent that gets the prompt file's path and gives up
+        let script = dir.join("stub.sh");
+        std::fs::write(&script, "#!/bin/sh
+cat \"$1\"\necho 'no tests found' >&2\nexit 3\n").unwrap();
+        let profile = toml::from_str(&format!("command = \"sh\"\nargs = [{:?}]\nmode = \"headless\"", script)).unwrap();
+        let agent = Agent::new("stub", profile);
+        let (runner, mut rx) = HeadlessRunner::new();
+        let issue = IssueRef::new("acme", "api", 8);
+
+        runner.invoke(&agent, &issue, "Fix it", &dir, &dir).unwrap();
+        assert_eq!(rx.recv().await.unwrap().event, HeadlessEvent::Started { session_id: None });
+        match rx.recv().await.unwrap().event {
+            HeadlessEvent::Stopped { state, reason } => {
+                assert_eq!(state, PlebState::Waiting);
+                assert!(reason.ends_with(": no tests found"), "{}", reason);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let output = std::fs::read_to_string(dir.join("8").join(OUTPUT_FILE)).unwrap();
+        assert_eq!(output, "Fix it\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -4,6 +4,10 @@ use serde_json::Value;
 use std::fs;
 use std::path::Path;
 
+use crate::config::RepoConfig;
+use crate::github::IssueRef;
+use crate::quick::{self, QuickRef, SessionKey};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Hook {
     #[serde(rename = "type")]
@@ -143,15 +147,23 @@ pub fn install_hooks(path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Parse issue number from a worktree path
-/// Supports both old format "/path/worktrees/issue-123" and
-/// new format "/path/worktrees/123-slug_username_suffix"
-pub fn extract_issue_number_from_path(path: &str) -> Option<u64> {
+/// Parse the session a worktree path belongs to
+/// Supports the old issue format "/path/worktrees/issue-123", the new issue
+/// format "/path/worktrees/123-slug_username_suffix" and quick sessions
+/// "/path/worktrees/quick-{id}"
+pub fn extract_session_from_path(path: &str) -> Option<SessionKey> {
     for component in path.split('/') {
+        // Quick session: "quick-{id}"
+        if let Some(id) = component.strip_prefix(quick::PREFIX) {
+            if !id.is_empty() {
+                return Some(SessionKey::Quick(id.to_string()));
+            }
+        }
+
         // Try old format: "issue-{number}"
         if let Some(issue_part) = component.strip_prefix("issue-") {
             if let Ok(number) = issue_part.parse::<u64>() {
-                return Some(number);
+                return Some(SessionKey::Issue(number));
             }
         }
 
@@ -159,43 +171,152 @@ pub fn extract_issue_number_from_path(path: &str) -> Option<u64> {
         if let Some(dash_pos) = component.find('-') {
             let prefix = &component[..dash_pos];
             if let Ok(number) = prefix.parse::<u64>() {
-                return Some(number);
+                return Some(SessionKey::Issue(number));
             }
         }
     }
     None
 }
 
+/// Resolve the issue a worktree path belongs to across all configured repos.
+/// The repo is the one whose worktree base contains the path; with a single
+/// repo configured, any path carrying an issue number belongs to it.
+pub fn extract_issue_ref_from_path(path: &str, repos: &[RepoConfig]) -> Option<IssueRef> {
+    match extract_session_key_from_path(path, repos)? {
+        (repo, SessionKey::Issue(number)) => {
+            Some(IssueRef::new(&repo.github.owner, &repo.github.repo, number))
+        }
+        (_, SessionKey::Quick(_)) => None,
+    }
+}
+
+/// Resolve the quick session a worktree path belongs to, like `extract_issue_ref_from_path`
+pub fn extract_quick_ref_from_path(path: &str, repos: &[RepoConfig]) -> Option<QuickRef> {
+    match extract_session_key_from_path(path, repos)? {
+        (repo, SessionKey::Quick(id)) => Some(QuickRef::new(&repo.github.owner, &repo.github.repo, id)),
+        (_, SessionKey::Issue(_)) => None,
+    }
+}
+
+fn extract_session_key_from_path<'a>(
+    path: &str,
+    repos: &'a [RepoConfig],
+) -> Option<(&'a RepoConfig, SessionKey)> {
+    let path_buf = Path::new(path);
+
+    let matched = repos
+        .iter()
+        .filter(|r| crate::config::path_within(path_buf, &r.paths.worktree_base))
+        .max_by_key(|r| r.paths.worktree_base.components().count());
+
+    match matched {
+        Some(repo) => {
+            // Only look below the worktree base so its own components can't match
+            let base = repo
+                .paths
+                .worktree_base
+                .canonicalize()
+                .unwrap_or_else(|_| repo.paths.worktree_base.clone());
+            let full = path_buf.canonicalize().unwrap_or_else(|_| path_buf.to_path_buf());
+            let relative = full.strip_prefix(&base).unwrap_or(&full);
+            Some((repo, extract_session_from_path(&relative.to_string_lossy())?))
+        }
+        None if repos.len() == 1 => Some((&repos[0], extract_session_from_path(path)?)),
+        None => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_extract_issue_number() {
+    fn test_extract_session() {
         // Old format: issue-{number}
         assert_eq!(
-            extract_issue_number_from_path("/path/worktrees/issue-123"),
-            Some(123)
+            extract_session_from_path("/path/worktrees/issue-123"),
+            Some(SessionKey::Issue(123))
         );
         assert_eq!(
-            extract_issue_number_from_path("/home/user/worktrees/issue-42/src"),
-            Some(42)
+            extract_session_from_path("/home/user/worktrees/issue-42/src"),
+            Some(SessionKey::Issue(42))
         );
-        assert_eq!(extract_issue_number_from_path("issue-456"), Some(456));
+        assert_eq!(extract_session_from_path("issue-456"), Some(SessionKey::Issue(456)));
 
         // New format: {number}-{slug}_{username}_{suffix}
         assert_eq!(
-            extract_issue_number_from_path("/path/worktrees/2592-add-invoices-table_user_pleb"),
-            Some(2592)
+            extract_session_from_path("/path/worktrees/2592-add-invoices-table_user_pleb"),
+            Some(SessionKey::Issue(2592))
+        );
+        assert_eq!(
+            extract_session_from_path("/home/acron/projects/kikin/monorepo-branches/2592-add-invoices-table-to-the_acron0_pleb"),
+            Some(SessionKey::Issue(2592))
+        );
+
+        // Quick sessions are not issues, even when their branch starts with a number
+        assert_eq!(
+            extract_session_from_path("/path/worktrees/quick-123-hotfix"),
+            Some(SessionKey::Quick("123-hotfix".to_string()))
         );
         assert_eq!(
-            extract_issue_number_from_path("/home/acron/projects/kikin/monorepo-branches/2592-add-invoices-table-to-the_acron0_pleb"),
-            Some(2592)
+            extract_session_from_path("/path/worktrees/quick-123-hotfix/src"),
+            Some(SessionKey::Quick("123-hotfix".to_string()))
         );
 
         // No issue number
-        assert_eq!(extract_issue_number_from_path("/path/no-issue-here"), None);
-        assert_eq!(extract_issue_number_from_path("/path/main"), None);
+        assert_eq!(extract_session_from_path("/path/no-issue-here"), None);
+        assert_eq!(extract_session_from_path("/path/main"), None);
+    }
+
+    #[test]
+    fn test_extract_issue_ref_from_path() {
+        let make_repo = |name: &str, worktree_base: &str| RepoConfig {
+            github: crate::config::GithubConfig {
+                owner: "acme".to_string(),
+                repo: name.to_string(),
+                token_env: "GITHUB_TOKEN".to_string(),
+                rate_limit_reserve_percent: 10,
+                api_url: None,
+            },
+            labels: Default::default(),
+            paths: crate::config::PathConfig {
+                repo_dir: std::path::PathBuf::from(format!("/src/{}", name)),
+                worktree_base: std::path::PathBuf::from(worktree_base),
+            },
+            prompts: Default::default(),
+            branch: Default::default(),
+            provision: Default::default(),
+            agent: Default::default(),
+        };
+        let api = make_repo("api", "/work/api-worktrees");
+        let web = make_repo("web", "/work/web-worktrees");
+        let both = [api.clone(), web];
+
+        assert_eq!(
+            extract_issue_ref_from_path("/work/web-worktrees/12-fix_user_pleb/src", &both),
+            Some(IssueRef::new("acme", "web", 12))
+        );
+        assert_eq!(
+            extract_issue_ref_from_path("/work/api-worktrees/12-fix_user_pleb", &both),
+            Some(IssueRef::new("acme", "api", 12))
+        );
+        // Outside every worktree base is ambiguous with several repos
+        assert_eq!(extract_issue_ref_from_path("/elsewhere/12-fix", &both), None);
+
+        assert_eq!(
+            extract_quick_ref_from_path("/work/web-worktrees/quick-fix-typo/src", &both),
+            Some(QuickRef::new("acme", "web", "fix-typo"))
+        );
+        assert_eq!(
+            extract_issue_ref_from_path("/work/web-worktrees/quick-fix-typo", &both),
+            None
+        );
+
+        // A single repo claims any path with an issue number
+        assert_eq!(
+            extract_issue_ref_from_path("/elsewhere/12-fix", &[api]),
+            Some(IssueRef::new("acme", "api", 12))
+        );
     }
 
     #[test]
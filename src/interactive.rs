@@ -0,0 +1,160 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::agent::Agent;
+use crate::config::PromptStyle;
+use crate::sandbox::SandboxSession;
+use crate::session::{SessionBackend, WindowTarget};
+
+/// Starts interactive agents in session windows and types messages into them
+pub struct InteractiveRunner {
+    tmux: Arc<dyn SessionBackend>,
+}
+
+impl InteractiveRunner {
+    /// Create a new InteractiveRunner
+    pub fn new(tmux: Arc<dyn SessionBackend>) -> Self {
+        Self { tmux }
+    }
+
+    /// Invoke the agent in the issue's tmux window with the given prompt
+    /// The agent starts in interactive mode so user can attach and interact
+    /// If a sandbox is given, the agent runs inside it rather than on the host
+    pub async fn invoke(
+        &self,
+        agent: &Agent,
+        issue: &impl WindowTarget,
+        prompt: &str,
+        state_dir: &Path,
+        sandbox: Option<&SandboxSession>,
+    ) -> Result<()> {
+        // Create issue-specific directory for prompt file
+        let issue_dir = state_dir.join(issue.state_dir_name());
+        std::fs::create_dir_all(&issue_dir)
+            .with_context(|| format!("Failed to create issue directory: {:?}", issue_dir))?;
+
+        // Write prompt to persistent location
+        let prompt_file = issue_dir.join("prompt.md");
+        std::fs::write(&prompt_file, prompt)
+            .with_context(|| format!("Failed to write prompt file: {:?}", prompt_file))?;
+
+        self.launch(agent, issue, Some(&prompt_file), sandbox).await
+    }
+
+    /// Start the agent in the window without an initial prompt
+    pub async fn start(&self, agent: &Agent, issue: &impl WindowTarget) -> Result<()> {
+        self.launch(agent, issue, None, None).await
+    }
+
+    async fn launch(
+        &self,
+        agent: &Agent,
+        issue: &impl WindowTarget,
+        prompt_file: Option<&Path>,
+        sandbox: Option<&SandboxSession>,
+    ) -> Result<()> {
+        let mut command = agent.command_line(prompt_file)?;
+        if let Some(sandbox) = sandbox {
+            command = sandbox.wrap(&command);
+        }
+
+        tracing::info!(
+            "Invoking agent {} for {} with command: {}",
+            agent.name,
+            issue,
+            command
+        );
+
+        // Start the agent with the prompt
+        self.tmux.send_keys(issue, &command).await?;
+
+        if !agent.profile.accept_trust_prompt {
+            return Ok(());
+        }
+
+        // Wait briefly for the workspace trust prompt to appear
+        // (entering a sandbox takes a little longer)
+        let delay_ms = if sandbox.is_some() { 2000 } else { 500 };
+        tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+
+        // Automatically accept the workspace trust prompt by sending Enter
+        // (which selects the default "Yes, I trust this folder" option)
+        self.tmux.send_keys(issue, "").await?;
+
+        Ok(())
+    }
+
+    /// Type a message into the issue's running session. Like the initial prompt,
+    /// the message is written to the issue directory and passed the way the
+    /// agent's profile says.
+    pub async fn send_message(
+        &self,
+        agent: &Agent,
+        issue: &impl WindowTarget,
+        message: &str,
+        state_dir: &Path,
+        file_name: &str,
+    ) -> Result<()> {
+        let issue_dir = state_dir.join(issue.state_dir_name());
+        std::fs::create_dir_all(&issue_dir)
+            .with_context(|| format!("Failed to create issue directory: {:?}", issue_dir))?;
+
+        let message_file = issue_dir.join(file_name);
+        std::fs::write(&message_file, message)
+            .with_context(|| format!("Failed to write message file: {:?}", message_file))?;
+
+        tracing::info!("Sending {} to the session for issue {}", file_name, issue);
+
+        self.tmux
+            .send_keys(issue, &agent.message_keys(&message_file, message))
+            .await?;
+
+        // The first Enter after an `@` reference picks it from the completion menu
+        if agent.profile.prompt == PromptStyle::AtFile {
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            self.tmux.send_keys(issue, "").await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClaudeConfig;
+    use crate::github::IssueRef;
+    use crate::session::fake::FakeBackend;
+
+    #[tokio::test]
+    async fn test_invoke_types_command_into_window() {
+        let backend = Arc::new(FakeBackend::default());
+        let config: ClaudeConfig = toml::from_str("").unwrap();
+        let agent = Agent::new("claude", config.profile());
+        let runner = InteractiveRunner::new(backend.clone());
+        let issue = IssueRef::new("acme", "api", 12);
+        let state_dir = std::env::temp_dir().join(format!("pleb-claude-test-{}", std::process::id()));
+
+        backend
+            .create_window(&issue, "12-fix_user_pleb", Path::new("/work"))
+            .await
+            .unwrap();
+        runner
+            .invoke(&agent, &issue, "Fix it", &state_dir, None)
+            .await
+            .unwrap();
+
+        let prompt_file = state_dir.join("12").join("prompt.md");
+        assert_eq!(std::fs::read_to_string(&prompt_file).unwrap(), "Fix it");
+        let keys = backend.keys(&issue).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(keys[0].starts_with("claude --permission-mode plan"));
+        assert!(keys[0].ends_with(&format!("@{}", prompt_file.display())));
+        // Accepts the workspace trust prompt
+        assert_eq!(keys[1], "");
+
+        let _ = std::fs::remove_dir_all(&state_dir);
+    }
+}
@@ -0,0 +1,334 @@
+//! Joined view of everything pleb keeps for a repository (`pleb list`).
+//!
+//! Issues are gathered from three places that can drift apart: tmux windows,
+//! git worktrees and the pleb labels on GitHub. Each issue becomes one `Row`
+//! with the problems found between them, and `fix_for` picks the repair
+//! `pleb list --fix` applies.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use crate::config::RepoConfig;
+use crate::github::{GitHubClient, Issue, IssueRef};
+use crate::state::PlebState;
+use crate::session::{self, SessionBackend};
+use crate::worktree::WorktreeManager;
+
+/// An inconsistency between tmux, the worktrees and GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    /// A tmux window whose worktree is gone
+    WindowWithoutWorktree,
+    /// A worktree for an issue without any pleb label
+    WorktreeWithoutLabel,
+    /// A working, waiting or done label but no tmux window
+    LabelWithoutSession,
+    /// The issue's PR is merged but its window or worktree is still around
+    BranchMerged,
+}
+
+impl Problem {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Problem::WindowWithoutWorktree => "window with no worktree",
+            Problem::WorktreeWithoutLabel => "worktree with no pleb label",
+            Problem::LabelWithoutSession => "label with no session",
+            Problem::BranchMerged => "branch already merged",
+        }
+    }
+}
+
+/// How `--fix` repairs a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fix {
+    /// Transition to finished (if needed), then clean up as `pleb cleanup` would
+    FinishAndCleanUp,
+    /// Remove the worktree and window, as `pleb cleanup` would
+    CleanUp,
+    /// Recreate the missing window or worktree, as `pleb restore` would
+    Restore,
+}
+
+/// One issue as seen by tmux, git and GitHub
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    /// State from the issue's pleb label
+    pub state: Option<PlebState>,
+    pub window: bool,
+    pub worktree: Option<PathBuf>,
+    /// Whether one of the issue's PRs is merged; None if it has none or the check failed
+    pub pr_merged: Option<bool>,
+    pub problems: Vec<Problem>,
+}
+
+/// Problems between what tmux, git and GitHub say about one issue
+pub fn find_problems(
+    state: Option<PlebState>,
+    window: bool,
+    worktree: bool,
+    pr_merged: bool,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if window && !worktree {
+        problems.push(Problem::WindowWithoutWorktree);
+    }
+    if worktree && state.is_none() {
+        problems.push(Problem::WorktreeWithoutLabel);
+    }
+    let expects_session = matches!(
+        state,
+        Some(PlebState::Working | PlebState::Waiting | PlebState::Done)
+    );
+    if expects_session && !window {
+        problems.push(Problem::LabelWithoutSession);
+    }
+    if pr_merged && (window || worktree) {
+        problems.push(Problem::BranchMerged);
+    }
+    problems
+}
+
+/// The repair for a row's problems, if it has any
+pub fn fix_for(row: &Row) -> Option<Fix> {
+    if row.problems.is_empty() {
+        None
+    } else if row.problems.contains(&Problem::BranchMerged) {
+        Some(Fix::FinishAndCleanUp)
+    } else if row.state.is_none() {
+        // Nothing on GitHub says pleb should have a session here
+        Some(Fix::CleanUp)
+    } else {
+        Some(Fix::Restore)
+    }
+}
+
+/// Join the windows, worktrees and labelled issues of a repository into rows,
+/// ordered by issue number
+pub fn join(
+    windows: &BTreeSet<u64>,
+    worktrees: &BTreeMap<u64, PathBuf>,
+    issues: &BTreeMap<u64, (Issue, Option<PlebState>)>,
+    merged: &HashMap<u64, bool>,
+) -> Vec<Row> {
+    let numbers: BTreeSet<u64> = windows
+        .iter()
+        .chain(worktrees.keys())
+        .chain(issues.keys())
+        .copied()
+        .collect();
+
+    numbers
+        .into_iter()
+        .map(|number| {
+            let issue = issues.get(&number);
+            let state = issue.and_then(|(_, state)| *state);
+            let window = windows.contains(&number);
+            let worktree = worktrees.get(&number).cloned();
+            let pr_merged = merged.get(&number).copied();
+            Row {
+                number,
+                title: issue.map(|(i, _)| i.title.clone()).unwrap_or_default(),
+                url: issue.map(|(i, _)| i.html_url.clone()).unwrap_or_default(),
+                state,
+                window,
+                pr_merged,
+                problems: find_problems(
+                    state,
+                    window,
+                    worktree.is_some(),
+                    pr_merged.unwrap_or(false),
+                ),
+                worktree,
+            }
+        })
+        .collect()
+}
+
+/// Gather the rows for one repository. Issues in `headless` have no windows
+/// to expect; `tmux` is None when no issue does.
+pub async fn scan(
+    repo: &RepoConfig,
+    github: &GitHubClient,
+    tmux: Option<&dyn SessionBackend>,
+    headless: &BTreeSet<u64>,
+    worktree: &WorktreeManager,
+) -> Result<Vec<Row>> {
+    let windows: BTreeSet<u64> = match tmux {
+        Some(tmux) => tmux
+            .list_windows()
+            .await?
+            .into_iter()
+            .filter(|window| {
+                session::window_matches(
+                    window,
+                    &IssueRef::new(&repo.github.owner, &repo.github.repo, window.number),
+                )
+            })
+            .map(|window| window.number)
+            .collect(),
+        None => BTreeSet::new(),
+    };
+
+    let worktrees: BTreeMap<u64, PathBuf> = match worktree.list_worktrees().await {
+        Ok(numbers) => numbers
+            .into_iter()
+            .filter_map(|n| Some((n, worktree.get_worktree_path(n)?)))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to list worktrees for {}: {:#}", repo.full_name(), e);
+            BTreeMap::new()
+        }
+    };
+
+    // Open issues carrying a pleb label (ready issues have no session yet)
+    let labels = &repo.labels;
+    let mut issues = BTreeMap::new();
+    for label in [
+        &labels.provisioning,
+        &labels.waiting,
+        &labels.working,
+        &labels.done,
+        &labels.finished,
+    ] {
+        for issue in github.get_issues_with_label(label).await? {
+            let state = github.get_pleb_state(&issue, labels);
+            issues.insert(issue.number, (issue, state));
+        }
+    }
+
+    // Windows and worktrees for issues that lost their label or were closed
+    let unlabelled: Vec<u64> = windows
+        .iter()
+        .chain(worktrees.keys())
+        .filter(|n| !issues.contains_key(n))
+        .copied()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    for number in unlabelled {
+        match github.get_issue(number).await {
+            Ok(issue) => {
+                let state = github.get_pleb_state(&issue, labels);
+                issues.insert(number, (issue, state));
+            }
+            Err(e) => tracing::warn!("Failed to fetch issue {}: {:#}", github.issue_ref(number), e),
+        }
+    }
+
+    // Issue worktrees are named after their branch
+    let lookups: Vec<(u64, Option<&str>)> = issues
+        .keys()
+        .map(|number| {
+            let branch = worktrees
+                .get(number)
+                .and_then(|path| path.file_name())
+                .and_then(|name| name.to_str());
+            (*number, branch)
+        })
+        .collect();
+    let merged = github.check_prs_merged(&lookups).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to check PR merge status: {:#}", e);
+        HashMap::new()
+    });
+
+    let mut rows = join(&windows, &worktrees, &issues, &merged);
+    for row in &mut rows {
+        if tmux.is_none() || headless.contains(&row.number) {
+            row.problems.retain(|p| *p != Problem::LabelWithoutSession);
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::IssueState;
+
+    fn issue(number: u64) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {}", number),
+            body: String::new(),
+            labels: Vec::new(),
+            state: IssueState::Open,
+            html_url: format!("https://github.com/acme/api/issues/{}", number),
+            author: "alice".to_string(),
+            created_at: 0,
+            milestone_due_on: None,
+        }
+    }
+
+    #[test]
+    fn test_find_problems() {
+        use PlebState::*;
+
+        // Everything in place
+        assert!(find_problems(Some(Working), true, true, false).is_empty());
+        // Ready and finished issues don't need a session
+        assert!(find_problems(Some(Ready), false, false, false).is_empty());
+        assert!(find_problems(Some(Finished), false, false, true).is_empty());
+
+        assert_eq!(
+            find_problems(Some(Waiting), true, false, false),
+            vec![Problem::WindowWithoutWorktree]
+        );
+        assert_eq!(
+            find_problems(None, false, true, false),
+            vec![Problem::WorktreeWithoutLabel]
+        );
+        assert_eq!(
+            find_problems(Some(Done), false, true, false),
+            vec![Problem::LabelWithoutSession]
+        );
+        assert_eq!(
+            find_problems(Some(Done), true, true, true),
+            vec![Problem::BranchMerged]
+        );
+    }
+
+    #[test]
+    fn test_join_and_fix() {
+        let windows: BTreeSet<u64> = [1, 2, 5].into();
+        let worktrees: BTreeMap<u64, PathBuf> = [
+            (1, PathBuf::from("/work/1-a")),
+            (3, PathBuf::from("/work/3-c")),
+            (5, PathBuf::from("/work/5-e")),
+        ]
+        .into();
+        let issues: BTreeMap<u64, (Issue, Option<PlebState>)> = [
+            (1, (issue(1), Some(PlebState::Working))),
+            (2, (issue(2), Some(PlebState::Waiting))),
+            (3, (issue(3), None)),
+            (4, (issue(4), Some(PlebState::Working))),
+            (5, (issue(5), Some(PlebState::Done))),
+        ]
+        .into();
+        let merged: HashMap<u64, bool> = [(1, false), (5, true)].into();
+
+        let rows = join(&windows, &worktrees, &issues, &merged);
+        let summary: Vec<(u64, Vec<Problem>, Option<Fix>)> = rows
+            .iter()
+            .map(|row| (row.number, row.problems.clone(), fix_for(row)))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (1, vec![], None),
+                (2, vec![Problem::WindowWithoutWorktree], Some(Fix::Restore)),
+                (3, vec![Problem::WorktreeWithoutLabel], Some(Fix::CleanUp)),
+                (4, vec![Problem::LabelWithoutSession], Some(Fix::Restore)),
+                (5, vec![Problem::BranchMerged], Some(Fix::FinishAndCleanUp)),
+            ]
+        );
+        assert_eq!(rows[0].title, "Issue 1");
+        assert_eq!(rows[2].worktree, Some(PathBuf::from("/work/3-c")));
+    }
+}
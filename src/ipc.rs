@@ -2,20 +2,60 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedReadHalf;
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+
+use crate::state::TrackedIssue;
+use crate::telemetry;
 
 /// Message from hook to daemon
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookMessage {
     /// The raw Claude Code event name (e.g., "UserPromptSubmit", "Stop", "PostToolUse", "PermissionRequest")
     pub event_name: String,
+    /// Repository owner of the issue, resolved from the cwd
+    #[serde(default)]
+    pub owner: String,
+    /// Repository name of the issue, resolved from the cwd
+    #[serde(default)]
+    pub repo: String,
     /// Issue number extracted from the cwd
     pub issue_number: u64,
     /// Full JSON payload from Claude Code hook stdin
     pub payload: serde_json::Value,
 }
 
+/// A line sent to the daemon socket: either a hook message or a status subscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IpcRequest {
+    Hook(HookMessage),
+    /// Ask the daemon to stream a `StatusSnapshot` line whenever its state changes
+    WatchStatus { watch_status: bool },
+}
+
+/// The daemon's view of every watched repository, streamed to `pleb ui`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    pub pid: u32,
+    /// Unix timestamp (seconds) when the daemon started
+    pub started_at: u64,
+    /// Remaining GitHub rate limit, as shown by `pleb status`
+    pub rate_budget: String,
+    pub repos: Vec<RepoSnapshot>,
+}
+
+/// Tracked issues of one repository
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoSnapshot {
+    pub owner: String,
+    pub repo: String,
+    pub issues: Vec<TrackedIssue>,
+    /// Queued issue numbers, in the order they will be started
+    pub queued: Vec<u64>,
+}
+
 /// Response from daemon to hook
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookResponse {
@@ -26,13 +66,24 @@ pub struct HookResponse {
 /// Server that listens for hook messages
 pub struct IpcServer {
     socket_path: PathBuf,
+    /// Latest status, streamed to subscribers
+    status: Option<watch::Receiver<StatusSnapshot>>,
 }
 
 impl IpcServer {
     /// Create a new IPC server (doesn't start listening yet)
     pub fn new(daemon_dir: &Path) -> Self {
         let socket_path = daemon_dir.join("pleb.sock");
-        Self { socket_path }
+        Self {
+            socket_path,
+            status: None,
+        }
+    }
+
+    /// Serve status subscriptions from this channel
+    pub fn with_status(mut self, status: watch::Receiver<StatusSnapshot>) -> Self {
+        self.status = Some(status);
+        self
     }
 
     /// Get the socket path
@@ -63,20 +114,24 @@ impl IpcServer {
         // Start accept loop in background
         let (tx, rx) = mpsc::channel(32);
         let socket_path = self.socket_path.clone();
+        let status = self.status.clone();
 
         tokio::spawn(async move {
             loop {
                 match listener.accept().await {
                     Ok((stream, _)) => {
                         let tx = tx.clone();
+                        let status = status.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_connection(stream, tx).await {
+                            if let Err(e) = handle_connection(stream, tx, status).await {
                                 tracing::warn!("Error handling IPC connection: {}", e);
+                                metrics::counter!(telemetry::IPC_CONNECTION_ERRORS).increment(1);
                             }
                         });
                     }
                     Err(e) => {
                         tracing::error!("Error accepting IPC connection: {}", e);
+                        metrics::counter!(telemetry::IPC_CONNECTION_ERRORS).increment(1);
                         break;
                     }
                 }
@@ -98,15 +153,34 @@ impl Drop for IpcServer {
     }
 }
 
-async fn handle_connection(mut stream: UnixStream, tx: mpsc::Sender<HookMessage>) -> Result<()> {
+async fn handle_connection(
+    mut stream: UnixStream,
+    tx: mpsc::Sender<HookMessage>,
+    status: Option<watch::Receiver<StatusSnapshot>>,
+) -> Result<()> {
     let (reader, mut writer) = stream.split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
     reader.read_line(&mut line).await?;
 
-    let message: HookMessage = serde_json::from_str(line.trim())
-        .context("Failed to parse hook message")?;
+    let request: IpcRequest = serde_json::from_str(line.trim())
+        .context("Failed to parse IPC message")?;
+    let message = match request {
+        IpcRequest::Hook(message) => message,
+        IpcRequest::WatchStatus { .. } => {
+            let mut status = status.context("Daemon does not serve status")?;
+            // Send the current status, then every update until either side goes away
+            loop {
+                let snapshot_json = serde_json::to_string(&*status.borrow_and_update())?;
+                writer.write_all(snapshot_json.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                if status.changed().await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    };
 
     tracing::debug!("Received hook message: {:?}", message);
 
@@ -136,16 +210,22 @@ async fn handle_connection(mut stream: UnixStream, tx: mpsc::Sender<HookMessage>
 }
 
 /// Client for sending messages to the daemon from hooks
+/// Environment variable naming the daemon socket to connect to
+pub const SOCKET_ENV: &str = "PLEB_SOCKET";
+
 pub struct IpcClient {
     socket_path: PathBuf,
 }
 
 impl IpcClient {
-    /// Create a client from a daemon directory
+    /// Create a client from a daemon directory. `PLEB_SOCKET` overrides the
+    /// socket path, for hooks running in a sandbox that forwards the socket.
     pub fn new(daemon_dir: &Path) -> Self {
-        Self {
-            socket_path: daemon_dir.join("pleb.sock"),
-        }
+        let socket_path = std::env::var_os(SOCKET_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| daemon_dir.join("pleb.sock"));
+        Self { socket_path }
     }
 
     /// Send a hook message to the daemon
@@ -168,6 +248,41 @@ impl IpcClient {
 
         Ok(response)
     }
+
+    /// Subscribe to the daemon's status updates
+    pub async fn watch_status(&self) -> Result<StatusStream> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .await
+            .with_context(|| format!("Failed to connect to daemon socket: {:?}", self.socket_path))?;
+
+        let request_json = serde_json::to_string(&IpcRequest::WatchStatus { watch_status: true })?;
+        stream.write_all(request_json.as_bytes()).await?;
+        stream.write_all(b"\n").await?;
+
+        let (reader, _) = stream.into_split();
+        Ok(StatusStream {
+            reader: BufReader::new(reader),
+        })
+    }
+}
+
+/// Status updates streamed from the daemon
+pub struct StatusStream {
+    reader: BufReader<OwnedReadHalf>,
+}
+
+impl StatusStream {
+    /// Wait for the next snapshot. Returns None once the daemon closes the stream.
+    pub async fn next(&mut self) -> Result<Option<StatusSnapshot>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let snapshot = serde_json::from_str(line.trim())
+            .context("Failed to parse daemon status")?;
+        Ok(Some(snapshot))
+    }
 }
 
 #[cfg(test)]
@@ -190,6 +305,8 @@ mod tests {
         });
         let message = HookMessage {
             event_name: "UserPromptSubmit".to_string(),
+            owner: "acme".to_string(),
+            repo: "api".to_string(),
             issue_number: 42,
             payload,
         };
@@ -202,6 +319,7 @@ mod tests {
         // Receive on server
         let received: HookMessage = rx.recv().await.unwrap();
         assert_eq!(received.issue_number, 42);
+        assert_eq!((received.owner.as_str(), received.repo.as_str()), ("acme", "api"));
         assert_eq!(received.event_name, "UserPromptSubmit");
         assert_eq!(received.payload["session_id"], "test-session");
 
@@ -212,4 +330,39 @@ mod tests {
         // Cleanup
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[tokio::test]
+    async fn test_watch_status() {
+        let dir = std::env::temp_dir().join(format!("pleb-test-status-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let (status_tx, status_rx) = watch::channel(StatusSnapshot {
+            pid: 1234,
+            ..Default::default()
+        });
+        let mut server = IpcServer::new(&dir).with_status(status_rx);
+        let _rx = server.start().await.unwrap();
+
+        let mut stream = IpcClient::new(&dir).watch_status().await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().pid, 1234);
+
+        status_tx.send_replace(StatusSnapshot {
+            pid: 1234,
+            repos: vec![RepoSnapshot {
+                owner: "acme".to_string(),
+                repo: "api".to_string(),
+                queued: vec![7],
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let snapshot = stream.next().await.unwrap().unwrap();
+        assert_eq!(snapshot.repos[0].queued, vec![7]);
+
+        // The stream ends when the daemon goes away
+        drop(status_tx);
+        assert!(stream.next().await.unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -1,30 +1,59 @@
-mod claude;
+mod agent;
 mod cli;
 mod commands;
 mod config;
+mod dashboard;
+mod draft;
 mod github;
+mod headless;
 mod hooks;
+mod interactive;
+mod inventory;
 mod ipc;
 mod media;
+mod output;
+mod quick;
+mod ratelimit;
+mod sandbox;
+mod scheduler;
+mod screen;
+mod session;
 mod state;
+mod status_comment;
+mod telemetry;
 mod templates;
 mod tmux;
+mod transcript;
+mod transition;
+mod ui;
+mod usage;
+mod webhook;
 mod worktree;
+mod zellij;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::Path;
+use std::sync::Arc;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use cli::{Cli, Commands, ConfigAction, HooksAction};
-use config::Config;
-use tmux::TmuxManager;
+use cli::{Cli, Commands, ConfigAction, HooksAction, OutputFormat, ReportKind};
+use agent::Agent;
+use config::{CommentsConfig, Config, CostsConfig, LabelConfig, RepoConfig, StateSource};
+use github::IssueRef;
+use session::{SessionBackend, WindowTarget};
 use github::GitHubClient;
+use quick::{QuickRef, QuickTracker, SessionKey};
+use sandbox::Sandbox;
 use worktree::WorktreeManager;
-use claude::ClaudeRunner;
-use templates::{TemplateEngine, IssueContext};
-use state::PlebState;
+use interactive::InteractiveRunner;
+use headless::{HeadlessEvent, HeadlessRunner, HeadlessUpdate};
+use templates::{CommentContext, CommentsContext, TemplateEngine, IssueContext};
+use state::{InvalidTransition, IssueTracker, PlebState};
+use status_comment::StatusCommenter;
+use transition::TransitionEngine;
 
 /// Convert a string to a URL-safe slug
 /// - Converts to lowercase
@@ -72,20 +101,29 @@ fn slugify(s: &str, max_len: usize) -> String {
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Refuse to expose the dashboard by accident, before daemonizing hides the error
+    if let Commands::Watch { http: Some(listen), http_public, .. } = &cli.command {
+        dashboard::check_listen(*listen, *http_public, "pass --http-public")?;
+    }
+
     // Handle daemon mode specially - must fork BEFORE creating tokio runtime
-    if let Commands::Watch { daemon: true } = &cli.command {
+    if let Commands::Watch { daemon: true, http, .. } = &cli.command {
         let config = load_config(&cli.config)?;
-        return run_daemon_mode(config, cli.verbose);
+        return run_daemon_mode(config, *http, cli.verbose);
     }
 
-    // Initialize tracing for non-daemon modes
+    // Initialize tracing for non-daemon modes (on stderr when stdout carries JSON)
     let log_level = if cli.verbose { "pleb=debug" } else { "pleb=info" };
+    let log_writer = match cli.format {
+        OutputFormat::Table => BoxMakeWriter::new(std::io::stdout),
+        OutputFormat::Json => BoxMakeWriter::new(std::io::stderr),
+    };
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| log_level.into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(log_writer))
         .init();
 
     // Create tokio runtime for async operations
@@ -112,7 +150,7 @@ fn main() -> Result<()> {
         _ => {
             // For all other commands, load and validate config
             let config = load_config(&cli.config)?;
-            runtime.block_on(handle_command(cli.command, config))?;
+            runtime.block_on(handle_command(cli.command, cli.repo.as_deref(), cli.format, config))?;
         }
     }
 
@@ -167,102 +205,462 @@ fn load_config(path: &str) -> Result<Config> {
     Ok(config)
 }
 
+/// Build an issue tracker from the pleb labels currently on GitHub.
+/// Used to seed the state file the first time it is needed (e.g. after upgrading
+/// from a version that only tracked state through labels).
+async fn import_tracker_from_labels(
+    github: &GitHubClient,
+    worktree: &WorktreeManager,
+    labels: &LabelConfig,
+) -> Result<IssueTracker> {
+    let mut tracker = IssueTracker::new();
+
+    let managed_labels = [
+        &labels.provisioning,
+        &labels.waiting,
+        &labels.working,
+        &labels.done,
+        &labels.finished,
+    ];
 
-/// Orchestrator that manages the main daemon loop
-/// State is derived from GitHub labels - minimal in-memory tracking
-struct Orchestrator {
+    for label in managed_labels {
+        let issues = github.get_issues_with_label(label).await?;
+        for issue in issues {
+            let Some(state) = github.get_pleb_state(&issue, labels) else {
+                continue;
+            };
+
+            let worktree_path = worktree.get_worktree_path(issue.number);
+            let branch_name = worktree_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string());
+
+            let tracked = tracker.track(issue.number, state);
+            tracked.title = issue.title.clone();
+            tracked.html_url = issue.html_url.clone();
+            tracked.worktree_path = worktree_path;
+            tracked.tmux_window = branch_name.clone();
+            tracked.branch_name = branch_name;
+        }
+    }
+
+    tracing::info!("Imported {} managed issue(s) from GitHub labels", tracker.all().len());
+
+    Ok(tracker)
+}
+
+/// Write the loaded config into a worktree so `pleb` commands and hooks run
+/// there find it. Paths are already resolved to absolute paths, so they keep
+/// pointing at the right places from inside the worktree.
+fn write_worktree_config(config: &Config, worktree_path: &Path, issue: &impl std::fmt::Display) {
+    let result = toml::to_string_pretty(config)
+        .context("Failed to serialize config")
+        .and_then(|contents| {
+            std::fs::write(worktree_path.join("pleb.toml"), contents)
+                .context("Failed to write pleb.toml")
+        });
+
+    match result {
+        Ok(()) => tracing::debug!("Wrote pleb.toml to worktree for issue {}", issue),
+        Err(e) => tracing::warn!("Failed to write pleb.toml to worktree for issue {}: {}", issue, e),
+    }
+}
+
+/// The agent working on a tracked issue: the one recorded when it was
+/// provisioned, or the repository's default for records without one
+fn tracked_agent(config: &Config, repo: &RepoConfig, tracked: Option<&state::TrackedIssue>) -> Result<Agent> {
+    let name = tracked
+        .and_then(|t| t.agent.as_deref())
+        .unwrap_or(&repo.agent.default);
+    config.agent(name)
+}
+
+/// The session backend interactive agents run in
+fn session_backend(tmux: Option<&Arc<dyn SessionBackend>>) -> Result<Arc<dyn SessionBackend>> {
+    tmux.cloned().context("No session backend for interactive agents")
+}
+
+/// Issues of a repo whose agent runs headless (and so have no window)
+fn headless_issues(config: &Config, repo: &RepoConfig, tracker: &IssueTracker) -> BTreeSet<u64> {
+    tracker
+        .all()
+        .into_iter()
+        .filter(|t| tracked_agent(config, repo, Some(t)).is_ok_and(|agent| agent.is_headless()))
+        .map(|t| t.issue_number)
+        .collect()
+}
+
+/// Sandbox instances used by a repo's sessions other than `except`'s
+fn sandboxes_in_use(tracker: &IssueTracker, except: u64) -> Vec<String> {
+    tracker
+        .all()
+        .into_iter()
+        .filter(|t| t.issue_number != except && t.state != PlebState::Finished)
+        .filter_map(|t| t.sandbox.clone())
+        .collect()
+}
+
+/// Clients and issue state for one watched repository
+struct RepoContext {
+    config: RepoConfig,
     github: GitHubClient,
     worktree: WorktreeManager,
-    tmux: TmuxManager,
-    claude: ClaudeRunner,
     templates: TemplateEngine,
+    /// Directory holding the state file and per-issue directories (prompts, media)
+    state_dir: std::path::PathBuf,
+    /// Persistent record of every managed issue in this repo
+    tracker: IssueTracker,
+    /// Path to the state file backing `tracker`
+    state_file: std::path::PathBuf,
+    /// Track issues we've already logged as "skipping" to avoid log spam
+    logged_skips: HashSet<u64>,
+    /// Keep a status comment on each issue (`comments.status_comment`)
+    status_comment: bool,
+    /// Prices for the usage shown in status comments, if `costs.in_status_comment`
+    costs: Option<CostsConfig>,
+}
+
+impl RepoContext {
+    async fn new(config: RepoConfig, comments: &CommentsConfig, costs: &CostsConfig) -> Result<Self> {
+        let github = GitHubClient::new(&config.github).await?;
+        let worktree = WorktreeManager::new(&config.paths);
+        let mut templates = TemplateEngine::new(&config.prompts)?;
+        if comments.status_comment {
+            templates.load_status_comment_template(&config.prompts.status_comment)?;
+        }
+
+        // Reload persisted issue state from the previous run
+        let state_dir = config.state_dir()?;
+        let state_file = config.state_file()?;
+        let tracker = IssueTracker::load(&state_file)?;
+        tracing::info!(
+            "Loaded {} tracked issue(s) for {} from {}",
+            tracker.all().len(),
+            config.full_name(),
+            state_file.display()
+        );
+
+        Ok(Self {
+            config,
+            github,
+            worktree,
+            templates,
+            state_dir,
+            tracker,
+            state_file,
+            logged_skips: HashSet::new(),
+            status_comment: comments.status_comment,
+            costs: costs.in_status_comment.then(|| costs.clone()),
+        })
+    }
+
+    /// Identify an issue in this repository
+    fn issue_ref(&self, number: u64) -> IssueRef {
+        self.github.issue_ref(number)
+    }
+
+    /// Transition engine bound to this repo's GitHub client and state file
+    fn transitions(&self) -> TransitionEngine<'_> {
+        let engine = TransitionEngine::new(&self.github, &self.config.labels, &self.state_file);
+        if self.status_comment {
+            let commenter = StatusCommenter::new(
+                &self.github,
+                &self.templates,
+                &self.config.prompts.status_comment,
+            );
+            engine.with_status_comment(match &self.costs {
+                Some(costs) => commenter.with_costs(costs),
+                None => commenter,
+            })
+        } else {
+            engine
+        }
+    }
+
+    /// Recount a session transcript into the issue's token usage
+    fn record_usage(&self, issue_number: u64, transcript: &Path) {
+        let title = self.tracker.get(issue_number).map(|t| t.title.as_str()).unwrap_or_default();
+        let issue_dir = self.state_dir.join(issue_number.to_string());
+        if let Err(e) = usage::record(&issue_dir, title, transcript) {
+            tracing::warn!("Failed to record usage for issue {}: {}", self.issue_ref(issue_number), e);
+        }
+    }
+
+    /// Reload the issue tracker from the state file
+    fn reload_tracker(&mut self) {
+        match IssueTracker::load(&self.state_file) {
+            Ok(tracker) => self.tracker = tracker,
+            Err(e) => tracing::warn!("Failed to reload issue state, using in-memory copy: {}", e),
+        }
+    }
+
+    /// Apply a change to the issue tracker and persist it.
+    /// The state file is reloaded under its lock first so changes made by CLI
+    /// commands (`pleb transition`, `pleb cleanup`) are not overwritten.
+    fn update_tracker(&mut self, f: impl FnOnce(&mut IssueTracker)) {
+        let updated = IssueTracker::update(&self.state_file, |t| {
+            f(t);
+            t.clone()
+        });
+        match updated {
+            Ok(tracker) => self.tracker = tracker,
+            Err(e) => tracing::error!("Failed to save issue state: {}", e),
+        }
+    }
+}
+
+/// Orchestrator that manages the main daemon loop for every watched repository
+/// Issue state is mirrored to GitHub labels and persisted locally in each repo's state file
+struct Orchestrator {
+    repos: Vec<RepoContext>,
+    /// Multiplexer holding the windows of interactive agents; None when every
+    /// agent runs headless
+    tmux: Option<Arc<dyn SessionBackend>>,
+    /// Where Claude Code sessions run (`[sandbox]`)
+    sandbox: Sandbox,
+    /// Runs agents with `mode = "headless"` as child processes
+    headless: HeadlessRunner,
+    /// Events from headless runs, taken by `run`
+    headless_rx: Option<tokio::sync::mpsc::UnboundedReceiver<HeadlessUpdate>>,
     config: Config,
     /// The authenticated GitHub username
     gh_username: String,
     /// GitHub token for API calls requiring custom headers
     gh_token: String,
-    /// Track issues we've already logged as "skipping" to avoid log spam
-    logged_skips: HashSet<u64>,
     /// IPC server for receiving hook messages
     ipc_server: ipc::IpcServer,
+    /// Status streamed to `pleb ui` through the IPC socket
+    status_tx: tokio::sync::watch::Sender<ipc::StatusSnapshot>,
+    /// Unix timestamp when the orchestrator started
+    started_at: u64,
     /// HTTP client for downloading media from issues
     media_client: reqwest::Client,
+    /// GitHub webhook listener, if enabled
+    webhook_server: Option<webhook::WebhookServer>,
+    /// Prometheus metrics listener, if enabled
+    metrics_server: Option<telemetry::MetricsServer>,
+    /// Read-only web dashboard, if enabled with `watch --http`
+    dashboard: Option<dashboard::DashboardServer>,
+    /// Unix timestamp until which polling is paused to save rate limit
+    rate_limited_until: Option<u64>,
+    /// Poll at the next turn of the loop instead of waiting for the interval
+    /// (set by webhook events that may have made an issue ready)
+    poll_requested: bool,
 }
 
 impl Orchestrator {
     async fn new(config: Config) -> Result<Self> {
-        let github = GitHubClient::new(&config.github).await?;
-        let worktree = WorktreeManager::new(&config.paths);
-
-        // Create TmuxManager with GitHub token passed as environment variable
-        // This ensures hooks running in tmux have access to the token
+        // Create the session backend with GitHub token passed as environment variable
+        // This ensures hooks running in the session have access to the token
         let token = std::env::var(&config.github.token_env)
             .with_context(|| format!("Missing environment variable: {}", config.github.token_env))?;
-        let tmux = TmuxManager::new(&config.tmux)
-            .with_env(&config.github.token_env, token.clone());
+        let tmux = session::issue_backend(&config, vec![(config.github.token_env.clone(), token)]);
 
-        let claude = ClaudeRunner::new(&config.claude, &config.tmux);
-        let templates = TemplateEngine::new(&config.prompts)?;
+        Self::with_backend(config, tmux).await
+    }
+
+    /// Create the orchestrator with the given session backend
+    async fn with_backend(config: Config, tmux: Option<Arc<dyn SessionBackend>>) -> Result<Self> {
+        let mut repos = Vec::new();
+        for repo_config in config.repos() {
+            repos.push(RepoContext::new(repo_config, &config.comments, &config.costs).await?);
+        }
+
+        let token = std::env::var(&config.github.token_env)
+            .with_context(|| format!("Missing environment variable: {}", config.github.token_env))?;
+        let (headless, headless_rx) = HeadlessRunner::new();
 
         // Create IPC server for hook messages
         let daemon_dir = config.daemon_dir()?;
-        let ipc_server = ipc::IpcServer::new(&daemon_dir);
+        let (status_tx, status_rx) = tokio::sync::watch::channel(ipc::StatusSnapshot::default());
+        let ipc_server = ipc::IpcServer::new(&daemon_dir).with_status(status_rx);
 
         // Create HTTP client for media downloads (needs auth for private repos)
         let media_client = media::create_media_client(&token)?;
 
-        // Fetch authenticated user
-        let gh_username = github.get_authenticated_user().await?;
+        // Create webhook listener if configured
+        let webhook_server = match &config.webhook.listen {
+            Some(listen) => {
+                let secret = std::env::var(&config.webhook.secret_env).with_context(|| {
+                    format!("Missing environment variable: {}", config.webhook.secret_env)
+                })?;
+                Some(webhook::WebhookServer::new(listen, secret))
+            }
+            None => None,
+        };
+
+        // Install the metrics recorder before anything is recorded
+        let metrics_server = match &config.metrics.listen {
+            Some(listen) => Some(telemetry::MetricsServer::new(listen, telemetry::install()?)),
+            None => None,
+        };
+
+        // Fetch authenticated user (the same token is used for every repo)
+        let gh_username = repos
+            .first()
+            .context("No repositories configured")?
+            .github
+            .get_authenticated_user()
+            .await?;
         tracing::info!("Authenticated as GitHub user: {}", gh_username);
 
+        let sandbox = Sandbox::new(&config)?;
+
         Ok(Self {
-            github,
-            worktree,
+            repos,
             tmux,
-            claude,
-            templates,
+            sandbox,
+            headless,
+            headless_rx: Some(headless_rx),
             config,
             gh_username,
             gh_token: token,
-            logged_skips: HashSet::new(),
             ipc_server,
+            status_tx,
+            started_at: state::unix_now(),
             media_client,
+            webhook_server,
+            metrics_server,
+            dashboard: None,
+            rate_limited_until: None,
+            poll_requested: false,
         })
     }
 
+    /// Serve the read-only dashboard on `listen`, if given
+    fn with_dashboard(mut self, listen: Option<std::net::SocketAddr>) -> Result<Self> {
+        if let Some(listen) = listen {
+            self.dashboard = Some(dashboard::DashboardServer::new(
+                listen,
+                self.config.repos(),
+                self.tmux.clone(),
+                self.config.log_file()?,
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Send the current state of every repo to `pleb ui` subscribers
+    fn publish_status(&mut self) {
+        let mut repos = Vec::new();
+        for repo in &mut self.repos {
+            // Pick up changes made by CLI commands
+            repo.reload_tracker();
+            repos.push(ipc::RepoSnapshot {
+                owner: repo.config.github.owner.clone(),
+                repo: repo.config.github.repo.clone(),
+                issues: repo.tracker.all().into_iter().cloned().collect(),
+                queued: repo.tracker.queued(),
+            });
+        }
+
+        self.status_tx.send_replace(ipc::StatusSnapshot {
+            pid: std::process::id(),
+            started_at: self.started_at,
+            rate_budget: ratelimit::format_budget(&ratelimit::snapshot(), ratelimit::now()),
+            repos,
+        });
+    }
+
+    /// Index of the repo a hook message belongs to.
+    /// Messages without a repo (from older hook binaries) map to the only repo, if there is one.
+    fn repo_index(&self, owner: &str, repo: &str) -> Option<usize> {
+        if owner.is_empty() && repo.is_empty() && self.repos.len() == 1 {
+            return Some(0);
+        }
+
+        self.repos
+            .iter()
+            .position(|r| r.config.github.owner == owner && r.config.github.repo == repo)
+    }
+
+    /// The agent working on an issue of a repo
+    fn issue_agent(&self, index: usize, issue_number: u64) -> Result<Agent> {
+        let repo = &self.repos[index];
+        tracked_agent(&self.config, &repo.config, repo.tracker.get(issue_number))
+    }
+
     async fn run(&mut self) -> Result<()> {
-        // Verify GitHub connection
-        tracing::info!("Verifying GitHub connection...");
-        self.github.verify_connection().await?;
-
-        // Ensure repo is cloned
-        tracing::info!("Ensuring repository is cloned...");
-        self.worktree
-            .ensure_repo(&self.config.github.owner, &self.config.github.repo)
-            .await?;
+        for repo in &mut self.repos {
+            // Verify GitHub connection
+            tracing::info!("Verifying GitHub connection for {}...", repo.config.full_name());
+            repo.github.verify_connection().await?;
+
+            // Seed the state file from GitHub labels if there is nothing tracked yet
+            if repo.tracker.is_empty() {
+                match import_tracker_from_labels(&repo.github, &repo.worktree, &repo.config.labels).await {
+                    Ok(tracker) => repo.update_tracker(|t| *t = tracker),
+                    Err(e) => tracing::warn!("Failed to import issue state from labels: {}", e),
+                }
+            }
+
+            // Ensure repo is cloned
+            tracing::info!("Ensuring repository {} is cloned...", repo.config.full_name());
+            repo.worktree
+                .ensure_repo(&repo.config.github.owner, &repo.config.github.repo)
+                .await?;
+
+            // Load the new_issue template
+            tracing::info!("Loading templates...");
+            repo.templates
+                .load_template(&repo.config.prompts.new_issue)?;
+            if self.config.comments.feed_to_session {
+                repo.templates
+                    .load_comment_template(&repo.config.prompts.comment)?;
+            }
+        }
 
-        // Load the new_issue template
-        tracing::info!("Loading templates...");
-        self.templates
-            .load_template(&self.config.prompts.new_issue)?;
+        // Headless runs ended with the previous daemon
+        for index in 0..self.repos.len() {
+            if let Err(e) = self.interrupt_headless_runs(index).await {
+                tracing::error!("Failed to update interrupted headless runs: {:#}", e);
+            }
+        }
+        let mut headless_rx = self.headless_rx.take();
 
         // Start IPC server for hook messages
         tracing::info!("Starting IPC server...");
         let mut ipc_rx = self.ipc_server.start().await?;
 
+        // Start webhook listener; polling then only runs as a slow fallback
+        let mut webhook_rx = match &self.webhook_server {
+            Some(server) => Some(server.start().await?.1),
+            None => None,
+        };
+
+        if let Some(server) = &self.metrics_server {
+            server.start().await?;
+        }
+        if let Some(server) = &self.dashboard {
+            server.start().await?;
+        }
+
         // Display startup banner
-        tracing::info!(
-            "Watching {}/{} for issues with label '{}'",
-            self.config.github.owner, self.config.github.repo, self.config.labels.ready
-        );
+        for repo in &self.repos {
+            tracing::info!(
+                "Watching {} for issues with label '{}'",
+                repo.config.full_name(),
+                repo.config.labels.ready
+            );
+        }
 
         // Enter polling loop
-        let poll_interval = std::time::Duration::from_secs(self.config.watch.poll_interval_secs);
+        let poll_interval = std::time::Duration::from_secs(match webhook_rx {
+            Some(_) => self.config.webhook.fallback_poll_interval_secs,
+            None => self.config.watch.poll_interval_secs,
+        });
+
+        // The interval arm only ticks; the cycle runs in its handler, which
+        // other events can't cancel halfway through provisioning an issue
+        let mut poll_timer = tokio::time::interval(poll_interval);
+        poll_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         // Create ctrl_c future once, outside the loop
         let ctrl_c = tokio::signal::ctrl_c();
         tokio::pin!(ctrl_c);
 
+        self.publish_status();
         loop {
             tokio::select! {
                 biased;
@@ -276,185 +674,607 @@ impl Orchestrator {
                     if let Err(e) = self.handle_hook_message(msg).await {
                         tracing::error!("Error handling hook message: {}", e);
                     }
+                    self.publish_status();
                 }
-                _ = async {
-                    if let Err(e) = self.poll_cycle().await {
-                        tracing::error!("Poll cycle error: {}", e);
+                Some(event) = async {
+                    match webhook_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
                     }
-
-                    // Check for merged PRs on active issues
-                    if let Err(e) = self.check_merged_prs().await {
-                        tracing::error!("Check merged PRs error: {}", e);
+                } => {
+                    if let Err(e) = self.handle_webhook_event(event).await {
+                        tracing::error!("Error handling webhook event: {}", e);
+                    }
+                    self.publish_status();
+                }
+                Some(update) = async {
+                    match headless_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
                     }
-
-                    tokio::time::sleep(poll_interval).await;
                 } => {
-                    // Continue to next cycle
+                    if let Err(e) = self.handle_headless_update(update).await {
+                        tracing::error!("Error handling headless run event: {}", e);
+                    }
+                    self.publish_status();
+                }
+                _ = poll_timer.tick() => {
+                    match self.rate_limit_backoff().await {
+                        Some(wait) => poll_timer.reset_after(std::time::Duration::from_secs(wait)),
+                        None => self.poll_once().await,
+                    }
                 }
             }
+
+            if std::mem::take(&mut self.poll_requested) {
+                poll_timer.reset_immediately();
+            }
         }
 
         Ok(())
     }
 
     /// Handle a message from a Claude Code hook
-    async fn handle_hook_message(&self, msg: ipc::HookMessage) -> Result<()> {
-        let target_state = match msg.event_name.as_str() {
-            "UserPromptSubmit" => PlebState::Working,
-            "Stop" | "PermissionRequest" => PlebState::Waiting,
+    async fn handle_hook_message(&mut self, msg: ipc::HookMessage) -> Result<()> {
+        metrics::counter!(telemetry::HOOK_MESSAGES, "event" => msg.event_name.clone()).increment(1);
+
+        let Some(index) = self.repo_index(&msg.owner, &msg.repo) else {
+            tracing::warn!(
+                "Hook '{}' for {}/{}#{} does not match a watched repository, ignoring",
+                msg.event_name,
+                msg.owner,
+                msg.repo,
+                msg.issue_number
+            );
+            return Ok(());
+        };
+        // Only agents followed through hooks count; hooks left in a worktree don't
+        let agent = self.issue_agent(index, msg.issue_number)?;
+        let repo = &mut self.repos[index];
+        let issue_ref = repo.issue_ref(msg.issue_number);
+        if agent.state_source() != StateSource::Hooks {
+            tracing::debug!(
+                "Ignoring hook '{}' for issue {}, whose agent {} isn't followed through hooks",
+                msg.event_name,
+                issue_ref,
+                agent.name
+            );
+            return Ok(());
+        }
+
+        // Remember the Claude session id for this issue
+        if let Some(session_id) = msg.payload.get("session_id").and_then(|v| v.as_str()) {
+            if repo
+                .tracker
+                .get(msg.issue_number)
+                .is_some_and(|t| t.session_id.as_deref() != Some(session_id))
+            {
+                let session_id = session_id.to_string();
+                repo.update_tracker(|t| {
+                    let _ = t.set_session_id(msg.issue_number, &session_id);
+                });
+            }
+        }
+
+        // Remember where Claude keeps the transcript; link it and count its usage on every stop
+        if let Some(path) = msg.payload.get("transcript_path").and_then(|v| v.as_str()) {
+            let path = std::path::PathBuf::from(path);
+            if repo
+                .tracker
+                .get(msg.issue_number)
+                .is_some_and(|t| t.transcript_path.as_deref() != Some(path.as_path()))
+            {
+                repo.update_tracker(|t| {
+                    let _ = t.set_transcript_path(msg.issue_number, &path);
+                });
+            }
+            if msg.event_name == "Stop" {
+                let issue_dir = repo.state_dir.join(msg.issue_number.to_string());
+                if let Err(e) = transcript::link(&issue_dir, &path) {
+                    tracing::warn!("Failed to link transcript for issue {}: {}", issue_ref, e);
+                }
+                repo.record_usage(msg.issue_number, &path);
+            }
+        }
+
+        let tool_name = msg.payload.get("tool_name").and_then(|v| v.as_str());
+        let (target_state, stop_reason) = match msg.event_name.as_str() {
+            "UserPromptSubmit" => (PlebState::Working, None),
+            "Stop" => (PlebState::Waiting, Some("Finished its turn".to_string())),
+            "PermissionRequest" => (
+                PlebState::Waiting,
+                Some(match tool_name {
+                    Some(tool) => format!("Needs permission to use {}", tool),
+                    None => "Needs permission".to_string(),
+                }),
+            ),
             "PostToolUse" => {
                 // Only transition to Waiting if Claude used AskUserQuestion
-                let tool_name = msg.payload.get("tool_name").and_then(|v| v.as_str());
                 if tool_name == Some("AskUserQuestion") {
-                    PlebState::Waiting
+                    (PlebState::Waiting, Some("Asked a question".to_string()))
                 } else {
                     tracing::debug!(
-                        "PostToolUse for tool {:?} on issue #{}, no state transition",
+                        "PostToolUse for tool {:?} on issue {}, no state transition",
                         tool_name,
-                        msg.issue_number
+                        issue_ref
                     );
                     return Ok(());
                 }
             }
             _ => {
                 tracing::warn!(
-                    "Unknown hook event '{}' for issue #{}",
+                    "Unknown hook event '{}' for issue {}",
                     msg.event_name,
-                    msg.issue_number
+                    issue_ref
                 );
                 return Ok(());
             }
         };
 
         tracing::info!(
-            "Hook message: {} on issue #{} -> {:?}",
+            "Hook message: {} on issue {} -> {:?}",
             msg.event_name,
-            msg.issue_number,
+            issue_ref,
             target_state
         );
 
+        self.follow_session(index, msg.issue_number, target_state, stop_reason)
+            .await
+    }
+
+    /// Move an issue to the state its session reported (through a hook or a
+    /// headless run), recording why it stopped
+    async fn follow_session(
+        &mut self,
+        index: usize,
+        issue_number: u64,
+        target_state: PlebState,
+        stop_reason: Option<String>,
+    ) -> Result<()> {
+        let agent = self.issue_agent(index, issue_number)?;
+        let repo = &mut self.repos[index];
+        let issue_ref = repo.issue_ref(issue_number);
+
         // Get current state and transition
-        let issue = self.github.get_issue(msg.issue_number).await?;
-        let current_state = self.github.get_pleb_state(&issue, &self.config.labels);
+        let issue = repo.github.get_issue(issue_number).await?;
+        let current_state = repo.github.get_pleb_state(&issue, &repo.config.labels);
 
         if let Some(from_state) = current_state {
-            if from_state == target_state {
-                tracing::debug!(
-                    "Issue #{} already in {:?} state, skipping transition",
-                    msg.issue_number,
-                    target_state
-                );
-                return Ok(());
+            match repo
+                .transitions()
+                .with_stop_reason(stop_reason)
+                .transition(&issue, Some(from_state), target_state, false)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                // Late hooks (e.g. Stop after /pleb-shipit) are expected; the engine has logged it
+                Err(e) if e.downcast_ref::<InvalidTransition>().is_some() => return Ok(()),
+                Err(e) => return Err(e),
             }
-            self.github
-                .transition_state(msg.issue_number, from_state, target_state, &self.config.labels)
-                .await?;
+            repo.reload_tracker();
 
             // Update tmux window name to reflect new state
-            let state_name = match target_state {
-                PlebState::Waiting => "waiting",
-                PlebState::Working => "working",
-                _ => "unknown",
-            };
-            if let Err(e) = self.tmux.rename_window(msg.issue_number, state_name).await {
-                tracing::warn!("Failed to rename tmux window for issue #{}: {}", msg.issue_number, e);
+            if let Some(tmux) = self.tmux.as_ref().filter(|_| !agent.is_headless()) {
+                if let Err(e) = tmux.rename_window(&issue_ref, target_state.name()).await {
+                    tracing::warn!("Failed to rename tmux window for issue {}: {}", issue_ref, e);
+                }
             }
 
             tracing::info!(
-                "Hook transitioned issue #{} from {:?} to {:?}",
-                msg.issue_number,
+                "Session moved issue {} from {:?} to {:?}",
+                issue_ref,
                 from_state,
                 target_state
             );
+
+            // Comments that arrived while Claude was working can go in now
+            if target_state == PlebState::Waiting {
+                self.deliver_comments(index, Some(issue_number)).await?;
+            }
         } else {
             tracing::debug!(
-                "Issue #{} is not in a pleb state, ignoring hook",
-                msg.issue_number
+                "Issue {} is not in a pleb state, ignoring its session",
+                issue_ref
             );
         }
 
         Ok(())
     }
 
-    async fn poll_cycle(&mut self) -> Result<()> {
-        tracing::debug!("Polling for new issues...");
+    /// Handle an event from a headless run
+    async fn handle_headless_update(&mut self, update: headless::HeadlessUpdate) -> Result<()> {
+        let Some(index) = self.repo_index(&update.issue.owner, &update.issue.repo) else {
+            return Ok(());
+        };
+        let repo = &mut self.repos[index];
+        let issue_number = update.issue.number;
+
+        let (target_state, stop_reason) = match update.event {
+            HeadlessEvent::Started { session_id } => {
+                if let Some(session_id) = session_id {
+                    repo.update_tracker(|t| {
+                        let _ = t.set_session_id(issue_number, &session_id);
+                    });
+                }
+                (PlebState::Working, None)
+            }
+            HeadlessEvent::Stopped { state, reason } => {
+                let transcript = repo
+                    .state_dir
+                    .join(issue_number.to_string())
+                    .join(transcript::TRANSCRIPT_FILE);
+                repo.record_usage(issue_number, &transcript);
+                (state, Some(reason))
+            }
+        };
+
+        tracing::info!(
+            "Headless run for issue {} -> {:?}{}",
+            update.issue,
+            target_state,
+            stop_reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default()
+        );
+        self.follow_session(index, issue_number, target_state, stop_reason)
+            .await
+    }
+
+    /// Move issues left Working by a previous daemon to Waiting; their runs
+    /// are gone, and a new comment resumes them
+    async fn interrupt_headless_runs(&mut self, index: usize) -> Result<()> {
+        let repo = &self.repos[index];
+        let headless = headless_issues(&self.config, &repo.config, &repo.tracker);
+        let working: Vec<u64> = repo
+            .tracker
+            .all()
+            .into_iter()
+            .filter(|t| t.state == PlebState::Working && headless.contains(&t.issue_number))
+            .map(|t| t.issue_number)
+            .collect();
+
+        for issue_number in working {
+            self.follow_session(
+                index,
+                issue_number,
+                PlebState::Waiting,
+                Some("Interrupted by a daemon restart".to_string()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// One full poll: ready issues, merged PRs and new comments in every repo
+    async fn poll_once(&mut self) {
+        if let Err(e) = self.poll_cycle().await {
+            tracing::error!("Poll cycle error: {}", e);
+            metrics::counter!(telemetry::POLL_ERRORS, "stage" => "poll").increment(1);
+        }
+
+        // Check for merged PRs on active issues
+        for index in 0..self.repos.len() {
+            if let Err(e) = self.check_merged_prs(index).await {
+                tracing::error!("Check merged PRs error: {}", e);
+                metrics::counter!(telemetry::POLL_ERRORS, "stage" => "merged_prs").increment(1);
+            }
+        }
+
+        // Pass new issue comments on to running sessions
+        for index in 0..self.repos.len() {
+            if let Err(e) = self.deliver_comments(index, None).await {
+                tracing::error!("Comment delivery error: {}", e);
+                metrics::counter!(telemetry::POLL_ERRORS, "stage" => "comments").increment(1);
+            }
+        }
+
+        for repo in &self.repos {
+            telemetry::record_issue_states(&repo.config.full_name(), &repo.tracker);
+        }
+        self.publish_status();
+    }
+
+    /// Handle a GitHub webhook delivery. Events that may make an issue ready
+    /// or free a session slot ask for a poll rather than running one here.
+    async fn handle_webhook_event(&mut self, event: webhook::WebhookEvent) -> Result<()> {
+        use webhook::WebhookEvent;
+
+        let target = event.target().clone();
+        let Some(index) = self.repo_index(&target.owner, &target.repo) else {
+            tracing::debug!("Webhook event for unwatched repository {}, ignoring", target);
+            return Ok(());
+        };
+
+        // The next poll after the rate limit resets picks this up
+        if self.rate_limited_until.is_some_and(|until| until > ratelimit::now()) {
+            tracing::debug!("Rate limited, deferring webhook event for {}", target);
+            return Ok(());
+        }
+
+        match event {
+            WebhookEvent::IssueLabeled { label, .. } => {
+                let labels = &self.repos[index].config.labels;
+                let managed = [
+                    &labels.ready,
+                    &labels.provisioning,
+                    &labels.waiting,
+                    &labels.working,
+                    &labels.done,
+                    &labels.finished,
+                ];
+                if !managed.contains(&&label) {
+                    tracing::debug!("Issue {} labeled '{}', no action", target, label);
+                    return Ok(());
+                }
+
+                // New ready issue, or a session slot may have been freed
+                tracing::info!("Webhook: issue {} labeled '{}'", target, label);
+                self.poll_requested = true;
+            }
+            WebhookEvent::IssueClosed { .. } | WebhookEvent::PullRequestClosed { merged: true, .. } => {
+                tracing::info!("Webhook: {} closed, checking for merged PRs", target);
+                self.check_merged_prs(index).await?;
+                // A finished issue frees a session slot for the queue
+                self.poll_requested = true;
+            }
+            WebhookEvent::PullRequestClosed { merged: false, .. } => {
+                tracing::debug!("Pull request {} closed without merging, no action", target);
+            }
+            WebhookEvent::IssueCommentCreated { author, .. } => {
+                tracing::debug!("Webhook: comment by {} on {}", author, target);
+                self.deliver_comments(index, Some(target.number)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch ready issues for one repo and drop queued issues that lost their ready label.
+    /// Returns None if the fetch failed.
+    async fn fetch_ready_issues(&mut self, index: usize) -> Option<Vec<github::Issue>> {
+        let repo = &mut self.repos[index];
 
         // Fetch issues with pleb:ready label
-        let issues = match self
+        let issues = match repo
             .github
-            .get_issues_with_label(&self.config.labels.ready)
+            .get_issues_with_label(&repo.config.labels.ready)
             .await
         {
             Ok(issues) => issues,
             Err(e) => {
                 // Network errors shouldn't crash the daemon
-                tracing::error!("Failed to fetch issues: {}. Will retry on next poll.", e);
-                return Ok(());
+                tracing::error!(
+                    "Failed to fetch issues for {}: {}. Will retry on next poll.",
+                    repo.config.full_name(),
+                    e
+                );
+                return None;
             }
         };
 
+        // Collect current issue numbers for cleanup
+        let current_issue_numbers: HashSet<u64> = issues.iter().map(|i| i.number).collect();
+
+        // Drop queued issues that lost their ready label
+        repo.reload_tracker();
+        let stale: Vec<u64> = repo
+            .tracker
+            .queued()
+            .into_iter()
+            .filter(|n| !current_issue_numbers.contains(n))
+            .collect();
+        if !stale.is_empty() {
+            tracing::info!(
+                "Removing {} issue(s) in {} no longer ready from the queue",
+                stale.len(),
+                repo.config.full_name()
+            );
+            repo.update_tracker(|t| {
+                for number in &stale {
+                    t.dequeue(*number);
+                    if t.get(*number).is_some_and(|i| i.state == PlebState::Ready) {
+                        t.untrack(*number);
+                    }
+                }
+            });
+        }
+
         if issues.is_empty() {
             tracing::debug!(
-                "No new issues with {} label",
-                self.config.labels.ready
+                "No new issues with {} label in {}",
+                repo.config.labels.ready,
+                repo.config.full_name()
             );
-            // Clear logged_skips since no issues are in ready state
-            self.logged_skips.clear();
-            return Ok(());
         }
 
-        // Collect current issue numbers for cleanup
-        let current_issue_numbers: HashSet<u64> = issues.iter().map(|i| i.number).collect();
-
         // Clean up logged_skips: remove issues no longer in ready state
-        self.logged_skips.retain(|n| current_issue_numbers.contains(n));
-
-        // Process each issue that doesn't already have a tmux window
-        let mut processed_count = 0;
-        for issue in issues {
-            // Check if tmux window already exists (idempotent check)
-            if self.tmux.window_exists(issue.number).await? {
-                // Only log skip once per issue
-                if !self.logged_skips.contains(&issue.number) {
-                    tracing::info!("Issue #{} already has tmux window, skipping", issue.number);
-                    self.logged_skips.insert(issue.number);
-                }
-                continue;
-            }
+        repo.logged_skips.retain(|n| current_issue_numbers.contains(n));
 
-            // Issue is being processed, remove from logged_skips if present
-            self.logged_skips.remove(&issue.number);
+        Some(issues)
+    }
 
-            // Process this new issue
-            if let Err(e) = self.process_issue(&issue).await {
-                tracing::error!("Failed to process issue #{}: {}", issue.number, e);
-                // Continue with other issues - don't crash the daemon
-            } else {
-                processed_count += 1;
+    /// Refresh the GitHub rate-limit budget and decide whether to skip this poll.
+    /// Returns the seconds to wait when core or search quota is running low.
+    async fn rate_limit_backoff(&mut self) -> Option<u64> {
+        if let Some(repo) = self.repos.first() {
+            if let Err(e) = repo.github.refresh_rate_budget().await {
+                tracing::debug!("{:#}", e);
             }
         }
 
-        if processed_count > 0 {
+        let now = ratelimit::now();
+        let budget = ratelimit::snapshot();
+        let reserve = self.config.github.rate_limit_reserve_percent;
+        match ratelimit::backoff_until(&budget, reserve, now) {
+            Some((resource, reset)) => {
+                if self.rate_limited_until.is_none() {
+                    tracing::warn!(
+                        "GitHub {} rate limit is below {}% ({}), pausing polling for {}s",
+                        resource,
+                        reserve,
+                        ratelimit::format_budget(&budget, now),
+                        reset - now
+                    );
+                }
+                self.rate_limited_until = Some(reset);
+                Some(reset - now)
+            }
+            None => {
+                if self.rate_limited_until.take().is_some() {
+                    tracing::info!("GitHub rate limit has reset, resuming polling");
+                }
+                None
+            }
+        }
+    }
+
+    async fn poll_cycle(&mut self) -> Result<()> {
+        tracing::debug!("Polling for new issues...");
+
+        let mut candidates: Vec<(usize, github::Issue)> = Vec::new();
+        for index in 0..self.repos.len() {
+            if let Some(issues) = self.fetch_ready_issues(index).await {
+                candidates.extend(issues.into_iter().map(|issue| (index, issue)));
+            }
+        }
+
+        if candidates.is_empty() {
+            return Ok(());
+        }
+
+        // Rank ready issues from every repo by the scheduling rules; queue (FIFO) order breaks ties
+        candidates.sort_by_key(|(index, issue)| {
+            self.repos[*index]
+                .tracker
+                .queue_position(issue.number)
+                .unwrap_or(usize::MAX)
+        });
+        candidates.sort_by(|(_, a), (_, b)| scheduler::compare(a, b, &self.config.scheduling));
+
+        let now = state::unix_now();
+        let ranked: Vec<(usize, u64, String)> = candidates
+            .iter()
+            .map(|(index, i)| {
+                (*index, i.number, scheduler::describe_issue(i, &self.config.scheduling, now))
+            })
+            .collect();
+
+        // Process each issue that doesn't already have a tmux window
+        let mut processed_count = 0;
+        for (index, issue) in candidates {
+            let issue_ref = self.repos[index].issue_ref(issue.number);
+
+            // Check if tmux window already exists (idempotent check); headless agents have none
+            let headless = self
+                .config
+                .agent(self.repos[index].config.agent.select(&issue.labels))
+                .is_ok_and(|agent| agent.is_headless());
+            let has_window = match self.tmux.as_ref().filter(|_| !headless) {
+                Some(tmux) => tmux.window_exists(&issue_ref).await?,
+                None => false,
+            };
+            if has_window {
+                // Only log skip once per issue
+                if self.repos[index].logged_skips.insert(issue.number) {
+                    tracing::info!("Issue {} already has tmux window, skipping", issue_ref);
+                }
+                continue;
+            }
+
+            // Queue the issue if all session slots (shared by every repo) are in use
+            if let Some(max) = self.config.watch.max_concurrent_sessions {
+                let active: usize = self.repos.iter().map(|r| r.tracker.active_count()).sum();
+                if active >= max {
+                    let repo = &mut self.repos[index];
+                    if repo.tracker.queue_position(issue.number).is_none() {
+                        let mut position = 0;
+                        repo.update_tracker(|t| {
+                            let tracked = t.track(issue.number, PlebState::Ready);
+                            tracked.title = issue.title.clone();
+                            tracked.html_url = issue.html_url.clone();
+                            position = t.enqueue(issue.number);
+                        });
+                        tracing::info!(
+                            "Issue {} queued at position {} ({}/{} sessions in use)",
+                            issue_ref,
+                            position + 1,
+                            active,
+                            max
+                        );
+                    }
+                    continue;
+                }
+            }
+
+            // Issue is being processed, remove from logged_skips and the queue if present
+            let repo = &mut self.repos[index];
+            repo.logged_skips.remove(&issue.number);
+            if repo.tracker.queue_position(issue.number).is_some() {
+                repo.update_tracker(|t| {
+                    t.dequeue(issue.number);
+                });
+            }
+
+            // Process this new issue
+            if let Err(e) = self.process_issue(index, &issue).await {
+                tracing::error!("Failed to process issue {}: {}", issue_ref, e);
+                // Continue with other issues - don't crash the daemon
+            } else {
+                processed_count += 1;
+            }
+        }
+
+        if processed_count > 0 {
             tracing::info!("Provisioned {} new issue(s)", processed_count);
         }
 
+        // Keep each persisted queue in ranked order so `pleb status` shows real positions
+        for (index, repo) in self.repos.iter_mut().enumerate() {
+            let repo_ranked: Vec<(u64, &String)> = ranked
+                .iter()
+                .filter(|(i, _, _)| *i == index)
+                .map(|(_, n, rank)| (*n, rank))
+                .collect();
+            let order: Vec<u64> = repo_ranked.iter().map(|(n, _)| *n).collect();
+            let queued = repo.tracker.queued();
+            let mut ranked_queue = queued.clone();
+            ranked_queue.sort_by_key(|n| order.iter().position(|o| o == n).unwrap_or(usize::MAX));
+            let ranks_changed = repo_ranked.iter().any(|(n, rank)| {
+                queued.contains(n)
+                    && repo.tracker.get(*n).is_some_and(|t| t.rank.as_ref() != Some(*rank))
+            });
+
+            if ranked_queue != queued || ranks_changed {
+                repo.update_tracker(|t| {
+                    t.reorder_queue(&order);
+                    for (number, rank) in &repo_ranked {
+                        if t.queue_position(*number).is_some() {
+                            if let Some(tracked) = t.get_mut(*number) {
+                                tracked.rank = Some((*rank).clone());
+                            }
+                        }
+                    }
+                });
+            }
+        }
+
         Ok(())
     }
 
-    /// Check all active issues (working, waiting, done) for merged PRs and transition to finished
-    async fn check_merged_prs(&mut self) -> Result<()> {
-        tracing::debug!("Checking for merged PRs...");
+    /// Check all active issues (working, waiting, done) in one repo for merged PRs and transition to finished
+    async fn check_merged_prs(&mut self, index: usize) -> Result<()> {
+        let repo = &mut self.repos[index];
+        tracing::debug!("Checking for merged PRs in {}...", repo.config.full_name());
 
         // Fetch issues with working, waiting, and done labels (all active states)
         let labels_to_check = [
-            &self.config.labels.working,
-            &self.config.labels.waiting,
-            &self.config.labels.done,
+            &repo.config.labels.working,
+            &repo.config.labels.waiting,
+            &repo.config.labels.done,
         ];
 
         let mut all_issues = Vec::new();
         for label in labels_to_check {
-            match self.github.get_issues_with_label(label).await {
+            match repo.github.get_issues_with_label(label).await {
                 Ok(issues) => all_issues.extend(issues),
                 Err(e) => {
                     tracing::error!("Failed to fetch issues with label '{}': {}. Will retry on next poll.", label, e);
@@ -468,87 +1288,240 @@ impl Orchestrator {
             return Ok(());
         }
 
+        // Fetch PR state for all active issues at once
+        let lookups: Vec<(u64, Option<&str>)> = all_issues
+            .iter()
+            .map(|issue| {
+                let branch = repo.tracker.get(issue.number).and_then(|t| t.branch_name.as_deref());
+                (issue.number, branch)
+            })
+            .collect();
+        let merged = match repo.github.check_prs_merged(&lookups).await {
+            Ok(merged) => merged,
+            Err(e) => {
+                tracing::warn!("Error checking PR merge status: {:#}. Will retry on next poll.", e);
+                return Ok(());
+            }
+        };
+
         // Check each issue for merged PR
         for issue in all_issues {
+            let issue_ref = repo.issue_ref(issue.number);
+
             // Get current state
-            let current_state = match self.github.get_pleb_state(&issue, &self.config.labels) {
+            let current_state = match repo.github.get_pleb_state(&issue, &repo.config.labels) {
                 Some(state) => state,
                 None => {
-                    tracing::debug!("Issue #{} has no pleb state, skipping", issue.number);
+                    tracing::debug!("Issue {} has no pleb state, skipping", issue_ref);
                     continue;
                 }
             };
 
             // Check if PR is merged
-            match self.github.check_pr_merged(issue.number).await {
-                Ok(Some(true)) => {
+            match merged.get(&issue.number) {
+                Some(true) => {
                     // PR is merged, transition to finished
                     tracing::info!(
-                        "Issue #{} PR merged, transitioning from {:?} to Finished",
-                        issue.number,
+                        "Issue {} PR merged, transitioning from {:?} to Finished",
+                        issue_ref,
                         current_state
                     );
 
-                    if let Err(e) = self.github
-                        .transition_state(
-                            issue.number,
-                            current_state,
-                            PlebState::Finished,
-                            &self.config.labels,
-                        )
+                    if let Err(e) = repo
+                        .transitions()
+                        .transition(&issue, Some(current_state), PlebState::Finished, false)
                         .await
                     {
                         tracing::error!(
-                            "Failed to transition issue #{} to finished: {}",
-                            issue.number,
+                            "Failed to transition issue {} to finished: {}",
+                            issue_ref,
                             e
                         );
                         continue;
                     }
-
-                    // Update tmux window title to "finished"
-                    if let Err(e) = self.tmux.rename_window(issue.number, "finished").await {
-                        tracing::warn!(
-                            "Failed to rename tmux window for issue #{}: {}",
-                            issue.number,
-                            e
-                        );
+                    repo.reload_tracker();
+
+                    // Update tmux window title to "finished" (headless agents have no window)
+                    let headless = tracked_agent(&self.config, &repo.config, repo.tracker.get(issue.number))
+                        .is_ok_and(|agent| agent.is_headless());
+                    if let Some(tmux) = self.tmux.as_ref().filter(|_| !headless) {
+                        if let Err(e) = tmux.rename_window(&issue_ref, "finished").await {
+                            tracing::warn!(
+                                "Failed to rename tmux window for issue {}: {}",
+                                issue_ref,
+                                e
+                            );
+                        }
                     }
                 }
-                Ok(Some(false)) => {
+                Some(false) => {
                     // PR exists but not merged yet
-                    tracing::debug!("Issue #{} PR exists but not merged yet", issue.number);
+                    tracing::debug!("Issue {} PR exists but not merged yet", issue_ref);
                 }
-                Ok(None) => {
+                None => {
                     // No PR found for this issue (may have just been provisioned)
-                    tracing::debug!("Issue #{} has no PR yet", issue.number);
+                    tracing::debug!("Issue {} has no PR yet", issue_ref);
                 }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deliver new comments from allowed users to the sessions of Working and
+    /// Waiting issues (or just `only`). Comments stay pending while Claude is
+    /// working and go in once the issue is Waiting.
+    async fn deliver_comments(&mut self, index: usize, only: Option<u64>) -> Result<()> {
+        if !self.config.comments.feed_to_session {
+            return Ok(());
+        }
+
+        let repo = &mut self.repos[index];
+        let issues: Vec<state::TrackedIssue> = repo
+            .tracker
+            .all()
+            .into_iter()
+            .filter(|t| matches!(t.state, PlebState::Working | PlebState::Waiting))
+            .filter(|t| only.is_none_or(|n| n == t.issue_number))
+            .cloned()
+            .collect();
+
+        for tracked in issues {
+            let issue_ref = repo.issue_ref(tracked.issue_number);
+            // Problems with one issue don't hold up delivery to the others
+            let agent = match tracked_agent(&self.config, &repo.config, Some(&tracked)) {
+                Ok(agent) => agent,
                 Err(e) => {
-                    tracing::error!(
-                        "Error checking PR merge status for issue #{}: {}",
-                        issue.number,
-                        e
-                    );
+                    tracing::warn!("Can't deliver comments on issue {}: {:#}", issue_ref, e);
+                    continue;
+                }
+            };
+
+            let comments = match repo
+                .github
+                .get_issue_comments(tracked.issue_number, tracked.started_at)
+                .await
+            {
+                Ok(comments) => comments,
+                Err(e) => {
+                    tracing::warn!("{:#}. Will retry on next poll.", e);
+                    continue;
                 }
+            };
+
+            let new_comments: Vec<&github::IssueComment> = comments
+                .iter()
+                .filter(|c| c.id > tracked.last_comment_id.unwrap_or(0))
+                .filter(|c| !status_comment::is_status_comment(&c.body))
+                .filter(|c| self.config.comments.is_allowed(&c.author, &self.gh_username))
+                .collect();
+            let Some(newest) = new_comments.iter().map(|c| c.id).max() else {
+                continue;
+            };
+
+            if tracked.state == PlebState::Working {
+                tracing::debug!(
+                    "{} new comment(s) on issue {} pending until Claude is waiting",
+                    new_comments.len(),
+                    issue_ref
+                );
+                continue;
             }
+
+            let context = CommentsContext {
+                issue_number: tracked.issue_number,
+                title: tracked.title.clone(),
+                html_url: tracked.html_url.clone(),
+                comments: new_comments
+                    .iter()
+                    .map(|c| CommentContext {
+                        author: c.author.clone(),
+                        body: c.body.clone(),
+                        html_url: c.html_url.clone(),
+                    })
+                    .collect(),
+            };
+            let message = match repo
+                .templates
+                .render_comments(&repo.config.prompts.comment, &context)
+            {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::warn!("{:#}. Will retry on next poll.", e);
+                    continue;
+                }
+            };
+
+            let file_name = format!("comments-{}.md", newest);
+            if agent.is_headless() {
+                if self.headless.is_running(&issue_ref) {
+                    tracing::debug!("Headless run for issue {} is still ending", issue_ref);
+                    continue;
+                }
+                if tracked.worktree_path.is_none() {
+                    tracing::warn!("Issue {} has no worktree to resume in", issue_ref);
+                    continue;
+                }
+                if let Err(e) = self.headless.send_message(
+                    &agent,
+                    &issue_ref,
+                    &tracked,
+                    &message,
+                    &repo.state_dir,
+                    &file_name,
+                ) {
+                    tracing::warn!("Failed to resume issue {}: {:#}. Will retry on next poll.", issue_ref, e);
+                    continue;
+                }
+            } else if let Err(e) = match session_backend(self.tmux.as_ref()) {
+                Ok(tmux) => {
+                    InteractiveRunner::new(tmux)
+                        .send_message(&agent, &issue_ref, &message, &repo.state_dir, &file_name)
+                        .await
+                }
+                Err(e) => Err(e),
+            } {
+                tracing::warn!(
+                    "Failed to deliver comments to issue {}: {:#}. Will retry on next poll.",
+                    issue_ref,
+                    e
+                );
+                continue;
+            }
+
+            tracing::info!(
+                "Delivered {} comment(s) on issue {} to its session",
+                new_comments.len(),
+                issue_ref
+            );
+            repo.update_tracker(|t| {
+                let _ = t.set_last_comment_id(tracked.issue_number, newest);
+            });
         }
 
         Ok(())
     }
 
-    async fn process_issue(&mut self, issue: &github::Issue) -> Result<()> {
-        tracing::info!("Processing issue #{}: {}", issue.number, issue.title);
+    async fn process_issue(&mut self, index: usize, issue: &github::Issue) -> Result<()> {
+        let repo = &mut self.repos[index];
+        let issue_ref = repo.issue_ref(issue.number);
+        tracing::info!("Processing issue {}: {}", issue_ref, issue.title);
+        let started = std::time::Instant::now();
+        let agent = self.config.agent(repo.config.agent.select(&issue.labels))?;
 
         // Transition label: ready -> provisioning
-        self.github
-            .transition_state(
-                issue.number,
-                PlebState::Ready,
-                PlebState::Provisioning,
-                &self.config.labels,
-            )
+        repo.transitions()
+            .transition(issue, Some(PlebState::Ready), PlebState::Provisioning, false)
             .await?;
 
+        // Start a fresh record (the issue may have been managed before)
+        repo.update_tracker(|t| {
+            let tracked = t.track(issue.number, PlebState::Provisioning);
+            tracked.title = issue.title.clone();
+            tracked.html_url = issue.html_url.clone();
+            tracked.agent = Some(agent.name.clone());
+        });
+
         // Construct branch/worktree name: {issue_number}-{slug}_{username}_{suffix}
         let slug = slugify(&issue.title, 30);
         let branch_name = format!(
@@ -556,44 +1529,51 @@ impl Orchestrator {
             issue.number,
             slug,
             self.gh_username,
-            self.config.branch.suffix
+            repo.config.branch.suffix
         );
 
         // Create worktree
-        let worktree_path = self
+        let worktree_path = repo
             .worktree
-            .create_worktree(issue.number, &branch_name, &branch_name)
+            .create_worktree(&branch_name, &branch_name)
             .await?;
 
-        // Copy pleb.toml to worktree if it exists (may not be in source control)
-        let pleb_toml_src = Path::new("pleb.toml");
-        if pleb_toml_src.exists() {
-            let pleb_toml_dest = worktree_path.join("pleb.toml");
-            if let Err(e) = std::fs::copy(pleb_toml_src, &pleb_toml_dest) {
-                tracing::warn!(
-                    "Failed to copy pleb.toml to worktree for issue #{}: {}",
-                    issue.number,
-                    e
-                );
-            } else {
-                tracing::debug!("Copied pleb.toml to worktree for issue #{}", issue.number);
-            }
-        }
+        // Make the config available inside the worktree (it may not be in source control)
+        write_worktree_config(&self.config, &worktree_path, &issue_ref);
 
-        // Install Claude Code hooks in worktree
-        if let Err(e) = hooks::install_hooks(&worktree_path) {
+        // Install the agent's hooks and files in the worktree
+        if let Err(e) = agent.install(&worktree_path) {
             tracing::warn!(
-                "Failed to install hooks for issue #{}: {}",
-                issue.number,
+                "Failed to set up agent {} for issue {}: {}",
+                agent.name,
+                issue_ref,
                 e
             );
             // Continue anyway - hooks are nice to have but not critical
         } else {
-            tracing::info!("Installed Claude Code hooks for issue #{}", issue.number);
+            tracing::info!("Set up agent {} for issue {}", agent.name, issue_ref);
         }
 
-        // Create tmux window
-        self.tmux.create_window(&branch_name, &worktree_path).await?;
+        // Create tmux window (headless agents have none)
+        let tmux = match agent.is_headless() {
+            true => None,
+            false => Some(session_backend(self.tmux.as_ref())?),
+        };
+        let window_name = match &tmux {
+            None => None,
+            Some(tmux) => {
+                tmux.create_window(&issue_ref, &branch_name, &worktree_path).await?;
+                Some(session::window_name(&issue_ref, &branch_name))
+            }
+        };
+
+        repo.update_tracker(|t| {
+            if let Some(tracked) = t.get_mut(issue.number) {
+                tracked.worktree_path = Some(worktree_path.clone());
+                tracked.branch_name = Some(branch_name.clone());
+                tracked.tmux_window = window_name;
+            }
+        });
 
         // Create context for template rendering (used by both provision hooks and prompts)
         // Note: We use a placeholder body here for provision hooks; the real processed body
@@ -602,34 +1582,47 @@ impl Orchestrator {
             issue,
             &branch_name,
             &worktree_path,
-            &self.config.paths.repo_dir,
+            &repo.config.paths.repo_dir,
         );
 
         // Execute on_provision hooks with template variable support
-        for cmd in &self.config.provision.on_provision {
-            let rendered_cmd = self.templates.render_string(cmd, &provision_context)?;
-            tracing::info!("Running on_provision hook for issue #{}: {}", issue.number, rendered_cmd);
-            self.tmux.send_keys(issue.number, &rendered_cmd).await?;
-            // Small delay to let command start before next one
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-        }
+        // (they are typed into the window, so headless runs have none)
+        let on_provision = &repo.config.provision.on_provision;
+        if let Some(tmux) = &tmux {
+            for cmd in on_provision {
+                let rendered_cmd = repo.templates.render_string(cmd, &provision_context)?;
+                tracing::info!("Running on_provision hook for issue {}: {}", issue_ref, rendered_cmd);
+                tmux.send_keys(&issue_ref, &rendered_cmd).await?;
+                // Small delay to let command start before next one
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
 
-        // Ensure pane 0 is selected before invoking Claude (hooks may have changed focus)
-        if !self.config.provision.on_provision.is_empty() {
-            self.tmux.select_pane(issue.number, 0).await?;
+            // Ensure pane 0 is selected before invoking Claude (hooks may have changed focus)
+            if !on_provision.is_empty() {
+                tmux.select_pane(&issue_ref, 0).await?;
+            }
+        } else if !on_provision.is_empty() {
+            tracing::warn!("Skipping on_provision commands for headless issue {}", issue_ref);
         }
 
-        // Get daemon dir for media storage
-        let daemon_dir = self.config.daemon_dir()?;
-        let issue_dir = daemon_dir.join(issue.number.to_string());
+        // Per-issue directory for media storage
+        let issue_dir = repo.state_dir.join(issue.number.to_string());
         std::fs::create_dir_all(&issue_dir)
             .with_context(|| format!("Failed to create issue directory: {}", issue_dir.display()))?;
 
+        // Keep a raw log of the window next to the transcript
+        if let Some(tmux) = &tmux {
+            let log_file = issue_dir.join(transcript::RAW_LOG_FILE);
+            if let Err(e) = tmux.log_output(&issue_ref, &log_file).await {
+                tracing::warn!("Failed to log the window for issue {}: {}", issue_ref, e);
+            }
+        }
+
         // Fetch body_html which contains signed URLs for private attachments
         // GitHub user-attachments require this special endpoint to get downloadable URLs
-        let body_html = self.github.get_issue_body_html(issue.number, &self.gh_token).await
+        let body_html = repo.github.get_issue_body_html(issue.number, &self.gh_token).await
             .unwrap_or_else(|e| {
-                tracing::warn!("Failed to fetch body_html for issue #{}: {}. Media may not download.", issue.number, e);
+                tracing::warn!("Failed to fetch body_html for issue {}: {}. Media may not download.", issue_ref, e);
                 String::new()
             });
 
@@ -658,33 +1651,56 @@ impl Orchestrator {
             &processed_issue,
             &branch_name,
             &worktree_path,
-            &self.config.paths.repo_dir,
+            &repo.config.paths.repo_dir,
         );
-        let prompt = self
+        let prompt = repo
             .templates
-            .render(&self.config.prompts.new_issue, &context)?;
+            .render(&repo.config.prompts.new_issue, &context)?;
 
-        // Invoke Claude
-        self.claude.invoke(issue.number, &prompt, &daemon_dir).await?;
+        // Start the issue's sandbox, if sessions run in one
+        let in_use = sandboxes_in_use(&repo.tracker, issue.number);
+        let sandbox = self
+            .sandbox
+            .prepare(&repo.config, &issue_ref, &worktree_path, &in_use)
+            .await?;
+        if let Some(sandbox) = &sandbox {
+            repo.update_tracker(|t| {
+                if let Some(tracked) = t.get_mut(issue.number) {
+                    tracked.sandbox = Some(sandbox.instance.clone());
+                }
+            });
+        }
+
+        // Invoke the agent
+        match &tmux {
+            None => {
+                self.headless
+                    .invoke(&agent, &issue_ref, &prompt, &repo.state_dir, &worktree_path)?;
+            }
+            Some(tmux) => {
+                InteractiveRunner::new(tmux.clone())
+                    .invoke(&agent, &issue_ref, &prompt, &repo.state_dir, sandbox.as_ref())
+                    .await?;
+            }
+        }
 
         // Transition label: provisioning -> working
-        self.github
-            .transition_state(
-                issue.number,
-                PlebState::Provisioning,
-                PlebState::Working,
-                &self.config.labels,
-            )
+        repo.transitions()
+            .transition(issue, Some(PlebState::Provisioning), PlebState::Working, false)
             .await?;
+        repo.reload_tracker();
+        metrics::histogram!(telemetry::PROVISIONING_DURATION).record(started.elapsed().as_secs_f64());
 
         // Update tmux window name to show "working" state
-        if let Err(e) = self.tmux.rename_window(issue.number, "working").await {
-            tracing::warn!("Failed to rename tmux window for issue #{}: {}", issue.number, e);
+        if let Some(tmux) = &tmux {
+            if let Err(e) = tmux.rename_window(&issue_ref, "working").await {
+                tracing::warn!("Failed to rename tmux window for issue {}: {}", issue_ref, e);
+            }
         }
 
         tracing::info!(
-            "Successfully provisioned issue #{}: {}",
-            issue.number,
+            "Successfully provisioned issue {}: {}",
+            issue_ref,
             issue.title
         );
 
@@ -695,31 +1711,37 @@ impl Orchestrator {
 async fn handle_transition_command(
     issue_number: u64,
     state_str: &str,
-    config: Config,
+    force: bool,
+    repo: &RepoConfig,
+    comments: &CommentsConfig,
+    costs: &CostsConfig,
 ) -> Result<()> {
     // Create GitHub client
-    let github = GitHubClient::new(&config.github).await?;
+    let github = GitHubClient::new(&repo.github).await?;
 
     // Fetch the issue to determine current state
     let issue = github.get_issue(issue_number).await?;
-    let current_state = github.get_pleb_state(&issue, &config.labels);
+    let current_state = github.get_pleb_state(&issue, &repo.labels);
 
     // Handle "none" as a special case to remove all pleb labels
     if state_str.to_lowercase() == "none" {
         // Remove all pleb labels
         let all_labels = vec![
-            &config.labels.ready,
-            &config.labels.provisioning,
-            &config.labels.waiting,
-            &config.labels.working,
-            &config.labels.done,
-            &config.labels.finished,
+            &repo.labels.ready,
+            &repo.labels.provisioning,
+            &repo.labels.waiting,
+            &repo.labels.working,
+            &repo.labels.done,
+            &repo.labels.finished,
         ];
 
         for label in all_labels {
             github.remove_label(issue_number, label).await?;
         }
 
+        // Stop tracking the issue locally as well
+        IssueTracker::update(&repo.state_file()?, |t| t.untrack(issue_number))?;
+
         println!("Issue #{} is no longer managed by pleb (all pleb labels removed)", issue_number);
         return Ok(());
     }
@@ -727,25 +1749,35 @@ async fn handle_transition_command(
     // Parse state string
     let target_state = parse_state(state_str)?;
 
-    // Transition to target state
-    if let Some(from_state) = current_state {
-        github
-            .transition_state(issue_number, from_state, target_state, &config.labels)
-            .await?;
-    } else {
-        // No current pleb label - just add the target state label
-        let target_label = match target_state {
-            PlebState::Ready => &config.labels.ready,
-            PlebState::Provisioning => &config.labels.provisioning,
-            PlebState::Waiting => &config.labels.waiting,
-            PlebState::Working => &config.labels.working,
-            PlebState::Done => &config.labels.done,
-            PlebState::Finished => &config.labels.finished,
-        };
-        github.add_label(issue_number, target_label).await?;
+    // Transition to target state (validated unless --force is given)
+    let mut templates = TemplateEngine::new(&repo.prompts)?;
+    let mut engine = TransitionEngine::new(&github, &repo.labels, &repo.state_file()?);
+    if comments.status_comment {
+        templates.load_status_comment_template(&repo.prompts.status_comment)?;
+        let commenter = StatusCommenter::new(&github, &templates, &repo.prompts.status_comment);
+        engine = engine.with_status_comment(match costs.in_status_comment {
+            true => commenter.with_costs(costs),
+            false => commenter,
+        });
     }
+    let changed = engine
+        .transition(&issue, current_state, target_state, force)
+        .await
+        .map_err(|e| {
+            let rejected = e.downcast_ref::<InvalidTransition>().is_some()
+                || (current_state.is_none() && target_state != PlebState::Ready);
+            if rejected {
+                anyhow::anyhow!("{} (use --force to override)", e)
+            } else {
+                e
+            }
+        })?;
 
-    println!("Issue #{} transitioned to {:?}", issue_number, target_state);
+    if changed {
+        println!("Issue #{} transitioned to {:?}", issue_number, target_state);
+    } else {
+        println!("Issue #{} is already in {:?}", issue_number, target_state);
+    }
 
     Ok(())
 }
@@ -754,7 +1786,7 @@ async fn handle_transition_command(
 struct DaemonStatus {
     running: bool,
     pid: Option<i32>,
-    uptime: Option<String>,
+    uptime: Option<std::time::Duration>,
 }
 
 /// Check daemon status by reading PID file and checking if process exists
@@ -792,12 +1824,7 @@ fn check_daemon_status(config: &Config) -> DaemonStatus {
                 let uptime = std::fs::metadata(&pid_file_path)
                     .ok()
                     .and_then(|metadata| metadata.modified().ok())
-                    .and_then(|modified| {
-                        let duration = std::time::SystemTime::now()
-                            .duration_since(modified)
-                            .ok()?;
-                        Some(format_duration(duration))
-                    });
+                    .and_then(|modified| std::time::SystemTime::now().duration_since(modified).ok());
 
                 DaemonStatus {
                     running: true,
@@ -823,160 +1850,805 @@ fn check_daemon_status(config: &Config) -> DaemonStatus {
     }
 }
 
-/// Format a duration into a human-readable string
-fn format_duration(duration: std::time::Duration) -> String {
-    let secs = duration.as_secs();
-    let days = secs / 86400;
-    let hours = (secs % 86400) / 3600;
-    let minutes = (secs % 3600) / 60;
-
-    if days > 0 {
-        format!("{}d {}h {}m", days, hours, minutes)
-    } else if hours > 0 {
-        format!("{}h {}m", hours, minutes)
-    } else {
-        format!("{}m", minutes)
-    }
-}
+/// Print tokens and cost per issue, from the usage kept in each issue directory
+fn handle_costs_report(
+    since: Option<chrono::NaiveDate>,
+    repo_selector: Option<&str>,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<()> {
+    let repos = match repo_selector {
+        Some(_) => vec![config.select_repo(repo_selector)?],
+        None => config.repos(),
+    };
+
+    let mut issues = Vec::new();
+    for repo in &repos {
+        let state_dir = repo.state_dir()?;
+        let Ok(entries) = std::fs::read_dir(&state_dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Some(number) = entry.file_name().to_str().and_then(|name| name.parse::<u64>().ok()) else {
+                continue;
+            };
+            let ledger = usage::IssueUsage::load(&entry.path())?;
+            let models = ledger.by_model(since);
+            if models.is_empty() {
+                continue;
+            }
+            issues.push(output::CostRecord {
+                repo: repo.full_name(),
+                number,
+                title: ledger.title,
+                tokens: usage::total(&models),
+                cost_usd: usage::cost(&models, &config.costs),
+                models,
+            });
+        }
+    }
+    // Most expensive first; unpriced issues last, by tokens
+    issues.sort_by(|a, b| {
+        b.cost_usd
+            .unwrap_or(-1.0)
+            .total_cmp(&a.cost_usd.unwrap_or(-1.0))
+            .then(b.tokens.output.cmp(&a.tokens.output))
+    });
+
+    let mut models = usage::ModelTokens::new();
+    for issue in &issues {
+        for (model, tokens) in &issue.models {
+            *models.entry(model.clone()).or_default() += *tokens;
+        }
+    }
+    let report = output::CostReport {
+        since,
+        tokens: usage::total(&models),
+        cost_usd: usage::cost(&models, &config.costs),
+        models,
+        issues,
+    };
+
+    if format == OutputFormat::Json {
+        return output::print_json(&report);
+    }
+
+    match since {
+        Some(since) => println!("Usage since {}:", since),
+        None => println!("Usage:"),
+    }
+    if report.issues.is_empty() {
+        println!("  No usage recorded.");
+        return Ok(());
+    }
+    let multi_repo = config.repos().len() > 1;
+    for issue in &report.issues {
+        let id = match multi_repo {
+            true => format!("{}#{}", issue.repo.rsplit('/').next().unwrap_or_default(), issue.number),
+            false => format!("#{}", issue.number),
+        };
+        println!(
+            "  {:<6} {:>9}  {}",
+            id,
+            usage::format_cost(issue.cost_usd),
+            output::truncate(&issue.title, 60)
+        );
+        println!("  {:<6} {:>9}  {}", "", "", usage::describe(&issue.tokens));
+    }
+    println!();
+    println!("By model:");
+    for (model, tokens) in &report.models {
+        let cost = usage::cost(&usage::ModelTokens::from([(model.clone(), *tokens)]), &config.costs);
+        println!("  {:<30} {:>9}  {}", model, usage::format_cost(cost), usage::describe(tokens));
+    }
+    println!();
+    println!(
+        "Total: {} for {} issue(s) ({})",
+        usage::format_cost(report.cost_usd),
+        report.issues.len(),
+        usage::describe(&report.tokens)
+    );
+    if report.cost_usd.is_none() {
+        println!("Models without a [costs.prices] entry make the total unknown.");
+    }
+    Ok(())
+}
+
+/// Print an issue's conversation, or the raw log of its window
+fn handle_transcript_command(
+    issue_number: u64,
+    raw: bool,
+    format: transcript::Format,
+    repo: &RepoConfig,
+) -> Result<()> {
+    let issue_dir = repo.state_dir()?.join(issue_number.to_string());
+
+    if raw {
+        let log_file = issue_dir.join(transcript::RAW_LOG_FILE);
+        if !log_file.exists() {
+            anyhow::bail!(
+                "No raw log for issue #{} at {} (headless issues and zellij windows have none)",
+                issue_number,
+                log_file.display()
+            );
+        }
+        let mut file = std::fs::File::open(&log_file)
+            .with_context(|| format!("Failed to open {}", log_file.display()))?;
+        std::io::copy(&mut file, &mut std::io::stdout().lock())
+            .context("Failed to print the raw log")?;
+        return Ok(());
+    }
+
+    // The copy in the issue directory, or else where the hooks last said it was
+    let mut path = issue_dir.join(transcript::TRANSCRIPT_FILE);
+    if !path.exists() {
+        let tracker = IssueTracker::load(&repo.state_file()?)?;
+        match tracker.get(issue_number).and_then(|t| t.transcript_path.clone()) {
+            Some(transcript_path) => path = transcript_path,
+            None => anyhow::bail!("No transcript recorded for issue #{}", issue_number),
+        }
+    }
+    let jsonl = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read transcript: {}", path.display()))?;
+    print!("{}", transcript::render(&jsonl, format));
+    Ok(())
+}
+
+async fn handle_status_command(
+    issue_number: Option<u64>,
+    repo_selector: Option<&str>,
+    format: OutputFormat,
+    config: Config,
+) -> Result<()> {
+    match issue_number {
+        Some(num) if format == OutputFormat::Json => {
+            let repo = config.select_repo(repo_selector)?;
+            let tracker = IssueTracker::load(&repo.state_file()?)?;
+            let github = GitHubClient::new(&repo.github).await?;
+            let window_exists = match session::issue_backend(&config, Vec::new()) {
+                Some(tmux) => tmux.window_exists(&github.issue_ref(num)).await?,
+                None => false,
+            };
+
+            let mut record = match tracker.get(num) {
+                Some(tracked) => output::IssueRecord::from_tracked(
+                    &repo.full_name(),
+                    tracked,
+                    tracker.queue_position(num),
+                    window_exists,
+                ),
+                None => {
+                    // Not tracked locally - fall back to GitHub labels
+                    let issue = github.get_issue(num).await?;
+                    output::IssueRecord {
+                        title: issue.title.clone(),
+                        state: github
+                            .get_pleb_state(&issue, &repo.labels)
+                            .map(|state| state.name().to_string()),
+                        url: issue.html_url.clone(),
+                        ..output::IssueRecord::untracked(&repo.full_name(), num, window_exists)
+                    }
+                }
+            };
+
+            let models = usage::IssueUsage::load(&repo.state_dir()?.join(num.to_string()))?.by_model(None);
+            if !models.is_empty() {
+                record.tokens = Some(usage::total(&models));
+                record.cost_usd = usage::cost(&models, &config.costs);
+            }
+
+            if record.pr_url.is_none() {
+                record.pr_url = github
+                    .get_pull_request_for_issue(num, tracker.get(num).and_then(|t| t.branch_name.as_deref()))
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::debug!("Failed to check for PR: {}", e);
+                        None
+                    });
+            }
+
+            output::print_json(&record)?;
+        }
+        Some(num) => {
+            let repo = config.select_repo(repo_selector)?;
+            let tracker = IssueTracker::load(&repo.state_file()?)?;
+            let github = GitHubClient::new(&repo.github).await?;
+
+            match tracker.get(num) {
+                Some(tracked) => {
+                    println!("Issue #{}: {}", tracked.issue_number, tracked.title);
+                    if config.repos().len() > 1 {
+                        println!("Repository: {}", repo.full_name());
+                    }
+                    println!(
+                        "State: {} (updated {} ago)",
+                        tracked.state.name(),
+                        state::format_duration(elapsed_since(tracked.last_updated))
+                    );
+                    if let Some(position) = tracker.queue_position(num) {
+                        println!("Queued: position {} of {}", position + 1, tracker.queued().len());
+                        if let Some(rank) = &tracked.rank {
+                            println!(
+                                "Rank: {} ({})",
+                                rank,
+                                scheduler::describe_order(&config.scheduling)
+                            );
+                        }
+                    }
+                    println!("URL: {}", tracked.html_url);
+                    if let Some(branch) = &tracked.branch_name {
+                        println!("Branch: {}", branch);
+                    }
+                    if let Some(path) = &tracked.worktree_path {
+                        println!("Worktree: {}", path.display());
+                    }
+                    if let Some(session_id) = &tracked.session_id {
+                        println!("Session: {}", session_id);
+                    }
+                    if let Some(path) = &tracked.transcript_path {
+                        println!("Transcript: {}", path.display());
+                    }
+                }
+                None => {
+                    // Not tracked locally - fall back to GitHub labels
+                    let issue = github.get_issue(num).await?;
+                    let current_state = github.get_pleb_state(&issue, &repo.labels);
+
+                    println!("Issue #{}: {}", issue.number, issue.title);
+
+                    match current_state {
+                        Some(state) => println!("State: {}", state.name()),
+                        None => println!("State: not managed by pleb"),
+                    }
+
+                    println!("URL: {}", issue.html_url);
+                }
+            }
+
+            // Check for associated pull request
+            match github.get_pull_request_for_issue(num, tracker.get(num).and_then(|t| t.branch_name.as_deref())).await {
+                Ok(Some(pr_url)) => {
+                    println!("PR: {}", pr_url);
+                }
+                Ok(None) => {
+                    // No PR found, don't print anything
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to check for PR: {}", e);
+                }
+            }
+
+            // Token usage, kept after cleanup as well
+            let models = usage::IssueUsage::load(&repo.state_dir()?.join(num.to_string()))?.by_model(None);
+            if !models.is_empty() {
+                println!("Tokens: {}", usage::describe(&usage::total(&models)));
+                if models.len() > 1 {
+                    for (model, tokens) in &models {
+                        println!("  {}: {}", model, usage::describe(tokens));
+                    }
+                }
+                println!("Cost: {}", usage::format_cost(usage::cost(&models, &config.costs)));
+            }
+        }
+        None => {
+            // Show daemon status and all managed issues
+            let daemon_status = check_daemon_status(&config);
+
+            let repos = match repo_selector {
+                Some(_) => vec![config.select_repo(repo_selector)?],
+                None => config.repos(),
+            };
+            let multi_repo = config.repos().len() > 1;
+
+            let mut trackers = Vec::new();
+            let mut quick_trackers = Vec::new();
+            for repo in &repos {
+                trackers.push(IssueTracker::load(&repo.state_file()?)?);
+                quick_trackers.push(QuickTracker::load(&repo.quick_state_file()?)?);
+            }
+
+            if format == OutputFormat::Json {
+                let tmux = session::backend(&config.tmux, Vec::new());
+                let windows = tmux
+                    .list_windows()
+                    .await
+                    .context("Failed to list issue windows")?;
+
+                let mut issues = Vec::new();
+                for (repo, tracker) in repos.iter().zip(&trackers) {
+                    for tracked in tracker.all() {
+                        let issue_ref = IssueRef::new(
+                            &repo.github.owner,
+                            &repo.github.repo,
+                            tracked.issue_number,
+                        );
+                        issues.push(output::IssueRecord::from_tracked(
+                            &repo.full_name(),
+                            tracked,
+                            tracker.queue_position(tracked.issue_number),
+                            windows.iter().any(|w| session::window_matches(w, &issue_ref)),
+                        ));
+                    }
+                }
+
+                let mut quick_sessions = Vec::new();
+                for (repo, quick_tracker) in repos.iter().zip(&quick_trackers) {
+                    for session in quick_tracker.all() {
+                        let quick_ref = QuickRef::new(&repo.github.owner, &repo.github.repo, &session.id);
+                        quick_sessions.push(output::QuickRecord::new(
+                            &repo.full_name(),
+                            session,
+                            tmux.window_exists(&quick_ref).await?,
+                        ));
+                    }
+                }
+
+                return output::print_json(&output::StatusRecord {
+                    daemon: output::DaemonRecord {
+                        running: daemon_status.running,
+                        pid: daemon_status.pid,
+                        uptime_secs: daemon_status.uptime.map(|uptime| uptime.as_secs()),
+                    },
+                    issues,
+                    quick_sessions,
+                });
+            }
+
+            if daemon_status.running {
+                print!("Daemon: running");
+                if let Some(pid) = daemon_status.pid {
+                    print!(" (PID: {}", pid);
+                    if let Some(uptime) = daemon_status.uptime {
+                        print!(", uptime: {}", state::format_duration(uptime));
+                    }
+                    print!(")");
+                }
+                println!();
+            } else {
+                println!("Daemon: stopped");
+            }
+            if multi_repo {
+                println!(
+                    "Repositories: {}",
+                    repos.iter().map(|r| r.full_name()).collect::<Vec<_>>().join(", ")
+                );
+            }
+            if let Some(max) = config.watch.max_concurrent_sessions {
+                println!(
+                    "Sessions: {}/{} in use, {} queued",
+                    trackers.iter().map(|t| t.active_count()).sum::<usize>(),
+                    max,
+                    trackers.iter().map(|t| t.queued().len()).sum::<usize>()
+                );
+                println!("Scheduling: {}", scheduler::describe_order(&config.scheduling));
+            }
+            if let Some(repo) = repos.first() {
+                // The budget is per token, so any repo's client will do
+                let github = GitHubClient::new(&repo.github).await?;
+                match github.refresh_rate_budget().await {
+                    Ok(()) => println!(
+                        "GitHub API: {}",
+                        ratelimit::format_budget(&ratelimit::snapshot(), ratelimit::now())
+                    ),
+                    Err(e) => tracing::debug!("{:#}", e),
+                }
+            }
+            println!();
+
+            if trackers.iter().all(|t| t.is_empty()) {
+                println!("No managed issues found.");
+            } else {
+                println!("Managed Issues:");
+                for (repo, tracker) in repos.iter().zip(&trackers) {
+                    for issue in tracker.all() {
+                        let state_str = match tracker.queue_position(issue.issue_number) {
+                            Some(position) => format!("[queued #{}]", position + 1),
+                            None => format!("[{}]", issue.state.name()),
+                        };
+
+                        // Prefix the number with the repo name when several repos are watched
+                        let id = if multi_repo {
+                            format!("{}#{}", repo.github.repo, issue.issue_number)
+                        } else {
+                            format!("#{}", issue.issue_number)
+                        };
+
+                        let title = output::truncate(&issue.title, 60);
+
+                        match (&issue.rank, tracker.queue_position(issue.issue_number)) {
+                            (Some(rank), Some(_)) => {
+                                println!("  {:<6} {:<15} {} ({})", id, state_str, title, rank)
+                            }
+                            _ => println!("  {:<6} {:<15} {}", id, state_str, title),
+                        }
+                    }
+                }
+            }
+
+            if quick_trackers.iter().any(|t| !t.is_empty()) {
+                println!();
+                println!("Quick Sessions:");
+                for (repo, quick_tracker) in repos.iter().zip(&quick_trackers) {
+                    for session in quick_tracker.all() {
+                        let id = if multi_repo {
+                            format!("{}:{}", repo.github.repo, session.id)
+                        } else {
+                            session.id.clone()
+                        };
+                        println!(
+                            "  {:<20} {:<15} {}",
+                            id,
+                            format!("[{}]", session.state.name()),
+                            session.branch_name
+                        );
+                    }
+                }
+            }
+
+            println!();
+            if daemon_status.running {
+                println!("Use 'pleb status <issue_number>' for detailed issue info.");
+            } else {
+                println!("No active daemon. Start with 'pleb watch --daemon'.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_quick_status_command(
+    id: &str,
+    repo: &RepoConfig,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<()> {
+    let tracker = QuickTracker::load(&repo.quick_state_file()?)?;
+    let session = tracker
+        .get(id)
+        .with_context(|| format!("No quick session '{}' in {}", id, repo.full_name()))?;
+    let quick_ref = QuickRef::new(&repo.github.owner, &repo.github.repo, id);
+    let window_exists = session::backend(&config.tmux, Vec::new())
+        .window_exists(&quick_ref)
+        .await?;
+
+    if format == OutputFormat::Json {
+        return output::print_json(&output::QuickRecord::new(&repo.full_name(), session, window_exists));
+    }
+
+    println!("Quick session: {}", session.id);
+    if config.repos().len() > 1 {
+        println!("Repository: {}", repo.full_name());
+    }
+    println!(
+        "State: {} (updated {} ago)",
+        session.state.name(),
+        state::format_duration(elapsed_since(session.last_updated))
+    );
+    println!("Branch: {}", session.branch_name);
+    if let Some(path) = &session.worktree_path {
+        println!("Worktree: {}", path.display());
+    }
+    println!("Window: {}", if window_exists { "open" } else { "missing" });
+    if let Some(session_id) = &session.session_id {
+        println!("Session: {}", session_id);
+    }
+
+    Ok(())
+}
+
+async fn handle_ui_command(repo_selector: Option<&str>, config: Config) -> Result<()> {
+    let mut ui = ui::Ui::new(&config, repo_selector)?;
+
+    loop {
+        let action = ui.run().await?;
+        let result = match &action {
+            ui::Action::Quit => return Ok(()),
+            ui::Action::Attach(issue) => {
+                let tmux = session::backend(&config.tmux, Vec::new());
+                match tmux.attach_window_command(issue).await? {
+                    Some(mut cmd) => {
+                        cmd.status().context("Failed to attach to tmux window")?;
+                        continue;
+                    }
+                    None => Err(anyhow::anyhow!(
+                        "No tmux window for issue {} (try restoring it)",
+                        issue
+                    )),
+                }
+            }
+            ui::Action::Transition { issue, state } => {
+                let repo = config.select_repo(Some(&format!("{}/{}", issue.owner, issue.repo)))?;
+                handle_transition_command(issue.number, state, false, &repo, &config.comments, &config.costs)
+                    .await
+            }
+            ui::Action::Cleanup(issue) => {
+                let repo = config.select_repo(Some(&format!("{}/{}", issue.owner, issue.repo)))?;
+                handle_cleanup_command(issue.number, &repo, config.clone()).await
+            }
+            ui::Action::Restore(issue) => {
+                let selector = format!("{}/{}", issue.owner, issue.repo);
+                handle_restore_command(Some(&selector), OutputFormat::Table, config.clone()).await
+            }
+        };
+
+        if let Err(e) = result {
+            eprintln!("Error: {:#}", e);
+        }
+        ui::wait_for_enter()?;
+    }
+}
+
+/// Time elapsed since a Unix timestamp (seconds)
+fn elapsed_since(timestamp: u64) -> std::time::Duration {
+    std::time::Duration::from_secs(state::unix_now().saturating_sub(timestamp))
+}
+
+async fn handle_new_command(
+    title: Option<String>,
+    suggest: bool,
+    no_ready: bool,
+    extra_labels: Vec<String>,
+    assignee: Option<String>,
+    repo: &RepoConfig,
+    config: &Config,
+) -> Result<()> {
+    use std::io::{IsTerminal, Read};
+
+    let github = GitHubClient::new(&repo.github).await?;
+
+    // Write the description in an editor, unless it is piped in
+    let interactive = std::io::stdin().is_terminal();
+    let description = if interactive {
+        let hint = match title {
+            Some(_) => "Describe the issue. Save an empty file to abort.",
+            None => "Describe the issue; the first line is its title. Save an empty file to abort.",
+        };
+        draft::edit("", hint)?
+    } else {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .context("Failed to read the description from stdin")?;
+        text
+    };
+    if description.trim().is_empty() {
+        anyhow::bail!("Aborting: the description is empty");
+    }
+
+    let mut text = match &title {
+        Some(title) => format!("{}\n\n{}", title, description.trim()),
+        None => description.clone(),
+    };
+
+    if suggest {
+        let mut proposal = draft::propose(&config.claude, &text).await?;
+        // Keep a given title; either way the draft's title line isn't repeated in the body
+        if let Some(title) = &title {
+            proposal.title = title.clone();
+        }
+        text = draft::revise(&proposal, &text);
+
+        if interactive {
+            text = draft::edit(
+                &text,
+                "Review the proposal; the first line is the title. Save an empty file to abort.",
+            )?;
+        }
+    }
+
+    let (title, body) = draft::parse_draft(&text).context("Aborting: the issue is empty")?;
+
+    let mut labels = Vec::new();
+    if !no_ready {
+        labels.push(repo.labels.ready.clone());
+    }
+    labels.extend(extra_labels);
+    let assignees: Vec<String> = assignee.into_iter().collect();
+
+    let issue = github.create_issue(&title, &body, &labels, &assignees).await?;
+
+    println!("Created {}: {}", github.issue_ref(issue.number), issue.title);
+    println!("URL: {}", issue.html_url);
+    if !no_ready {
+        println!("Labelled '{}'; pleb watch will pick it up", repo.labels.ready);
+    }
+
+    Ok(())
+}
+
+async fn handle_list_command(
+    repo_selector: Option<&str>,
+    fix: bool,
+    format: OutputFormat,
+    config: Config,
+) -> Result<()> {
+    if fix && format == OutputFormat::Json {
+        anyhow::bail!("--fix can't be combined with --format json");
+    }
+
+    let tmux = session::issue_backend(&config, Vec::new());
+    let repos = match repo_selector {
+        Some(_) => vec![config.select_repo(repo_selector)?],
+        None => config.repos(),
+    };
+
+    let mut scanned = Vec::new();
+    for repo in repos {
+        let github = GitHubClient::new(&repo.github).await?;
+        let worktree = WorktreeManager::new(&repo.paths);
+        let headless = headless_issues(&config, &repo, &IssueTracker::load(&repo.state_file()?)?);
+        let rows = inventory::scan(&repo, &github, tmux.as_deref(), &headless, &worktree)
+            .await
+            .with_context(|| format!("Failed to list sessions for {}", repo.full_name()))?;
+        scanned.push((repo, rows));
+    }
+
+    if format == OutputFormat::Json {
+        let mut records = Vec::new();
+        for (repo, rows) in &scanned {
+            let tracker = IssueTracker::load(&repo.state_file()?)?;
+            records.extend(
+                rows.iter()
+                    .map(|row| output::ListRecord::new(&repo.full_name(), row, &tracker)),
+            );
+        }
+        return output::print_json(&records);
+    }
+
+    for (repo, rows) in &scanned {
+        print_inventory(repo, rows);
+    }
+
+    if fix {
+        for (repo, rows) in &scanned {
+            fix_inventory(repo, rows, &config).await?;
+        }
+    } else if scanned.iter().any(|(_, rows)| rows.iter().any(|row| !row.problems.is_empty())) {
+        println!();
+        println!("Run 'pleb list --fix' to repair these problems.");
+    }
+
+    Ok(())
+}
+
+/// Print one repository's rows of `pleb list` as a table
+fn print_inventory(repo: &RepoConfig, rows: &[inventory::Row]) {
+    println!("{}:", repo.full_name());
+    if rows.is_empty() {
+        println!("  No sessions");
+        return;
+    }
+
+    let yes_no = |present: bool| if present { "yes" } else { "no" };
+    println!(
+        "  {:<8} {:<13} {:<7} {:<9} {:<7} TITLE",
+        "ISSUE", "STATE", "WINDOW", "WORKTREE", "PR"
+    );
+    for row in rows {
+        let pr = match row.pr_merged {
+            Some(true) => "merged",
+            Some(false) => "open",
+            None => "-",
+        };
+        println!(
+            "  {:<8} {:<13} {:<7} {:<9} {:<7} {}",
+            format!("#{}", row.number),
+            row.state.map(|state| state.name()).unwrap_or("-"),
+            yes_no(row.window),
+            yes_no(row.worktree.is_some()),
+            pr,
+            output::truncate(&row.title, 50)
+        );
+        for problem in &row.problems {
+            println!("  {:<8} ! {}", "", problem.describe());
+        }
+    }
+}
+
+/// Apply `pleb list --fix` to one repository's rows
+async fn fix_inventory(repo: &RepoConfig, rows: &[inventory::Row], config: &Config) -> Result<()> {
+    let mut restore = false;
 
-async fn handle_status_command(issue_number: Option<u64>, config: Config) -> Result<()> {
-    match issue_number {
-        Some(num) => {
-            // Single issue status - existing behavior
-            let github = GitHubClient::new(&config.github).await?;
-            let issue = github.get_issue(num).await?;
-            let current_state = github.get_pleb_state(&issue, &config.labels);
-
-            println!("Issue #{}: {}", issue.number, issue.title);
-
-            match current_state {
-                Some(state) => {
-                    let state_name = match state {
-                        PlebState::Ready => "ready",
-                        PlebState::Provisioning => "provisioning",
-                        PlebState::Waiting => "waiting",
-                        PlebState::Working => "working",
-                        PlebState::Done => "done",
-                        PlebState::Finished => "finished",
-                    };
-                    println!("State: {}", state_name);
-                }
-                None => {
-                    println!("State: not managed by pleb");
+    for row in rows {
+        let Some(fix) = inventory::fix_for(row) else {
+            continue;
+        };
+        let issue_ref = IssueRef::new(&repo.github.owner, &repo.github.repo, row.number);
+        println!();
+        println!("Fixing {}...", issue_ref);
+
+        match fix {
+            inventory::Fix::FinishAndCleanUp => {
+                if row.state.is_some_and(|state| state != PlebState::Finished) {
+                    handle_transition_command(
+                        row.number,
+                        "finished",
+                        false,
+                        repo,
+                        &config.comments,
+                        &config.costs,
+                    )
+                    .await
+                        .with_context(|| format!("Failed to finish {}", issue_ref))?;
                 }
+                handle_cleanup_command(row.number, repo, config.clone()).await?;
             }
-
-            println!("URL: {}", issue.html_url);
-
-            // Check for associated pull request
-            match github.get_pull_request_for_issue(num).await {
-                Ok(Some(pr_url)) => {
-                    println!("PR: {}", pr_url);
-                }
-                Ok(None) => {
-                    // No PR found, don't print anything
-                }
-                Err(e) => {
-                    tracing::debug!("Failed to check for PR: {}", e);
-                }
+            inventory::Fix::CleanUp => {
+                handle_cleanup_command(row.number, repo, config.clone()).await?;
             }
-        }
-        None => {
-            // Show daemon status and all managed issues
-            let daemon_status = check_daemon_status(&config);
-
-            if daemon_status.running {
-                print!("Daemon: running");
-                if let Some(pid) = daemon_status.pid {
-                    print!(" (PID: {}", pid);
-                    if let Some(uptime) = daemon_status.uptime {
-                        print!(", uptime: {}", uptime);
+            inventory::Fix::Restore => {
+                // Restore works from the local state, so record what GitHub says first
+                let Some(label_state) = row.state else {
+                    continue;
+                };
+                IssueTracker::update(&repo.state_file()?, |t| {
+                    if t.get(row.number).is_none() {
+                        t.track(row.number, label_state);
                     }
-                    print!(")");
-                }
-                println!();
-                println!();
-
-                // Fetch all managed issues
-                let github = GitHubClient::new(&config.github).await?;
-
-                // Collect all issues with any pleb label
-                let mut all_issues = Vec::new();
-                let labels = [
-                    &config.labels.ready,
-                    &config.labels.provisioning,
-                    &config.labels.waiting,
-                    &config.labels.working,
-                    &config.labels.done,
-                    &config.labels.finished,
-                ];
-
-                for label in labels {
-                    let issues = github.get_issues_with_label(label).await?;
-                    all_issues.extend(issues);
-                }
-
-                // Remove duplicates (issues can't have multiple pleb labels, but just in case)
-                let mut seen = HashSet::new();
-                all_issues.retain(|issue| seen.insert(issue.number));
-
-                // Sort by issue number
-                all_issues.sort_by_key(|issue| issue.number);
-
-                if all_issues.is_empty() {
-                    println!("No managed issues found.");
-                } else {
-                    println!("Managed Issues:");
-                    for issue in all_issues {
-                        let state = github.get_pleb_state(&issue, &config.labels);
-                        let state_str = match state {
-                            Some(PlebState::Ready) => "[ready]",
-                            Some(PlebState::Provisioning) => "[provisioning]",
-                            Some(PlebState::Waiting) => "[waiting]",
-                            Some(PlebState::Working) => "[working]",
-                            Some(PlebState::Done) => "[done]",
-                            Some(PlebState::Finished) => "[finished]",
-                            None => "[unknown]",
-                        };
-
-                        // Truncate title to 60 characters
-                        let title = if issue.title.len() > 60 {
-                            format!("{}...", &issue.title[..57])
-                        } else {
-                            issue.title.clone()
-                        };
-
-                        println!("  #{:<5} {:<15} {}", issue.number, state_str, title);
+                    t.update_state(row.number, label_state)?;
+                    if let Some(tracked) = t.get_mut(row.number) {
+                        tracked.title = row.title.clone();
+                        tracked.html_url = row.url.clone();
                     }
-                }
-
-                println!();
-                println!("Use 'pleb status <issue_number>' for detailed issue info.");
-            } else {
-                println!("Daemon: stopped");
-                println!();
-                println!("No active daemon. Start with 'pleb watch --daemon'.");
+                    anyhow::Ok(())
+                })??;
+                restore = true;
             }
         }
     }
 
+    if restore {
+        handle_restore_command(Some(&repo.full_name()), OutputFormat::Table, config.clone()).await?;
+    }
+
     Ok(())
 }
 
-async fn handle_cleanup_command(issue_number: u64, config: Config) -> Result<()> {
+async fn handle_cleanup_command(issue_number: u64, repo: &RepoConfig, config: Config) -> Result<()> {
     // Create managers directly (cleanup doesn't need daemon or full orchestrator)
-    let worktree = WorktreeManager::new(&config.paths);
-    let tmux = TmuxManager::new(&config.tmux);
-
-    // Check if worktree exists
-    let worktree_path = worktree.get_worktree_path(issue_number);
+    let worktree = WorktreeManager::new(&repo.paths);
+    let tmux = session::issue_backend(&config, Vec::new());
+    let state_file = repo.state_file()?;
+    let tracker = IssueTracker::load(&state_file)?;
+    let issue_ref = IssueRef::new(&repo.github.owner, &repo.github.repo, issue_number);
+
+    // Check if worktree exists (prefer the recorded path, fall back to searching)
+    let worktree_path = tracker
+        .get(issue_number)
+        .and_then(|t| t.worktree_path.clone())
+        .filter(|p| p.exists())
+        .or_else(|| worktree.get_worktree_path(issue_number));
     let worktree_exists = worktree_path.is_some();
 
     // Check if tmux window exists
-    let window_exists = tmux.window_exists(issue_number).await?;
+    let window_exists = match &tmux {
+        Some(tmux) => tmux.window_exists(&issue_ref).await?,
+        None => false,
+    };
+
+    // Forget the issue locally. Saved before killing the window because
+    // cleanup is usually run from inside that window.
+    let untracked = IssueTracker::update(&state_file, |t| t.untrack(issue_number))?;
+    let was_tracked = untracked.is_some();
+
+    // Tear down the issue's sandbox, also before killing the window
+    let recorded_sandbox = untracked.and_then(|t| t.sandbox);
+    match Sandbox::new(&config)?
+        .release(&issue_ref, recorded_sandbox.as_deref())
+        .await
+    {
+        Ok(Some(instance)) => println!("Deleted sandbox {} for issue #{}", instance, issue_number),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to tear down the sandbox for issue {}: {:#}", issue_ref, e),
+    }
 
     if !worktree_exists && !window_exists {
-        println!("Issue #{} has no worktree or tmux window to clean up", issue_number);
+        if was_tracked {
+            println!("Removed issue #{} from local state", issue_number);
+        } else {
+            println!("Issue #{} has no worktree or tmux window to clean up", issue_number);
+        }
         return Ok(());
     }
 
@@ -987,8 +2659,8 @@ async fn handle_cleanup_command(issue_number: u64, config: Config) -> Result<()>
     }
 
     // Kill tmux window
-    if window_exists {
-        tmux.kill_window(issue_number).await?;
+    if let Some(tmux) = tmux.filter(|_| window_exists) {
+        tmux.kill_window(&issue_ref).await?;
         println!("Killed tmux window for issue #{}", issue_number);
     }
 
@@ -996,147 +2668,347 @@ async fn handle_cleanup_command(issue_number: u64, config: Config) -> Result<()>
     Ok(())
 }
 
-async fn handle_restore_command(config: Config) -> Result<()> {
-    // Create GitHub client, TmuxManager, and WorktreeManager
-    let github = GitHubClient::new(&config.github).await?;
-    let worktree = WorktreeManager::new(&config.paths);
+async fn handle_quick_command(
+    branch_name: &str,
+    prompt_file: Option<&Path>,
+    repo: &RepoConfig,
+    config: &Config,
+) -> Result<()> {
+    let quick_ref = QuickRef::new(&repo.github.owner, &repo.github.repo, quick::quick_id(branch_name)?);
+    let prompt = prompt_file
+        .map(|path| {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read prompt file: {}", path.display()))
+        })
+        .transpose()?;
+
+    // Pass the GitHub token on when there is one, although quick sessions don't need it
+    let env = std::env::var(&config.github.token_env)
+        .map(|token| vec![(config.github.token_env.clone(), token)])
+        .unwrap_or_default();
+    let tmux = session::backend(&config.tmux, env);
+    if tmux.window_exists(&quick_ref).await? {
+        println!(
+            "Quick session '{}' is already running; use 'pleb attach' to get to it",
+            quick_ref.id
+        );
+        return Ok(());
+    }
+
+    let worktree = WorktreeManager::new(&repo.paths);
+    worktree.ensure_repo(&repo.github.owner, &repo.github.repo).await?;
+    let worktree_path = worktree
+        .create_worktree(branch_name, &quick_ref.worktree_name())
+        .await?;
+
+    // Quick sessions are always interactive: the repo's default agent if it
+    // is, Claude Code otherwise
+    let mut agent = config.agent(&repo.agent.default)?;
+    if agent.is_headless() {
+        let claude = config::ClaudeConfig {
+            mode: config::AgentMode::Interactive,
+            ..config.claude.clone()
+        };
+        agent = Agent::new(config::CLAUDE_AGENT, claude.profile());
+    }
+
+    // Same setup as for an issue, but no labels are touched
+    write_worktree_config(config, &worktree_path, &quick_ref);
+    if let Err(e) = agent.install(&worktree_path) {
+        tracing::warn!("Failed to set up agent {} for {}: {}", agent.name, quick_ref, e);
+    }
+    tmux.create_window(&quick_ref, branch_name, &worktree_path).await?;
+
+    let initial_state = if prompt.is_some() {
+        PlebState::Working
+    } else {
+        PlebState::Waiting
+    };
+    QuickTracker::update(&repo.quick_state_file()?, |tracker| {
+        let session = tracker.start(&quick_ref.id, branch_name, initial_state);
+        session.worktree_path = Some(worktree_path.clone());
+        session.tmux_window = Some(quick_ref.window_name(branch_name));
+    })?;
+
+    let runner = InteractiveRunner::new(tmux.clone());
+    match &prompt {
+        Some(prompt) => runner.invoke(&agent, &quick_ref, prompt, &repo.state_dir()?, None).await?,
+        None => runner.start(&agent, &quick_ref).await?,
+    }
+
+    println!("Started quick session '{}' on branch {}", quick_ref.id, branch_name);
+    println!("Worktree: {}", worktree_path.display());
+    println!(
+        "Use 'pleb attach' to get to it and 'pleb cleanup {}' when done",
+        quick_ref.id
+    );
+    Ok(())
+}
+
+async fn handle_quick_cleanup_command(id: &str, repo: &RepoConfig, config: &Config) -> Result<()> {
+    let quick_ref = QuickRef::new(&repo.github.owner, &repo.github.repo, id);
+    let tmux = session::backend(&config.tmux, Vec::new());
+    let worktree = WorktreeManager::new(&repo.paths);
+
+    // Forget the session first; cleanup is usually run from inside its window
+    let session = QuickTracker::update(&repo.quick_state_file()?, |tracker| tracker.remove(id))?;
+
+    let window_exists = tmux.window_exists(&quick_ref).await?;
+    let worktree_path = session
+        .as_ref()
+        .and_then(|s| s.worktree_path.clone())
+        .filter(|path| path.exists());
+
+    if session.is_none() && !window_exists {
+        anyhow::bail!("No quick session '{}' in {}", id, repo.full_name());
+    }
+
+    if let (Some(path), Some(session)) = (&worktree_path, &session) {
+        worktree.remove_worktree_at(path, &session.branch_name).await?;
+        println!("Removed worktree for quick session '{}'", id);
+    }
+    if window_exists {
+        tmux.kill_window(&quick_ref).await?;
+        println!("Killed tmux window for quick session '{}'", id);
+    }
+
+    println!("Successfully cleaned up quick session '{}'", id);
+    Ok(())
+}
 
-    // Create TmuxManager with GitHub token
+async fn handle_restore_command(
+    repo_selector: Option<&str>,
+    format: OutputFormat,
+    config: Config,
+) -> Result<()> {
+    // Create the session backend with GitHub token
     let token = std::env::var(&config.github.token_env)
         .with_context(|| format!("Missing environment variable: {}", config.github.token_env))?;
-    let tmux = TmuxManager::new(&config.tmux)
-        .with_env(&config.github.token_env, token.clone());
+    let tmux = session::backend(
+        &config.tmux,
+        vec![(config.github.token_env.clone(), token.clone())],
+    );
 
-    // Initialize template engine, Claude runner, and media client for provisioning
-    let templates = TemplateEngine::new(&config.prompts)?;
-    let claude = ClaudeRunner::new(&config.claude, &config.tmux);
+    // Initialize the agent runner and media client for provisioning
+    let runner = InteractiveRunner::new(tmux.clone());
     let media_client = reqwest::Client::new();
-    let daemon_dir = config.daemon_dir()?;
 
-    // Get authenticated username for branch naming
-    let gh_username = github.get_authenticated_user().await?;
+    let repos = match repo_selector {
+        Some(_) => vec![config.select_repo(repo_selector)?],
+        None => config.repos(),
+    };
 
-    // Fetch all open issues with managed labels (working, waiting, done, finished)
-    let labels_to_check = [
-        &config.labels.working,
-        &config.labels.waiting,
-        &config.labels.done,
-        &config.labels.finished,
-    ];
+    // Authenticated username is only needed if a branch name was never recorded
+    let mut gh_username: Option<String> = None;
+    let mut records = Vec::new();
 
-    let mut all_issues = Vec::new();
-    for label in labels_to_check {
-        match github.get_issues_with_label(label).await {
-            Ok(issues) => all_issues.extend(issues),
-            Err(e) => {
-                tracing::error!("Failed to fetch issues with label '{}': {}", label, e);
-                continue;
+    for repo in &repos {
+        let repo_records = restore_repo(
+            repo,
+            &config,
+            tmux.as_ref(),
+            &runner,
+            &media_client,
+            &token,
+            &mut gh_username,
+        )
+        .await
+        .with_context(|| format!("Failed to restore sessions for {}", repo.full_name()))?;
+        records.extend(repo_records);
+    }
+
+    tracing::info!(
+        "Checked {} issue(s), restored {} session(s)",
+        records.len(),
+        records.iter().filter(|r| r.restored).count()
+    );
+
+    if format == OutputFormat::Json {
+        output::print_json(&records)?;
+    }
+
+    Ok(())
+}
+
+/// Restore missing sessions for one repo. Returns what was done for each issue checked.
+async fn restore_repo(
+    repo: &RepoConfig,
+    config: &Config,
+    tmux: &dyn SessionBackend,
+    runner: &InteractiveRunner,
+    media_client: &reqwest::Client,
+    token: &str,
+    gh_username: &mut Option<String>,
+) -> Result<Vec<output::RestoreRecord>> {
+    let github = GitHubClient::new(&repo.github).await?;
+    let worktree = WorktreeManager::new(&repo.paths);
+    let templates = TemplateEngine::new(&repo.prompts)?;
+    let sandbox = Sandbox::new(config)?;
+    let state_dir = repo.state_dir()?;
+
+    // Load managed issues from the state file, seeding it from labels if empty
+    let state_file = repo.state_file()?;
+    let mut tracker = IssueTracker::load(&state_file)?;
+    if tracker.is_empty() {
+        let imported = import_tracker_from_labels(&github, &worktree, &repo.labels).await?;
+        tracker = IssueTracker::update(&state_file, |t| {
+            if t.is_empty() {
+                *t = imported;
             }
-        }
+            t.clone()
+        })?;
     }
 
-    // Deduplicate issues (an issue may have multiple labels)
-    let mut seen = HashSet::new();
-    all_issues.retain(|issue| seen.insert(issue.number));
+    // Only issues with an active or finished session need restoring. Headless
+    // issues have no window; the daemon resumes them when a comment arrives.
+    let headless = headless_issues(config, repo, &tracker);
+    if !headless.is_empty() {
+        tracing::info!(
+            "Skipping {} headless issue(s) in {}",
+            headless.len(),
+            repo.full_name()
+        );
+    }
+    let candidates: Vec<(u64, Option<String>, Option<std::path::PathBuf>)> = tracker
+        .all()
+        .into_iter()
+        .filter(|t| {
+            matches!(
+                t.state,
+                PlebState::Working | PlebState::Waiting | PlebState::Done | PlebState::Finished
+            )
+        })
+        .filter(|t| !headless.contains(&t.issue_number))
+        .map(|t| (t.issue_number, t.branch_name.clone(), t.worktree_path.clone()))
+        .collect();
 
-    if all_issues.is_empty() {
-        tracing::info!("No managed issues found to restore");
-        return Ok(());
+    if candidates.is_empty() {
+        tracing::info!("No managed issues found to restore in {}", repo.full_name());
+        return Ok(Vec::new());
     }
 
-    tracing::info!("Checking {} managed issue(s)...", all_issues.len());
+    tracing::info!("Checking {} managed issue(s) in {}...", candidates.len(), repo.full_name());
 
-    let mut restored_count = 0;
+    let mut records = Vec::new();
 
-    for issue in &all_issues {
-        let issue_number = issue.number;
+    for (issue_number, recorded_branch, recorded_worktree) in &candidates {
+        let issue_number = *issue_number;
+        let issue_ref = github.issue_ref(issue_number);
 
         // Check if tmux window exists
-        let window_exists = tmux.window_exists(issue_number).await?;
+        let window_exists = tmux.window_exists(&issue_ref).await?;
 
         // Check if worktree exists
-        let worktree_path = worktree.get_worktree_path(issue_number);
-        let worktree_exists = worktree_path.is_some();
+        let worktree_exists = recorded_worktree.as_ref().is_some_and(|p| p.exists())
+            || worktree.get_worktree_path(issue_number).is_some();
+
+        let mut record = output::RestoreRecord {
+            repo: repo.full_name(),
+            number: issue_number,
+            window_existed: window_exists,
+            worktree_existed: worktree_exists,
+            restored: false,
+        };
 
         // If both exist, skip
         if window_exists && worktree_exists {
-            tracing::debug!("Issue #{} already has session", issue_number);
+            tracing::debug!("Issue {} already has session", issue_ref);
+            records.push(record);
             continue;
         }
 
-        // Either worktree or tmux window is missing, restore the session
-        tracing::info!("Restoring session for issue #{}: {}", issue_number, issue.title);
+        // The issue body is needed for the restoration prompt
+        let issue = github.get_issue(issue_number).await?;
 
-        // Construct branch name: {issue_number}-{slug}_{username}_{suffix}
-        let slug = slugify(&issue.title, 30);
-        let branch_name = format!(
-            "{}-{}_{}_{}",
-            issue_number,
-            slug,
-            gh_username,
-            config.branch.suffix
-        );
+        // Either worktree or tmux window is missing, restore the session
+        tracing::info!("Restoring session for issue {}: {}", issue_ref, issue.title);
+
+        // Reuse the recorded branch name, or construct it: {issue_number}-{slug}_{username}_{suffix}
+        let branch_name = match recorded_branch {
+            Some(branch) => branch.clone(),
+            None => {
+                if gh_username.is_none() {
+                    *gh_username = Some(github.get_authenticated_user().await?);
+                }
+                let slug = slugify(&issue.title, 30);
+                format!(
+                    "{}-{}_{}_{}",
+                    issue_number,
+                    slug,
+                    gh_username.as_deref().unwrap_or_default(),
+                    repo.branch.suffix
+                )
+            }
+        };
 
         // Create worktree (idempotent)
         let worktree_path = worktree
-            .create_worktree(issue_number, &branch_name, &branch_name)
+            .create_worktree(&branch_name, &branch_name)
             .await?;
 
-        // Copy pleb.toml to worktree if it exists
-        let pleb_toml_src = Path::new("pleb.toml");
-        if pleb_toml_src.exists() {
-            let pleb_toml_dest = worktree_path.join("pleb.toml");
-            if let Err(e) = std::fs::copy(pleb_toml_src, &pleb_toml_dest) {
-                tracing::warn!(
-                    "Failed to copy pleb.toml to worktree for issue #{}: {}",
-                    issue_number,
-                    e
-                );
-            }
-        }
+        // Make the config available inside the worktree
+        write_worktree_config(config, &worktree_path, &issue_ref);
 
-        // Install hooks
-        if let Err(e) = hooks::install_hooks(&worktree_path) {
+        // Install the agent's hooks and files
+        let agent = tracked_agent(config, repo, tracker.get(issue_number))?;
+        if let Err(e) = agent.install(&worktree_path) {
             tracing::warn!(
-                "Failed to install hooks for issue #{}: {}",
-                issue_number,
+                "Failed to set up agent {} for issue {}: {}",
+                agent.name,
+                issue_ref,
                 e
             );
         }
 
         // Create tmux window (idempotent)
-        tmux.create_window(&branch_name, &worktree_path).await?;
+        tmux.create_window(&issue_ref, &branch_name, &worktree_path).await?;
+
+        // Record where the restored session lives
+        tracker = IssueTracker::update(&state_file, |t| {
+            if let Some(tracked) = t.get_mut(issue_number) {
+                tracked.worktree_path = Some(worktree_path.clone());
+                tracked.branch_name = Some(branch_name.clone());
+                tracked.tmux_window = Some(session::window_name(&issue_ref, &branch_name));
+                tracked.last_updated = state::unix_now();
+            }
+            t.clone()
+        })?;
 
         // Run on_provision hooks with template variable support
         let provision_context = IssueContext::from_issue(
-            issue,
+            &issue,
             &branch_name,
             &worktree_path,
-            &config.paths.repo_dir,
+            &repo.paths.repo_dir,
         );
 
-        for cmd in &config.provision.on_provision {
+        for cmd in &repo.provision.on_provision {
             let rendered_cmd = templates.render_string(cmd, &provision_context)?;
-            tracing::info!("Running on_provision hook for issue #{}: {}", issue_number, rendered_cmd);
-            tmux.send_keys(issue_number, &rendered_cmd).await?;
+            tracing::info!("Running on_provision hook for issue {}: {}", issue_ref, rendered_cmd);
+            tmux.send_keys(&issue_ref, &rendered_cmd).await?;
             // Small delay to let command start before next one
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
 
         // Ensure pane 0 is selected before invoking Claude (hooks may have changed focus)
-        if !config.provision.on_provision.is_empty() {
-            tmux.select_pane(issue_number, 0).await?;
+        if !repo.provision.on_provision.is_empty() {
+            tmux.select_pane(&issue_ref, 0).await?;
         }
 
         // Process media in issue body
-        let issue_dir = daemon_dir.join(issue_number.to_string());
+        let issue_dir = state_dir.join(issue_number.to_string());
         std::fs::create_dir_all(&issue_dir)
             .with_context(|| format!("Failed to create issue directory: {}", issue_dir.display()))?;
 
+        let log_file = issue_dir.join(transcript::RAW_LOG_FILE);
+        if let Err(e) = tmux.log_output(&issue_ref, &log_file).await {
+            tracing::warn!("Failed to log the window for issue {}: {}", issue_ref, e);
+        }
+
         // Fetch body_html for signed URLs
-        let body_html = github.get_issue_body_html(issue_number, &token).await
+        let body_html = github.get_issue_body_html(issue_number, token).await
             .unwrap_or_else(|e| {
-                tracing::warn!("Failed to fetch body_html for issue #{}: {}. Media may not download.", issue_number, e);
+                tracing::warn!("Failed to fetch body_html for issue {}: {}. Media may not download.", issue_ref, e);
                 String::new()
             });
 
@@ -1145,7 +3017,7 @@ async fn handle_restore_command(config: Config) -> Result<()> {
             &issue.body,
             &body_html,
             &issue_dir,
-            &media_client,
+            media_client,
         )
         .await
         .unwrap_or_else(|e| {
@@ -1165,20 +3037,31 @@ async fn handle_restore_command(config: Config) -> Result<()> {
             processed_body
         );
 
-        // Invoke Claude with restoration prompt
-        claude.invoke(issue_number, &restoration_prompt, &daemon_dir).await?;
+        // Start the issue's sandbox again, if sessions run in one
+        let in_use = sandboxes_in_use(&tracker, issue_number);
+        let session = sandbox
+            .prepare(repo, &issue_ref, &worktree_path, &in_use)
+            .await?;
+        if let Some(session) = &session {
+            tracker = IssueTracker::update(&state_file, |t| {
+                if let Some(tracked) = t.get_mut(issue_number) {
+                    tracked.sandbox = Some(session.instance.clone());
+                }
+                t.clone()
+            })?;
+        }
 
-        tracing::info!("Restored session for issue #{}", issue_number);
-        restored_count += 1;
-    }
+        // Invoke the agent with restoration prompt
+        runner
+            .invoke(&agent, &issue_ref, &restoration_prompt, &state_dir, session.as_ref())
+            .await?;
 
-    tracing::info!(
-        "Checked {} issue(s), restored {} session(s)",
-        all_issues.len(),
-        restored_count
-    );
+        tracing::info!("Restored session for issue {}", issue_ref);
+        record.restored = true;
+        records.push(record);
+    }
 
-    Ok(())
+    Ok(records)
 }
 
 async fn handle_cc_run_hook_command(event: &str, config: Config) -> Result<()> {
@@ -1199,12 +3082,17 @@ async fn handle_cc_run_hook_command(event: &str, config: Config) -> Result<()> {
         .as_str()
         .context("Missing or invalid 'cwd' field in hook payload")?;
 
-    // Extract issue number from path
-    let issue_number = match hooks::extract_issue_number_from_path(cwd) {
-        Some(num) => num,
+    // Quick sessions have no labels, so their state is kept here rather than by the daemon
+    if let Some(quick_ref) = hooks::extract_quick_ref_from_path(cwd, &config.repos()) {
+        return handle_quick_hook(event, &payload, &quick_ref, &config).await;
+    }
+
+    // Resolve the repo and issue number from the path
+    let issue = match hooks::extract_issue_ref_from_path(cwd, &config.repos()) {
+        Some(issue) => issue,
         None => {
             // Not a pleb-managed directory, exit silently
-            tracing::debug!("No issue number found in path: {}", cwd);
+            tracing::debug!("No issue found for path: {}", cwd);
             return Ok(());
         }
     };
@@ -1215,7 +3103,9 @@ async fn handle_cc_run_hook_command(event: &str, config: Config) -> Result<()> {
 
     let message = HookMessage {
         event_name: event.to_string(),
-        issue_number,
+        owner: issue.owner.clone(),
+        repo: issue.repo.clone(),
+        issue_number: issue.number,
         payload,
     };
 
@@ -1223,15 +3113,15 @@ async fn handle_cc_run_hook_command(event: &str, config: Config) -> Result<()> {
         Ok(response) => {
             if response.success {
                 tracing::info!(
-                    "Hook '{}' sent to daemon for issue #{}",
+                    "Hook '{}' sent to daemon for issue {}",
                     event,
-                    issue_number
+                    issue
                 );
             } else {
                 tracing::warn!(
-                    "Daemon rejected hook '{}' for issue #{}: {:?}",
+                    "Daemon rejected hook '{}' for issue {}: {:?}",
                     event,
-                    issue_number,
+                    issue,
                     response.message
                 );
             }
@@ -1249,6 +3139,43 @@ async fn handle_cc_run_hook_command(event: &str, config: Config) -> Result<()> {
     Ok(())
 }
 
+/// Follow a hook event from a quick session's Claude Code
+async fn handle_quick_hook(
+    event: &str,
+    payload: &serde_json::Value,
+    quick_ref: &QuickRef,
+    config: &Config,
+) -> Result<()> {
+    let Some(target_state) = quick::hook_state(event, payload) else {
+        return Ok(());
+    };
+    let Some(repo) = config
+        .repos()
+        .into_iter()
+        .find(|r| r.github.owner == quick_ref.owner && r.github.repo == quick_ref.repo)
+    else {
+        return Ok(());
+    };
+
+    let session_id = payload.get("session_id").and_then(|v| v.as_str());
+    let recorded = QuickTracker::update(&repo.quick_state_file()?, |tracker| {
+        tracker.get(&quick_ref.id)?;
+        Some(tracker.record_hook(&quick_ref.id, target_state, session_id))
+    })?;
+    let Some(changed) = recorded else {
+        tracing::debug!("{} is not tracked, ignoring hook '{}'", quick_ref, event);
+        return Ok(());
+    };
+
+    if let Some(state) = changed {
+        tracing::info!("Hook '{}' moved {} to {}", event, quick_ref, state.name());
+        session::backend(&config.tmux, Vec::new())
+            .rename_window(quick_ref, state.name())
+            .await?;
+    }
+    Ok(())
+}
+
 fn handle_hooks_command(action: HooksAction) -> Result<()> {
     match action {
         HooksAction::Generate => {
@@ -1373,7 +3300,7 @@ fn handle_stop_command(config: Config) -> Result<()> {
     Ok(())
 }
 
-fn run_daemon_mode(config: Config, verbose: bool) -> Result<()> {
+fn run_daemon_mode(config: Config, http: Option<std::net::SocketAddr>, verbose: bool) -> Result<()> {
     use daemonize::Daemonize;
     use std::fs;
 
@@ -1475,17 +3402,22 @@ fn run_daemon_mode(config: Config, verbose: bool) -> Result<()> {
 
     // Run the orchestrator
     runtime.block_on(async {
-        let mut orchestrator = Orchestrator::new(config).await?;
+        let mut orchestrator = Orchestrator::new(config).await?.with_dashboard(http)?;
         orchestrator.run().await
     })
 }
 
-async fn handle_command(command: Commands, config: Config) -> Result<()> {
+async fn handle_command(
+    command: Commands,
+    repo: Option<&str>,
+    format: OutputFormat,
+    config: Config,
+) -> Result<()> {
     match command {
-        Commands::Watch { daemon: _ } => {
+        Commands::Watch { daemon: _, http, .. } => {
             // Daemon mode is handled before tokio runtime is created
             // This branch is only reached for non-daemon watch
-            let mut orchestrator = Orchestrator::new(config).await?;
+            let mut orchestrator = Orchestrator::new(config).await?.with_dashboard(http)?;
             orchestrator.run().await?;
         }
         Commands::Log { follow, lines } => {
@@ -1494,26 +3426,15 @@ async fn handle_command(command: Commands, config: Config) -> Result<()> {
         Commands::Stop => {
             handle_stop_command(config)?;
         }
-        Commands::List => {
-            let tmux_manager = TmuxManager::new(&config.tmux);
-            let issue_numbers = tmux_manager.list_windows().await.context("Failed to list issue windows")?;
-
-            if issue_numbers.is_empty() {
-                println!("No active issue windows in session '{}'", config.tmux.session_name);
-            } else {
-                println!("Active issue windows in session '{}':", config.tmux.session_name);
-                for issue_number in issue_numbers {
-                    println!("  - issue-{}", issue_number);
-                }
-            }
+        Commands::List { fix } => {
+            handle_list_command(repo, fix, format, config).await?;
         }
         Commands::Attach => {
-            // Create TmuxManager with GitHub token for session creation
-            let token = std::env::var(&config.github.token_env).ok();
-            let mut tmux_manager = TmuxManager::new(&config.tmux);
-            if let Some(token) = token {
-                tmux_manager = tmux_manager.with_env(&config.github.token_env, token);
-            }
+            // Create the session backend with GitHub token for session creation
+            let env = std::env::var(&config.github.token_env)
+                .map(|token| vec![(config.github.token_env.clone(), token)])
+                .unwrap_or_default();
+            let tmux_manager = session::backend(&config.tmux, env);
 
             // Ensure the session exists before attaching
             tmux_manager.ensure_session().await.context("Failed to ensure tmux session exists")?;
@@ -1531,15 +3452,30 @@ async fn handle_command(command: Commands, config: Config) -> Result<()> {
         Commands::Transition {
             issue_number,
             state,
+            force,
         } => {
-            handle_transition_command(issue_number, &state, config).await?;
+            handle_transition_command(
+                issue_number,
+                &state,
+                force,
+                &config.select_repo(repo)?,
+                &config.comments,
+                &config.costs,
+            )
+            .await?;
         }
         Commands::CcRunHook { event } => {
             handle_cc_run_hook_command(&event, config).await?;
         }
-        Commands::Status { issue_number } => {
-            handle_status_command(issue_number, config).await?;
-        }
+        Commands::Status { session } => match session {
+            Some(SessionKey::Quick(id)) => {
+                handle_quick_status_command(&id, &config.select_repo(repo)?, format, &config).await?;
+            }
+            Some(SessionKey::Issue(number)) => {
+                handle_status_command(Some(number), repo, format, config).await?;
+            }
+            None => handle_status_command(None, repo, format, config).await?,
+        },
         Commands::Hooks { action } => {
             handle_hooks_command(action)?;
         }
@@ -1547,13 +3483,350 @@ async fn handle_command(command: Commands, config: Config) -> Result<()> {
             // Already handled above, shouldn't reach here
             unreachable!("Config command should be handled before this point");
         }
-        Commands::Cleanup { issue_number } => {
-            handle_cleanup_command(issue_number, config).await?;
+        Commands::Cleanup { session } => match session {
+            SessionKey::Issue(number) => {
+                handle_cleanup_command(number, &config.select_repo(repo)?, config).await?;
+            }
+            SessionKey::Quick(id) => {
+                handle_quick_cleanup_command(&id, &config.select_repo(repo)?, &config).await?;
+            }
+        },
+        Commands::Quick { branch, prompt } => {
+            handle_quick_command(&branch, prompt.as_deref(), &config.select_repo(repo)?, &config).await?;
         }
         Commands::Restore => {
-            handle_restore_command(config).await?;
+            handle_restore_command(repo, format, config).await?;
+        }
+        Commands::New {
+            title,
+            suggest,
+            no_ready,
+            labels,
+            assignee,
+        } => {
+            handle_new_command(
+                title,
+                suggest,
+                no_ready,
+                labels,
+                assignee,
+                &config.select_repo(repo)?,
+                &config,
+            )
+            .await?;
+        }
+        Commands::Ui => {
+            handle_ui_command(repo, config).await?;
+        }
+        Commands::Report {
+            report: ReportKind::Costs { since },
+        } => {
+            handle_costs_report(since, repo, format, &config)?;
+        }
+        Commands::Transcript {
+            issue_number,
+            raw,
+            markdown,
+        } => {
+            let format = match markdown {
+                true => transcript::Format::Markdown,
+                false => transcript::Format::Text,
+            };
+            handle_transcript_command(issue_number, raw, format, &config.select_repo(repo)?)?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Path as Route, Query, State};
+    use axum::routing::{delete, get, post};
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Mutex;
+
+    /// Issues as GitHub sees them: title and labels by number, and the
+    /// issues that have a merged PR
+    #[derive(Default)]
+    struct FakeGitHub {
+        issues: BTreeMap<u64, (String, Vec<String>)>,
+        merged: BTreeSet<u64>,
+    }
+
+    type Shared = Arc<Mutex<FakeGitHub>>;
+
+    fn user_json(login: &str) -> Value {
+        let mut user = json!({
+            "login": login,
+            "id": 1,
+            "node_id": "U_1",
+            "gravatar_id": "",
+            "type": "User",
+            "site_admin": false,
+            "patch_url": null,
+        });
+        for field in [
+            "avatar_url", "url", "html_url", "followers_url", "following_url", "gists_url",
+            "starred_url", "subscriptions_url", "organizations_url", "repos_url", "events_url",
+            "received_events_url",
+        ] {
+            user[field] = json!(format!("https://github.com/{}", login));
+        }
+        user
+    }
+
+    fn labels_json(labels: &[String]) -> Value {
+        labels
+            .iter()
+            .map(|name| {
+                json!({
+                    "id": 1,
+                    "node_id": "LA_1",
+                    "url": "https://github.com/labels",
+                    "name": name,
+                    "color": "ededed",
+                    "default": false,
+                })
+            })
+            .collect()
+    }
+
+    fn issue_json(owner: &str, repo: &str, number: u64, title: &str, labels: &[String]) -> Value {
+        let url = format!("https://github.com/{}/{}/issues/{}", owner, repo, number);
+        json!({
+            "id": number,
+            "node_id": format!("I_{}", number),
+            "url": url,
+            "repository_url": format!("https://github.com/{}/{}", owner, repo),
+            "labels_url": format!("{}/labels", url),
+            "comments_url": format!("{}/comments", url),
+            "events_url": format!("{}/events", url),
+            "html_url": url,
+            "number": number,
+            "state": "open",
+            "state_reason": null,
+            "title": title,
+            "body": "",
+            "user": user_json("alice"),
+            "labels": labels_json(labels),
+            "assignees": [],
+            "author_association": "OWNER",
+            "locked": false,
+            "comments": 0,
+            "created_at": "2024-06-01T00:00:00Z",
+            "updated_at": "2024-06-01T00:00:00Z",
+        })
+    }
+
+    /// Serve the REST and GraphQL routes the daemon uses to provision issues
+    /// and watch for merged PRs, and return the API's base URL
+    async fn serve_github(github: Shared) -> String {
+        async fn list(
+            State(github): State<Shared>,
+            Route((owner, repo)): Route<(String, String)>,
+            Query(query): Query<HashMap<String, String>>,
+        ) -> Json<Value> {
+            let github = github.lock().unwrap();
+            let label = query.get("labels").cloned().unwrap_or_default();
+            Json(
+                github
+                    .issues
+                    .iter()
+                    .filter(|(_, (_, labels))| labels.contains(&label))
+                    .map(|(number, (title, labels))| issue_json(&owner, &repo, *number, title, labels))
+                    .collect(),
+            )
+        }
+
+        async fn issue(
+            State(github): State<Shared>,
+            Route((owner, repo, number)): Route<(String, String, u64)>,
+        ) -> Json<Value> {
+            let github = github.lock().unwrap();
+            let (title, labels) = &github.issues[&number];
+            Json(issue_json(&owner, &repo, number, title, labels))
+        }
+
+        async fn add_labels(
+            State(github): State<Shared>,
+            Route((_, _, number)): Route<(String, String, u64)>,
+            Json(body): Json<Value>,
+        ) -> Json<Value> {
+            let mut github = github.lock().unwrap();
+            let labels = &mut github.issues.get_mut(&number).unwrap().1;
+            for label in body["labels"].as_array().unwrap() {
+                labels.push(label.as_str().unwrap().to_string());
+            }
+            Json(labels_json(labels))
+        }
+
+        async fn remove_label(
+            State(github): State<Shared>,
+            Route((_, _, number, label)): Route<(String, String, u64, String)>,
+        ) -> Json<Value> {
+            let mut github = github.lock().unwrap();
+            let labels = &mut github.issues.get_mut(&number).unwrap().1;
+            labels.retain(|l| *l != label);
+            Json(labels_json(labels))
+        }
+
+        async fn graphql(State(github): State<Shared>) -> Json<Value> {
+            // Answers every issue at once; the daemon only reads the ones it asked for
+            let github = github.lock().unwrap();
+            let repository: serde_json::Map<String, Value> = github
+                .merged
+                .iter()
+                .map(|number| {
+                    let pr = json!({
+                        "number": 100 + number,
+                        "url": format!("https://github.com/acme/api/pull/{}", 100 + number),
+                        "headRefName": format!("{}-branch", number),
+                        "state": "MERGED",
+                        "createdAt": "2024-06-02T00:00:00Z",
+                    });
+                    (format!("i{}", number), json!({ "closedByPullRequestsReferences": { "nodes": [pr] } }))
+                })
+                .collect();
+            Json(json!({ "data": { "repository": repository } }))
+        }
+
+        let app = Router::new()
+            .route("/user", get(|| async { Json(user_json("pleb-bot")) }))
+            .route("/repos/:owner/:repo/issues", get(list))
+            .route("/repos/:owner/:repo/issues/:number", get(issue))
+            .route("/repos/:owner/:repo/issues/:number/labels", post(add_labels))
+            .route("/repos/:owner/:repo/issues/:number/labels/:label", delete(remove_label))
+            .route("/graphql", post(graphql))
+            .with_state(github);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{}", addr)
+    }
+
+    /// A repository to make worktrees from, a prompt template and a config
+    /// for them, talking to the fake GitHub at `api_url`. `agent` is the
+    /// `[agents.stub]` section.
+    fn setup(name: &str, api_url: &str, agent: &str) -> (std::path::PathBuf, Config) {
+        std::env::set_var("GITHUB_TOKEN", "test-token");
+        let dir = std::env::temp_dir().join(format!("pleb-{}-test-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo_dir = dir.join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        for args in [&["init", "-q"][..], &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-q", "--allow-empty", "-m", "init"]] {
+            let status = std::process::Command::new("git").arg("-C").arg(&repo_dir).args(args).status().unwrap();
+            assert!(status.success());
+        }
+        std::fs::write(dir.join("new_issue.md"), "Work on #{{issue_number}}: {{title}}").unwrap();
+
+        let config = Config::from_str(&format!(
+            "[github]\nowner = \"pleb-test\"\nrepo = \"{name}-{pid}\"\napi_url = \"{api_url}\"\n\
+             [labels]\n[claude]\n[watch]\n[tmux]\n[branch]\n\
+             [paths]\nrepo_dir = \"{dir}/repo\"\nworktree_base = \"{dir}/worktrees\"\n\
+             [prompts]\ndir = \"{dir}\"\n\
+             [comments]\nstatus_comment = false\n\
+             [agent]\ndefault = \"stub\"\n\
+             [agents.stub]\n{agent}\n",
+            name = name,
+            pid = std::process::id(),
+            api_url = api_url,
+            dir = dir.display(),
+            agent = agent,
+        ))
+        .unwrap();
+        (dir, config)
+    }
+
+    /// Remove what a test left in its temp dir and ~/.pleb
+    fn cleanup(dir: &Path, config: &Config) {
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_dir_all(config.repos()[0].state_dir().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_provision_headless_issue_without_backend() {
+        let github = Shared::default();
+        github.lock().unwrap().issues.insert(7, ("Add invoices".to_string(), vec!["pleb:ready".to_string()]));
+        let api_url = serve_github(github.clone()).await;
+        let (dir, config) = setup("headless", &api_url, "command = \"true\"\nmode = \"headless\"");
+
+        // Every agent is headless, so there is no multiplexer to ask about windows
+        let tmux = session::issue_backend(&config, Vec::new());
+        assert!(tmux.is_none());
+        let mut orchestrator = Orchestrator::with_backend(config.clone(), tmux).await.unwrap();
+        let repo = &mut orchestrator.repos[0];
+        repo.templates.load_template(&repo.config.prompts.new_issue).unwrap();
+        let mut headless_rx = orchestrator.headless_rx.take().unwrap();
+
+        orchestrator.poll_cycle().await.unwrap();
+
+        assert_eq!(github.lock().unwrap().issues[&7].1, vec!["pleb:working"]);
+        let tracked = orchestrator.repos[0].tracker.get(7).unwrap().clone();
+        assert_eq!(tracked.state, PlebState::Working);
+        assert_eq!(tracked.branch_name.as_deref(), Some("7-add-invoices_pleb-bot_pleb"));
+        assert_eq!(tracked.tmux_window, None);
+        assert!(tracked.worktree_path.unwrap().is_dir());
+
+        // The agent ran in the background, without a window
+        let mut events = Vec::new();
+        while let Some(update) = headless_rx.recv().await {
+            events.push(update.event);
+            if matches!(events.last(), Some(HeadlessEvent::Stopped { .. })) {
+                break;
+            }
+        }
+        assert!(matches!(events.last(), Some(HeadlessEvent::Stopped { state: PlebState::Done, .. })));
+
+        cleanup(&dir, &config);
+    }
+
+    #[tokio::test]
+    async fn test_interactive_issue_window_lifecycle() {
+        let github = Shared::default();
+        github.lock().unwrap().issues.insert(8, ("Fix login".to_string(), vec!["pleb:ready".to_string()]));
+        let api_url = serve_github(github.clone()).await;
+        let (dir, config) = setup("interactive", &api_url, "command = \"stub-agent\"");
+
+        let backend = Arc::new(session::fake::FakeBackend::default());
+        let mut orchestrator = Orchestrator::with_backend(config.clone(), Some(backend.clone())).await.unwrap();
+        let repo = &mut orchestrator.repos[0];
+        repo.templates.load_template(&repo.config.prompts.new_issue).unwrap();
+        let issue = repo.issue_ref(8);
+
+        // Provisioning opens a window in the worktree and starts the agent in it
+        orchestrator.poll_cycle().await.unwrap();
+
+        assert_eq!(github.lock().unwrap().issues[&8].1, vec!["pleb:working"]);
+        let window = format!("pleb-test/interactive-{}/8-fix-login_pleb-bot_pleb", std::process::id());
+        let tracked = orchestrator.repos[0].tracker.get(8).unwrap().clone();
+        assert_eq!(tracked.tmux_window.as_deref(), Some(window.as_str()));
+        let windows = backend.windows();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].name, format!("{}:working", window));
+        assert_eq!(Some(&windows[0].working_dir), tracked.worktree_path.as_ref());
+        let keys = backend.keys(&issue).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].starts_with("stub-agent "));
+        assert!(keys[0].ends_with("prompt.md"));
+
+        // A poll while the window is open leaves the issue alone
+        github.lock().unwrap().issues.get_mut(&8).unwrap().1 = vec!["pleb:ready".to_string()];
+        orchestrator.poll_cycle().await.unwrap();
+        assert_eq!(backend.keys(&issue).unwrap().len(), 1);
+        github.lock().unwrap().issues.get_mut(&8).unwrap().1 = vec!["pleb:working".to_string()];
+
+        // Once its PR is merged the issue is finished and its window says so
+        github.lock().unwrap().merged.insert(8);
+        orchestrator.check_merged_prs(0).await.unwrap();
+
+        assert_eq!(github.lock().unwrap().issues[&8].1, vec!["pleb:finished"]);
+        assert_eq!(orchestrator.repos[0].tracker.get(8).unwrap().state, PlebState::Finished);
+        assert_eq!(backend.windows()[0].name, format!("{}:finished", window));
+
+        cleanup(&dir, &config);
+    }
+}
@@ -7,6 +7,8 @@ use anyhow::{Context, Result};
 use regex::Regex;
 use std::path::{Path, PathBuf};
 
+use crate::telemetry;
+
 /// Type of media found in issue description
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MediaType {
@@ -259,6 +261,7 @@ pub async fn process_issue_body(
             Err(e) => {
                 // Keep original on failure
                 tracing::warn!("Failed to download {}: {}. Keeping original URL.", item.url, e);
+                metrics::counter!(telemetry::MEDIA_DOWNLOAD_FAILURES).increment(1);
             }
         }
     }
@@ -361,6 +364,7 @@ pub async fn process_issue_body_with_html(
                     body_item.url,
                     e
                 );
+                metrics::counter!(telemetry::MEDIA_DOWNLOAD_FAILURES).increment(1);
             }
         }
     }
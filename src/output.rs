@@ -0,0 +1,280 @@
+//! Structured records printed by `--format json`.
+//!
+//! `status`, `list`, `restore` and `report` print these instead of their tables so
+//! scripts and editor integrations can use pleb directly. Field names are part
+//! of the interface; add fields rather than renaming them.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::inventory::{Problem, Row};
+use crate::quick::QuickSession;
+use crate::state::{IssueTracker, TrackedIssue};
+use crate::usage::{ModelTokens, Tokens};
+
+/// Whether the daemon is running
+#[derive(Debug, Serialize)]
+pub struct DaemonRecord {
+    pub running: bool,
+    pub pid: Option<i32>,
+    pub uptime_secs: Option<u64>,
+}
+
+/// One issue as known to pleb
+#[derive(Debug, Serialize)]
+pub struct IssueRecord {
+    /// "owner/repo"
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    /// pleb state name, or None if the issue is not managed by pleb
+    pub state: Option<String>,
+    /// 1-based position in the provisioning queue, if queued
+    pub queue_position: Option<usize>,
+    pub url: String,
+    pub pr_url: Option<String>,
+    pub branch: Option<String>,
+    pub worktree_path: Option<PathBuf>,
+    pub session_id: Option<String>,
+    /// The agent's transcript, as reported by its hooks
+    pub transcript_path: Option<PathBuf>,
+    /// Tokens used by the issue's sessions, all models together
+    pub tokens: Option<Tokens>,
+    /// Cost of those tokens in USD, if every model used has a price
+    pub cost_usd: Option<f64>,
+    pub window_exists: bool,
+}
+
+impl IssueRecord {
+    /// An issue pleb has no record of
+    pub fn untracked(repo: &str, number: u64, window_exists: bool) -> Self {
+        Self {
+            repo: repo.to_string(),
+            number,
+            title: String::new(),
+            state: None,
+            queue_position: None,
+            url: String::new(),
+            pr_url: None,
+            branch: None,
+            worktree_path: None,
+            session_id: None,
+            transcript_path: None,
+            tokens: None,
+            cost_usd: None,
+            window_exists,
+        }
+    }
+
+    pub fn from_tracked(
+        repo: &str,
+        tracked: &TrackedIssue,
+        queue_position: Option<usize>,
+        window_exists: bool,
+    ) -> Self {
+        Self {
+            repo: repo.to_string(),
+            number: tracked.issue_number,
+            title: tracked.title.clone(),
+            state: Some(tracked.state.name().to_string()),
+            queue_position: queue_position.map(|position| position + 1),
+            url: tracked.html_url.clone(),
+            pr_url: tracked.pr_url.clone(),
+            branch: tracked.branch_name.clone(),
+            worktree_path: tracked.worktree_path.clone(),
+            session_id: tracked.session_id.clone(),
+            transcript_path: tracked.transcript_path.clone(),
+            tokens: None,
+            cost_usd: None,
+            window_exists,
+        }
+    }
+}
+
+/// One row of `pleb list`: an issue with what tmux, git and GitHub say about it
+#[derive(Debug, Serialize)]
+pub struct ListRecord {
+    #[serde(flatten)]
+    pub issue: IssueRecord,
+    pub worktree_exists: bool,
+    /// Whether one of the issue's PRs is merged, if it has any
+    pub pr_merged: Option<bool>,
+    pub problems: Vec<Problem>,
+}
+
+impl ListRecord {
+    /// Describe an inventory row, using the local record of the issue when there is one
+    pub fn new(repo: &str, row: &Row, tracker: &IssueTracker) -> Self {
+        let mut issue = match tracker.get(row.number) {
+            Some(tracked) => {
+                IssueRecord::from_tracked(repo, tracked, tracker.queue_position(row.number), row.window)
+            }
+            None => IssueRecord::untracked(repo, row.number, row.window),
+        };
+        // GitHub is authoritative for the state, title and URL
+        issue.state = row.state.map(|state| state.name().to_string());
+        issue.title = row.title.clone();
+        issue.url = row.url.clone();
+        if issue.worktree_path.is_none() {
+            issue.worktree_path = row.worktree.clone();
+        }
+
+        Self {
+            issue,
+            worktree_exists: row.worktree.is_some(),
+            pr_merged: row.pr_merged,
+            problems: row.problems.clone(),
+        }
+    }
+}
+
+/// A `pleb quick` session
+#[derive(Debug, Serialize)]
+pub struct QuickRecord {
+    /// "owner/repo"
+    pub repo: String,
+    pub id: String,
+    pub branch: String,
+    pub state: String,
+    pub worktree_path: Option<PathBuf>,
+    pub session_id: Option<String>,
+    pub window_exists: bool,
+}
+
+impl QuickRecord {
+    pub fn new(repo: &str, session: &QuickSession, window_exists: bool) -> Self {
+        Self {
+            repo: repo.to_string(),
+            id: session.id.clone(),
+            branch: session.branch_name.clone(),
+            state: session.state.name().to_string(),
+            worktree_path: session.worktree_path.clone(),
+            session_id: session.session_id.clone(),
+            window_exists,
+        }
+    }
+}
+
+/// Output of `pleb status` without an issue number
+#[derive(Debug, Serialize)]
+pub struct StatusRecord {
+    pub daemon: DaemonRecord,
+    pub issues: Vec<IssueRecord>,
+    pub quick_sessions: Vec<QuickRecord>,
+}
+
+/// What `pleb restore` did for one issue
+#[derive(Debug, Serialize)]
+pub struct RestoreRecord {
+    /// "owner/repo"
+    pub repo: String,
+    pub number: u64,
+    pub window_existed: bool,
+    pub worktree_existed: bool,
+    /// Whether a new session was started
+    pub restored: bool,
+}
+
+/// One issue in `pleb report costs`
+#[derive(Debug, Serialize)]
+pub struct CostRecord {
+    /// "owner/repo"
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub models: ModelTokens,
+    pub tokens: Tokens,
+    /// None if a model used has no price
+    pub cost_usd: Option<f64>,
+}
+
+/// Output of `pleb report costs`
+#[derive(Debug, Serialize)]
+pub struct CostReport {
+    /// First day counted, if limited with --since
+    pub since: Option<chrono::NaiveDate>,
+    pub issues: Vec<CostRecord>,
+    /// Tokens by model over all issues
+    pub models: ModelTokens,
+    pub tokens: Tokens,
+    pub cost_usd: Option<f64>,
+}
+
+/// Print a value as pretty JSON on stdout
+pub fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Shorten text to at most `max_chars` characters, ending in "..." when cut
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PlebState;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Fix login", 60), "Fix login");
+        assert_eq!(truncate("abcdefghij", 8), "abcde...");
+        // Multibyte characters are counted, not bytes
+        let title = "Données de facturation: gérer les échéances et les relances automatiques";
+        let short = truncate(title, 60);
+        assert_eq!(short.chars().count(), 60);
+        assert!(short.ends_with("..."));
+        assert_eq!(truncate("日本語のタイトル", 5), "日本...");
+    }
+
+    #[test]
+    fn test_issue_record_json() {
+        let mut tracker = IssueTracker::new();
+        let tracked = tracker.track(42, PlebState::Waiting);
+        tracked.title = "Add invoices".to_string();
+        tracked.html_url = "https://github.com/acme/api/issues/42".to_string();
+        tracked.worktree_path = Some("/work/42-add-invoices".into());
+
+        let record = IssueRecord::from_tracked("acme/api", tracked, None, true);
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["repo"], "acme/api");
+        assert_eq!(json["number"], 42);
+        assert_eq!(json["state"], "waiting");
+        assert_eq!(json["worktree_path"], "/work/42-add-invoices");
+        assert_eq!(json["window_exists"], true);
+        assert!(json["pr_url"].is_null());
+    }
+
+    #[test]
+    fn test_list_record_json() {
+        let row = Row {
+            number: 7,
+            title: "Fix login".to_string(),
+            url: "https://github.com/acme/api/issues/7".to_string(),
+            state: Some(PlebState::Done),
+            window: false,
+            worktree: Some("/work/7-fix-login".into()),
+            pr_merged: Some(true),
+            problems: vec![Problem::LabelWithoutSession, Problem::BranchMerged],
+        };
+
+        let record = ListRecord::new("acme/api", &row, &IssueTracker::new());
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["number"], 7);
+        assert_eq!(json["state"], "done");
+        assert_eq!(json["worktree_path"], "/work/7-fix-login");
+        assert_eq!(json["worktree_exists"], true);
+        assert_eq!(json["window_exists"], false);
+        assert_eq!(json["pr_merged"], true);
+        assert_eq!(
+            json["problems"],
+            serde_json::json!(["label_without_session", "branch_merged"])
+        );
+    }
+}
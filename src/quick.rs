@@ -0,0 +1,320 @@
+//! Ad-hoc sessions started with `pleb quick`, for work too small for an issue.
+//!
+//! A quick session gets the same worktree, tmux window and Claude Code hooks as
+//! an issue, but never touches GitHub labels. It is identified by an id derived
+//! from its branch name, which can't be a number so it never collides with an
+//! issue, and is tracked in its own state file next to the issue state.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::state::{self, unix_now, PlebState};
+use crate::session::WindowTarget;
+
+/// Worktree directories and window names of quick sessions start with this
+pub const PREFIX: &str = "quick-";
+
+/// What a command or worktree path refers to: an issue or a quick session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionKey {
+    Issue(u64),
+    Quick(String),
+}
+
+impl std::str::FromStr for SessionKey {
+    type Err = String;
+
+    /// "42" and "#42" are issues; anything else is a quick session id
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(number) = s.trim_start_matches('#').parse::<u64>() {
+            return Ok(SessionKey::Issue(number));
+        }
+        let id = s.strip_prefix(PREFIX).unwrap_or(s);
+        if id.is_empty() {
+            return Err("expected an issue number or a quick session id".to_string());
+        }
+        Ok(SessionKey::Quick(id.to_string()))
+    }
+}
+
+impl std::fmt::Display for SessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionKey::Issue(number) => write!(f, "#{}", number),
+            SessionKey::Quick(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+/// Id of the quick session for a branch: the branch name with anything other
+/// than letters, digits, '-', '_' and '.' replaced by '-'
+pub fn quick_id(branch_name: &str) -> Result<String> {
+    let id: String = branch_name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let id = id.trim_matches('-').to_string();
+
+    if id.is_empty() {
+        anyhow::bail!("Branch name '{}' is not usable for a quick session", branch_name);
+    }
+    if id.chars().all(|c| c.is_ascii_digit()) {
+        anyhow::bail!(
+            "Branch name '{}' is a number, which would be mistaken for an issue",
+            branch_name
+        );
+    }
+    Ok(id)
+}
+
+/// State a quick session moves to on a Claude Code hook event, mirroring what
+/// the daemon does for issues (None if the event doesn't change it)
+pub fn hook_state(event: &str, payload: &serde_json::Value) -> Option<PlebState> {
+    match event {
+        "UserPromptSubmit" => Some(PlebState::Working),
+        "Stop" | "PermissionRequest" => Some(PlebState::Waiting),
+        "PostToolUse"
+            if payload.get("tool_name").and_then(|v| v.as_str()) == Some("AskUserQuestion") =>
+        {
+            Some(PlebState::Waiting)
+        }
+        _ => None,
+    }
+}
+
+/// A quick session in one repository
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuickRef {
+    pub owner: String,
+    pub repo: String,
+    pub id: String,
+}
+
+impl QuickRef {
+    pub fn new(owner: impl Into<String>, repo: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            owner: owner.into(),
+            repo: repo.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Name of the session's worktree directory: "quick-{id}"
+    pub fn worktree_name(&self) -> String {
+        format!("{}{}", PREFIX, self.id)
+    }
+}
+
+impl std::fmt::Display for QuickRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} quick session '{}'", self.owner, self.repo, self.id)
+    }
+}
+
+impl WindowTarget for QuickRef {
+    /// "{owner}/{repo}/quick-{id}", whatever the branch is called
+    fn window_name(&self, _branch_name: &str) -> String {
+        format!("{}/{}/{}", self.owner, self.repo, self.worktree_name())
+    }
+
+    fn matches_window(&self, name: &str) -> bool {
+        let base_name = name.split(':').next().unwrap_or(name);
+        base_name == self.window_name("")
+    }
+
+    fn state_dir_name(&self) -> String {
+        self.worktree_name()
+    }
+}
+
+/// A quick session as recorded locally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickSession {
+    pub id: String,
+    pub branch_name: String,
+    /// Waiting or working, following the Claude Code hooks
+    pub state: PlebState,
+    pub worktree_path: Option<PathBuf>,
+    /// Base name of the tmux window (without the ":state" suffix)
+    #[serde(default)]
+    pub tmux_window: Option<String>,
+    /// Claude Code session id, taken from the most recent hook payload
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Unix timestamp (seconds) when the session was started
+    pub started_at: u64,
+    /// Unix timestamp (seconds) of the last change to this record
+    pub last_updated: u64,
+}
+
+/// The quick sessions of one repository, persisted in `quick.json` in its
+/// state directory (see `RepoConfig::quick_state_file`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuickTracker {
+    sessions: BTreeMap<String, QuickSession>,
+}
+
+impl QuickTracker {
+    /// Load the sessions from disk, returning an empty tracker if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        state::load_json(path, "quick session file")
+    }
+
+    /// Load the sessions, apply a change and save them under the same lock as
+    /// `IssueTracker::update`, so `pleb quick` and concurrent hooks don't lose
+    /// each other's writes. Every writer goes through here.
+    pub fn update<T>(path: &Path, f: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        state::update_json(path, "quick session file", f)
+    }
+
+    /// Record a new session, replacing any earlier one with the same id
+    pub fn start(&mut self, id: &str, branch_name: &str, state: PlebState) -> &mut QuickSession {
+        let now = unix_now();
+        self.sessions.insert(
+            id.to_string(),
+            QuickSession {
+                id: id.to_string(),
+                branch_name: branch_name.to_string(),
+                state,
+                worktree_path: None,
+                tmux_window: None,
+                session_id: None,
+                started_at: now,
+                last_updated: now,
+            },
+        );
+        self.sessions.get_mut(id).unwrap()
+    }
+
+    /// Forget a session
+    pub fn remove(&mut self, id: &str) -> Option<QuickSession> {
+        self.sessions.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&QuickSession> {
+        self.sessions.get(id)
+    }
+
+    /// All sessions, sorted by id
+    pub fn all(&self) -> Vec<&QuickSession> {
+        self.sessions.values().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Follow a hook event. Returns the new state if it changed.
+    pub fn record_hook(&mut self, id: &str, state: PlebState, session_id: Option<&str>) -> Option<PlebState> {
+        let session = self.sessions.get_mut(id)?;
+        if let Some(session_id) = session_id {
+            session.session_id = Some(session_id.to_string());
+        }
+        session.last_updated = unix_now();
+        if session.state == state {
+            return None;
+        }
+        session.state = state;
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_key() {
+        assert_eq!("42".parse(), Ok(SessionKey::Issue(42)));
+        assert_eq!("#42".parse(), Ok(SessionKey::Issue(42)));
+        assert_eq!("fix-typo".parse(), Ok(SessionKey::Quick("fix-typo".to_string())));
+        assert_eq!("quick-fix-typo".parse(), Ok(SessionKey::Quick("fix-typo".to_string())));
+        assert!("".parse::<SessionKey>().is_err());
+    }
+
+    #[test]
+    fn test_quick_id() {
+        assert_eq!(quick_id("fix-typo").unwrap(), "fix-typo");
+        assert_eq!(quick_id("alice/bump deps").unwrap(), "alice-bump-deps");
+        assert_eq!(quick_id("123-hotfix").unwrap(), "123-hotfix");
+        assert!(quick_id("123").is_err());
+        assert!(quick_id("//").is_err());
+    }
+
+    #[test]
+    fn test_quick_window() {
+        let quick = QuickRef::new("acme", "api", "fix-typo");
+        let name = quick.window_name("alice/fix-typo");
+        assert_eq!(name, "acme/api/quick-fix-typo");
+        assert!(quick.matches_window(&name));
+        assert!(quick.matches_window("acme/api/quick-fix-typo:waiting"));
+        assert!(!quick.matches_window("acme/api/quick-fix-typo-2"));
+        // Quick windows are never mistaken for issue windows
+        assert_eq!(crate::session::parse_window_name("acme/api/quick-123-hotfix"), None);
+    }
+
+    #[test]
+    fn test_hook_state() {
+        let none = serde_json::json!({});
+        assert_eq!(hook_state("UserPromptSubmit", &none), Some(PlebState::Working));
+        assert_eq!(hook_state("Stop", &none), Some(PlebState::Waiting));
+        assert_eq!(hook_state("PostToolUse", &none), None);
+        let question = serde_json::json!({"tool_name": "AskUserQuestion"});
+        assert_eq!(hook_state("PostToolUse", &question), Some(PlebState::Waiting));
+    }
+
+    #[test]
+    fn test_record_hook() {
+        let mut tracker = QuickTracker::default();
+        tracker.start("fix-typo", "fix-typo", PlebState::Working);
+
+        assert_eq!(tracker.record_hook("fix-typo", PlebState::Working, Some("abc")), None);
+        assert_eq!(
+            tracker.record_hook("fix-typo", PlebState::Waiting, None),
+            Some(PlebState::Waiting)
+        );
+        let session = tracker.get("fix-typo").unwrap();
+        assert_eq!(session.state, PlebState::Waiting);
+        assert_eq!(session.session_id.as_deref(), Some("abc"));
+        assert_eq!(tracker.record_hook("other", PlebState::Waiting, None), None);
+    }
+
+    #[test]
+    fn test_concurrent_hooks_are_not_lost() {
+        let dir = std::env::temp_dir().join(format!("pleb-quick-lock-test-{}", std::process::id()));
+        let path = dir.join("quick.json");
+        QuickTracker::update(&path, |t| {
+            for id in ["a", "b", "c", "d"] {
+                t.start(id, id, PlebState::Working);
+            }
+        })
+        .unwrap();
+
+        let hooks: Vec<_> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|id| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    QuickTracker::update(&path, |t| t.record_hook(id, PlebState::Waiting, Some(id))).unwrap()
+                })
+            })
+            .collect();
+        for hook in hooks {
+            assert_eq!(hook.join().unwrap(), Some(PlebState::Waiting));
+        }
+
+        let tracker = QuickTracker::load(&path).unwrap();
+        assert!(tracker.all().iter().all(|s| s.state == PlebState::Waiting && s.session_id.as_deref() == Some(s.id.as_str())));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
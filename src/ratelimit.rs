@@ -0,0 +1,192 @@
+//! GitHub API rate-limit budget.
+//!
+//! Every REST response carries `x-ratelimit-*` headers for the resource the
+//! request was charged against ("core", "search", "graphql", ...). The limits
+//! belong to the token rather than to a repository, so the budget is tracked
+//! process-wide and shared by every `GitHubClient`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::HeaderMap;
+
+use crate::telemetry;
+
+/// Resources whose budget gates polling
+const POLLED_RESOURCES: [&str; 2] = ["core", "search"];
+
+/// Remaining quota for one rate-limited resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix timestamp (seconds) when the quota resets
+    pub reset: u64,
+}
+
+impl RateLimit {
+    /// Parse the `x-ratelimit-*` headers of a response into (resource, limit)
+    pub fn from_headers(headers: &HeaderMap) -> Option<(String, RateLimit)> {
+        let header = |name: &str| headers.get(name)?.to_str().ok();
+        let number = |name: &str| header(name)?.parse::<u64>().ok();
+
+        let limit = RateLimit {
+            limit: number("x-ratelimit-limit")?,
+            remaining: number("x-ratelimit-remaining")?,
+            reset: number("x-ratelimit-reset")?,
+        };
+        let resource = header("x-ratelimit-resource").unwrap_or("core");
+        Some((resource.to_string(), limit))
+    }
+
+    /// Whether less than `reserve_percent` of the quota is left and it hasn't reset yet
+    pub fn is_low(&self, reserve_percent: u8, now: u64) -> bool {
+        self.reset > now && self.remaining * 100 < self.limit * reserve_percent as u64
+    }
+}
+
+static BUDGET: Mutex<BTreeMap<String, RateLimit>> = Mutex::new(BTreeMap::new());
+
+/// Record the latest known quota for a resource
+pub fn record(resource: &str, limit: RateLimit) {
+    metrics::gauge!(telemetry::GITHUB_RATE_LIMIT_REMAINING, "resource" => resource.to_string())
+        .set(limit.remaining as f64);
+    BUDGET
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(resource.to_string(), limit);
+}
+
+/// Record the quota reported by a response's headers, if any
+pub fn record_headers(headers: &HeaderMap) {
+    if let Some((resource, limit)) = RateLimit::from_headers(headers) {
+        record(&resource, limit);
+    }
+}
+
+/// The latest known quota for every resource seen so far
+pub fn snapshot() -> BTreeMap<String, RateLimit> {
+    BUDGET.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// If core or search quota is running low, returns the resource and the
+/// timestamp at which polling may resume
+pub fn backoff_until(
+    budget: &BTreeMap<String, RateLimit>,
+    reserve_percent: u8,
+    now: u64,
+) -> Option<(String, u64)> {
+    POLLED_RESOURCES
+        .iter()
+        .filter_map(|resource| {
+            let limit = budget.get(*resource)?;
+            limit
+                .is_low(reserve_percent, now)
+                .then(|| (resource.to_string(), limit.reset))
+        })
+        .max_by_key(|(_, reset)| *reset)
+}
+
+/// Format the budget for display, e.g. "core 4890/5000 (resets in 42m), search 30/30"
+pub fn format_budget(budget: &BTreeMap<String, RateLimit>, now: u64) -> String {
+    if budget.is_empty() {
+        return "unknown".to_string();
+    }
+
+    budget
+        .iter()
+        .map(|(resource, limit)| {
+            let mut entry = format!("{} {}/{}", resource, limit.remaining, limit.limit);
+            if limit.remaining < limit.limit && limit.reset > now {
+                let minutes = (limit.reset - now).div_ceil(60);
+                entry.push_str(&format!(" (resets in {}m)", minutes));
+            }
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Current Unix time in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn limit(limit: u64, remaining: u64, reset: u64) -> RateLimit {
+        RateLimit {
+            limit,
+            remaining,
+            reset,
+        }
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("30"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("12"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1700000060"));
+        headers.insert("x-ratelimit-resource", HeaderValue::from_static("search"));
+
+        assert_eq!(
+            RateLimit::from_headers(&headers),
+            Some(("search".to_string(), limit(30, 12, 1700000060)))
+        );
+
+        // Resource defaults to core
+        headers.remove("x-ratelimit-resource");
+        assert_eq!(RateLimit::from_headers(&headers).unwrap().0, "core");
+
+        // Incomplete headers are ignored
+        headers.remove("x-ratelimit-remaining");
+        assert_eq!(RateLimit::from_headers(&headers), None);
+    }
+
+    #[test]
+    fn test_backoff_until() {
+        let now = 1_700_000_000;
+        let mut budget = BTreeMap::new();
+        budget.insert("core".to_string(), limit(5000, 4000, now + 600));
+        budget.insert("search".to_string(), limit(30, 29, now + 60));
+        assert_eq!(backoff_until(&budget, 10, now), None);
+
+        // Low core quota pauses polling until it resets
+        budget.insert("core".to_string(), limit(5000, 499, now + 600));
+        assert_eq!(backoff_until(&budget, 10, now), Some(("core".to_string(), now + 600)));
+
+        // The later reset wins when both are low
+        budget.insert("search".to_string(), limit(30, 2, now + 900));
+        assert_eq!(backoff_until(&budget, 10, now), Some(("search".to_string(), now + 900)));
+
+        // Quota that has already reset doesn't count
+        assert_eq!(backoff_until(&budget, 10, now + 1000), None);
+
+        // Other resources don't gate polling
+        let mut budget = BTreeMap::new();
+        budget.insert("graphql".to_string(), limit(5000, 0, now + 600));
+        assert_eq!(backoff_until(&budget, 10, now), None);
+    }
+
+    #[test]
+    fn test_format_budget() {
+        let now = 1_700_000_000;
+        let mut budget = BTreeMap::new();
+        assert_eq!(format_budget(&budget, now), "unknown");
+
+        budget.insert("core".to_string(), limit(5000, 4890, now + 2500));
+        budget.insert("search".to_string(), limit(30, 30, now + 60));
+        assert_eq!(
+            format_budget(&budget, now),
+            "core 4890/5000 (resets in 42m), search 30/30"
+        );
+    }
+}
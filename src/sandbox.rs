@@ -0,0 +1,608 @@
+//! Running Claude Code sessions in a sandbox (`[sandbox]`).
+//!
+//! A sandbox only sees what a session needs, mounted at the same paths as on
+//! the host: the worktree and the repository's git directory (read-write), the
+//! state directory with the prompts and media (read-only), and the pleb
+//! executable so the Claude Code hooks can run. The hooks reach the daemon
+//! through its socket, made available in the sandbox and named by
+//! `PLEB_SOCKET`.
+//!
+//! - `lima`: each issue gets its own VM, or one from a small pool per
+//!   repository. The socket is forwarded into the VM and the tmux window enters
+//!   it with `limactl shell` to start Claude.
+//! - `podman` / `docker`: the tmux window runs Claude in a container that lives
+//!   as long as the session, with the daemon directory (holding the socket)
+//!   bind-mounted read-only.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use tokio::process::Command;
+
+use crate::config::{Config, ContainerConfig, RepoConfig, SandboxBackend, SandboxConfig};
+use crate::github::IssueRef;
+use crate::ipc;
+
+/// Where the daemon socket appears inside a Lima VM
+const LIMA_GUEST_SOCKET: &str = "/tmp/pleb/pleb.sock";
+
+/// Home directory inside containers, which have no home for the host user
+const CONTAINER_HOME: &str = "/tmp";
+
+/// A host path made available in the sandbox at the same path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub path: PathBuf,
+    pub writable: bool,
+}
+
+/// A sandbox prepared for one session
+#[derive(Debug, Clone)]
+pub struct SandboxSession {
+    /// Instance (VM or container) name, recorded so cleanup can tear it down
+    pub instance: String,
+    /// Command line that runs what follows it in the sandbox
+    command_prefix: String,
+}
+
+impl SandboxSession {
+    /// Wrap a command typed into the tmux window so it runs in the sandbox
+    pub fn wrap(&self, command: &str) -> String {
+        format!("{} {}", self.command_prefix, command)
+    }
+}
+
+/// Creates, starts and tears down sandbox instances
+pub struct Sandbox {
+    config: SandboxConfig,
+    /// Host path of the daemon socket
+    daemon_socket: PathBuf,
+    /// Environment variables passed into the sandbox
+    env: Vec<String>,
+}
+
+impl Sandbox {
+    pub fn new(config: &Config) -> Result<Self> {
+        let mut env = vec![config.github.token_env.clone()];
+        for name in &config.sandbox.env {
+            if !env.contains(name) {
+                env.push(name.clone());
+            }
+        }
+
+        Ok(Self {
+            config: config.sandbox.clone(),
+            daemon_socket: config.daemon_dir()?.join("pleb.sock"),
+            env,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.backend != SandboxBackend::None
+    }
+
+    /// Name of the instance a session for `issue` runs in. From a Lima pool,
+    /// that is the instance used by the fewest of the sessions in `in_use`.
+    pub fn instance_for(&self, issue: &IssueRef, in_use: &[String]) -> String {
+        let base = format!(
+            "{}-{}-{}",
+            self.config.instance_prefix,
+            instance_component(&issue.owner),
+            instance_component(&issue.repo)
+        );
+        match self.pool_size() {
+            None => format!("{}-{}", base, issue.number),
+            Some(size) => (0..size.max(1))
+                .map(|i| format!("{}-pool-{}", base, i))
+                .min_by_key(|name| in_use.iter().filter(|used| *used == name).count())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Size of the Lima pool, if instances are pooled
+    fn pool_size(&self) -> Option<usize> {
+        match self.config.backend {
+            SandboxBackend::Lima => self.config.lima.pool_size,
+            _ => None,
+        }
+    }
+
+    /// Host paths the sandbox needs to run a session in `worktree_path`.
+    /// Pooled instances mount the whole worktree base, since they outlive any one worktree.
+    pub fn mounts(&self, repo: &RepoConfig, worktree_path: &Path) -> Result<Vec<Mount>> {
+        let workspace = match self.pool_size() {
+            Some(_) => repo.paths.worktree_base.clone(),
+            None => worktree_path.to_path_buf(),
+        };
+        // Worktrees keep their index and refs in the main repository's git directory
+        let git_dir = repo.paths.repo_dir.join(".git");
+        let git_dir = if git_dir.is_dir() { git_dir } else { repo.paths.repo_dir.clone() };
+
+        let state_dir = repo.state_dir()?;
+        std::fs::create_dir_all(&state_dir)
+            .with_context(|| format!("Failed to create state directory: {}", state_dir.display()))?;
+
+        let exe = std::env::current_exe().context("Failed to get current executable path")?;
+        let mut paths = vec![(workspace, true), (git_dir, true), (state_dir, false)];
+        if self.config.backend.is_container() {
+            // Containers can mount single files, and the socket's directory
+            // survives the daemon recreating the socket
+            paths.push((exe, false));
+            if let Some(daemon_dir) = self.daemon_socket.parent() {
+                paths.push((daemon_dir.to_path_buf(), false));
+            }
+        } else {
+            let exe_dir = exe.parent().context("Current executable has no parent directory")?;
+            paths.push((exe_dir.to_path_buf(), false));
+        }
+
+        let mut mounts: Vec<Mount> = Vec::new();
+        for (path, writable) in paths {
+            // Git records canonical paths, so mount them where it will look
+            let path = path.canonicalize().unwrap_or(path);
+            if !mounts.iter().any(|mount| mount.path == path) {
+                mounts.push(Mount { path, writable });
+            }
+        }
+        Ok(mounts)
+    }
+
+    /// Make sure the sandbox for a session is ready, creating it if needed.
+    /// Returns None when sandboxing is off.
+    pub async fn prepare(
+        &self,
+        repo: &RepoConfig,
+        issue: &IssueRef,
+        worktree_path: &Path,
+        in_use: &[String],
+    ) -> Result<Option<SandboxSession>> {
+        let instance = self.instance_for(issue, in_use);
+        let command_prefix = match self.config.backend {
+            SandboxBackend::None => return Ok(None),
+            SandboxBackend::Lima => {
+                self.start_lima(&instance, issue, repo, worktree_path).await?;
+                lima_command(&instance, worktree_path, &self.env)
+            }
+            SandboxBackend::Podman | SandboxBackend::Docker => {
+                let engine = self.container_engine();
+                // A container left behind by an interrupted session would block the name
+                if container_exists(engine, &instance).await? {
+                    tracing::info!("Removing leftover container {} of {}", instance, issue);
+                    run(engine, &["rm", "--force", &instance]).await?;
+                }
+                container_command(
+                    self.config.backend,
+                    &self.config.container,
+                    &instance,
+                    &self.mounts(repo, worktree_path)?,
+                    worktree_path,
+                    &self.daemon_socket,
+                    &self.env,
+                )?
+            }
+        };
+
+        Ok(Some(SandboxSession {
+            instance,
+            command_prefix,
+        }))
+    }
+
+    /// Tear down the instance an issue's session ran in, given the recorded
+    /// instance name if there is one. Pooled VMs are shared and kept.
+    /// Returns the name of the removed instance.
+    pub async fn release(&self, issue: &IssueRef, recorded: Option<&str>) -> Result<Option<String>> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let instance = match recorded {
+            Some(instance) => instance.to_string(),
+            None if self.pool_size().is_none() => self.instance_for(issue, &[]),
+            None => return Ok(None),
+        };
+
+        match self.config.backend {
+            SandboxBackend::None => return Ok(None),
+            SandboxBackend::Lima => {
+                if is_pooled(&instance) || lima_status(&instance).await?.is_none() {
+                    return Ok(None);
+                }
+                tracing::info!("Deleting Lima instance {} of {}", instance, issue);
+                limactl(&["delete", "--force", &instance]).await?;
+            }
+            SandboxBackend::Podman | SandboxBackend::Docker => {
+                let engine = self.container_engine();
+                if !container_exists(engine, &instance).await? {
+                    return Ok(None);
+                }
+                tracing::info!("Removing container {} of {}", instance, issue);
+                run(engine, &["rm", "--force", &instance]).await?;
+            }
+        }
+        Ok(Some(instance))
+    }
+
+    fn container_engine(&self) -> &'static str {
+        match self.config.backend {
+            SandboxBackend::Docker => "docker",
+            _ => "podman",
+        }
+    }
+
+    /// Create or start a Lima instance
+    async fn start_lima(
+        &self,
+        instance: &str,
+        issue: &IssueRef,
+        repo: &RepoConfig,
+        worktree_path: &Path,
+    ) -> Result<()> {
+        match lima_status(instance).await? {
+            Some(status) if status == "Running" => {
+                tracing::debug!("Lima instance {} is already running", instance);
+            }
+            Some(_) => {
+                tracing::info!("Starting Lima instance {} for {}", instance, issue);
+                limactl(&["start", "--tty=false", instance]).await?;
+            }
+            None => {
+                tracing::info!("Creating Lima instance {} for {}", instance, issue);
+                let settings = lima_settings(&self.mounts(repo, worktree_path)?, &self.daemon_socket);
+                limactl(&[
+                    "start",
+                    &format!("--name={}", instance),
+                    "--tty=false",
+                    "--set",
+                    &settings,
+                    &self.config.lima.template,
+                ])
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowercase letters, digits and '-' only, as Lima instance names require
+fn instance_component(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Whether an instance name belongs to a pool ("...-pool-{i}")
+fn is_pooled(instance: &str) -> bool {
+    instance
+        .rsplit_once("-pool-")
+        .is_some_and(|(_, index)| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// `limactl shell` prefix running a command in the VM
+fn lima_command(instance: &str, workdir: &Path, env: &[String]) -> String {
+    let mut parts = vec![
+        "limactl shell --workdir".to_string(),
+        shell_quote(&workdir.to_string_lossy()),
+        shell_quote(instance),
+        "env".to_string(),
+        format!("{}={}", ipc::SOCKET_ENV, LIMA_GUEST_SOCKET),
+    ];
+    // Expanded by the window's shell, so the values never end up in the logs
+    for name in env {
+        parts.push(format!("{}=\"${}\"", name, name));
+    }
+    parts.join(" ")
+}
+
+/// `limactl --set` expression replacing the template's mounts with ours and
+/// forwarding the daemon socket into the VM
+fn lima_settings(mounts: &[Mount], daemon_socket: &Path) -> String {
+    let mounts: Vec<serde_json::Value> = mounts
+        .iter()
+        .map(|mount| serde_json::json!({"location": mount.path, "writable": mount.writable}))
+        .collect();
+    let port_forwards = serde_json::json!([{
+        "guestSocket": LIMA_GUEST_SOCKET,
+        "hostSocket": daemon_socket,
+        "reverse": true,
+    }]);
+    format!(
+        ".mounts = {} | .portForwards = {}",
+        serde_json::Value::Array(mounts),
+        port_forwards
+    )
+}
+
+/// `podman run` / `docker run` prefix running a command in a new container
+fn container_command(
+    backend: SandboxBackend,
+    config: &ContainerConfig,
+    instance: &str,
+    mounts: &[Mount],
+    workdir: &Path,
+    daemon_socket: &Path,
+    env: &[String],
+) -> Result<String> {
+    let image = config
+        .image
+        .as_deref()
+        .context("sandbox.container.image must be set")?;
+
+    let mut parts = vec![match backend {
+        SandboxBackend::Docker => "docker run".to_string(),
+        _ => "podman run".to_string(),
+    }];
+    parts.push(format!("--rm -it --name {}", shell_quote(instance)));
+    // Files written to the worktree belong to the host user
+    match backend {
+        SandboxBackend::Docker => parts.push(format!(
+            "--user {}:{}",
+            nix::unistd::getuid(),
+            nix::unistd::getgid()
+        )),
+        _ => parts.push("--userns=keep-id".to_string()),
+    }
+    for mount in mounts {
+        let path = mount.path.to_string_lossy();
+        let volume = if mount.writable {
+            format!("{}:{}", path, path)
+        } else {
+            format!("{}:{}:ro", path, path)
+        };
+        parts.push(format!("-v {}", shell_quote(&volume)));
+    }
+    parts.push(format!("-w {}", shell_quote(&workdir.to_string_lossy())));
+    parts.push(format!("-e HOME={}", CONTAINER_HOME));
+    parts.push(format!(
+        "-e {}",
+        shell_quote(&format!("{}={}", ipc::SOCKET_ENV, daemon_socket.to_string_lossy()))
+    ));
+    // Without a value, the variable is taken from the window's environment
+    for name in env {
+        parts.push(format!("-e {}", name));
+    }
+    if !config.network {
+        parts.push("--network=none".to_string());
+    }
+    if let Some(cpus) = config.cpus {
+        parts.push(format!("--cpus={}", cpus));
+    }
+    if let Some(memory) = &config.memory {
+        parts.push(format!("--memory={}", shell_quote(memory)));
+    }
+    parts.push(shell_quote(image));
+    Ok(parts.join(" "))
+}
+
+#[derive(Debug, Deserialize)]
+struct LimaInstance {
+    name: String,
+    status: String,
+}
+
+/// Parse `limactl list --json`, which prints one instance object per line
+fn parse_instances(output: &str) -> Vec<LimaInstance> {
+    let output = output.trim();
+    if output.starts_with('[') {
+        return serde_json::from_str(output).unwrap_or_default();
+    }
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Status of a Lima instance ("Running", "Stopped", ...), or None if it doesn't exist
+async fn lima_status(instance: &str) -> Result<Option<String>> {
+    let output = limactl(&["list", "--json"]).await?;
+    Ok(parse_instances(&output)
+        .into_iter()
+        .find(|i| i.name == instance)
+        .map(|i| i.status))
+}
+
+/// Run limactl and return its stdout
+async fn limactl(args: &[&str]) -> Result<String> {
+    let output = run("limactl", args).await?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Whether a container with this name exists, running or not
+async fn container_exists(engine: &str, name: &str) -> Result<bool> {
+    let output = Command::new(engine)
+        .args(["container", "inspect", name])
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {} (is it installed?)", engine))?;
+    Ok(output.status.success())
+}
+
+/// Run a sandbox tool, failing with its stderr if it exits with an error
+async fn run(program: &str, args: &[&str]) -> Result<Output> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {} (is it installed?)", program))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output)
+}
+
+/// Quote a word for the shell in the tmux window, if it needs it
+pub fn shell_quote(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | ':' | '=' | '@');
+    if !word.is_empty() && word.chars().all(safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LimaConfig;
+
+    fn sandbox(backend: SandboxBackend, pool_size: Option<usize>) -> Sandbox {
+        Sandbox {
+            config: SandboxConfig {
+                backend,
+                lima: LimaConfig {
+                    pool_size,
+                    ..LimaConfig::default()
+                },
+                ..SandboxConfig::default()
+            },
+            daemon_socket: PathBuf::from("/home/alice/.pleb/acme-api/pleb.sock"),
+            env: vec!["GITHUB_TOKEN".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_instance_for() {
+        let issue = IssueRef::new("Acme", "web_api", 42);
+        let lima = sandbox(SandboxBackend::Lima, None);
+        assert_eq!(lima.instance_for(&issue, &[]), "pleb-acme-web-api-42");
+
+        let pool = sandbox(SandboxBackend::Lima, Some(3));
+        assert_eq!(pool.instance_for(&issue, &[]), "pleb-acme-web-api-pool-0");
+        let in_use = vec![
+            "pleb-acme-web-api-pool-0".to_string(),
+            "pleb-acme-web-api-pool-2".to_string(),
+        ];
+        assert_eq!(pool.instance_for(&issue, &in_use), "pleb-acme-web-api-pool-1");
+
+        // Containers are never pooled
+        let podman = sandbox(SandboxBackend::Podman, Some(3));
+        assert_eq!(podman.instance_for(&issue, &in_use), "pleb-acme-web-api-42");
+
+        assert!(is_pooled("pleb-acme-web-api-pool-1"));
+        assert!(!is_pooled("pleb-acme-web-api-42"));
+        assert!(!is_pooled("pleb-acme-pool-api-42"));
+    }
+
+    #[test]
+    fn test_lima_command() {
+        let session = SandboxSession {
+            instance: "pleb-acme-api-42".to_string(),
+            command_prefix: lima_command(
+                "pleb-acme-api-42",
+                Path::new("/work/42-add invoices"),
+                &["GITHUB_TOKEN".to_string()],
+            ),
+        };
+        assert_eq!(
+            session.wrap("claude --permission-mode plan @/home/alice/.pleb/acme-api/42/prompt.md"),
+            "limactl shell --workdir '/work/42-add invoices' pleb-acme-api-42 env \
+             PLEB_SOCKET=/tmp/pleb/pleb.sock GITHUB_TOKEN=\"$GITHUB_TOKEN\" \
+             claude --permission-mode plan @/home/alice/.pleb/acme-api/42/prompt.md"
+        );
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_lima_settings() {
+        let mounts = vec![
+            Mount {
+                path: PathBuf::from("/work/42-add-invoices"),
+                writable: true,
+            },
+            Mount {
+                path: PathBuf::from("/home/alice/.pleb/acme-api"),
+                writable: false,
+            },
+        ];
+        let settings = lima_settings(&mounts, Path::new("/home/alice/.pleb/acme-api/pleb.sock"));
+        assert_eq!(
+            settings,
+            ".mounts = [{\"location\":\"/work/42-add-invoices\",\"writable\":true},\
+             {\"location\":\"/home/alice/.pleb/acme-api\",\"writable\":false}] | \
+             .portForwards = [{\"guestSocket\":\"/tmp/pleb/pleb.sock\",\
+             \"hostSocket\":\"/home/alice/.pleb/acme-api/pleb.sock\",\"reverse\":true}]"
+        );
+    }
+
+    #[test]
+    fn test_container_command() {
+        let mounts = vec![
+            Mount {
+                path: PathBuf::from("/work/42-add-invoices"),
+                writable: true,
+            },
+            Mount {
+                path: PathBuf::from("/home/alice/.pleb/acme-api"),
+                writable: false,
+            },
+        ];
+        let config = ContainerConfig {
+            image: Some("localhost/claude:latest".to_string()),
+            network: false,
+            cpus: Some(1.5),
+            memory: Some("4g".to_string()),
+        };
+        let command = container_command(
+            SandboxBackend::Podman,
+            &config,
+            "pleb-acme-api-42",
+            &mounts,
+            Path::new("/work/42-add-invoices"),
+            Path::new("/home/alice/.pleb/acme-api/pleb.sock"),
+            &["GITHUB_TOKEN".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            command,
+            "podman run --rm -it --name pleb-acme-api-42 --userns=keep-id \
+             -v /work/42-add-invoices:/work/42-add-invoices \
+             -v /home/alice/.pleb/acme-api:/home/alice/.pleb/acme-api:ro \
+             -w /work/42-add-invoices -e HOME=/tmp \
+             -e PLEB_SOCKET=/home/alice/.pleb/acme-api/pleb.sock -e GITHUB_TOKEN \
+             --network=none --cpus=1.5 --memory=4g localhost/claude:latest"
+        );
+
+        // Docker runs as the host user instead, and the defaults add no limits
+        let config = ContainerConfig {
+            image: Some("claude".to_string()),
+            ..ContainerConfig::default()
+        };
+        let command = container_command(
+            SandboxBackend::Docker,
+            &config,
+            "pleb-acme-api-42",
+            &[],
+            Path::new("/work/42-add-invoices"),
+            Path::new("/home/alice/.pleb/acme-api/pleb.sock"),
+            &[],
+        )
+        .unwrap();
+        assert!(command.starts_with("docker run --rm -it --name pleb-acme-api-42 --user "));
+        assert!(command.ends_with("/home/alice/.pleb/acme-api/pleb.sock claude"));
+        assert!(!command.contains("--network"));
+    }
+
+    #[test]
+    fn test_parse_instances() {
+        let output = "{\"name\":\"default\",\"status\":\"Stopped\",\"dir\":\"/x\"}\n\
+                      {\"name\":\"pleb-acme-api-42\",\"status\":\"Running\"}\n";
+        let instances = parse_instances(output);
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[1].name, "pleb-acme-api-42");
+        assert_eq!(instances[1].status, "Running");
+        assert_eq!(parse_instances("[{\"name\":\"a\",\"status\":\"Broken\"}]").len(), 1);
+        assert!(parse_instances("").is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! Ranking of ready issues before they are provisioned.
+//!
+//! Issues are compared rule by rule in the order given by `[scheduling] order`;
+//! later rules only break ties. The sort is stable, so issues that tie on every
+//! rule keep the order they were passed in (the FIFO queue order).
+
+use std::cmp::Ordering;
+
+use crate::config::{RankRule, SchedulingConfig};
+use crate::github::Issue;
+
+/// Compare two issues using the configured rules; the issue that should be
+/// provisioned first sorts first
+pub fn compare(a: &Issue, b: &Issue, config: &SchedulingConfig) -> Ordering {
+    for rule in &config.order {
+        let ordering = match rule {
+            RankRule::Priority => priority_rank(a, config).cmp(&priority_rank(b, config)),
+            RankRule::Milestone => a
+                .milestone_due_on
+                .unwrap_or(u64::MAX)
+                .cmp(&b.milestone_due_on.unwrap_or(u64::MAX)),
+            RankRule::Author => author_rank(a, config).cmp(&author_rank(b, config)),
+            RankRule::Age => a.created_at.cmp(&b.created_at),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Index of the issue's highest priority label (lower is more urgent)
+fn priority_rank(issue: &Issue, config: &SchedulingConfig) -> usize {
+    config
+        .priority_labels
+        .iter()
+        .position(|label| issue.labels.contains(label))
+        .unwrap_or(config.priority_labels.len())
+}
+
+/// Index of the issue's author in the preferred authors list
+fn author_rank(issue: &Issue, config: &SchedulingConfig) -> usize {
+    config
+        .preferred_authors
+        .iter()
+        .position(|author| author == &issue.author)
+        .unwrap_or(config.preferred_authors.len())
+}
+
+/// Human-readable name of the rule order, e.g. "priority > milestone > age"
+pub fn describe_order(config: &SchedulingConfig) -> String {
+    if config.order.is_empty() {
+        return "fifo".to_string();
+    }
+
+    config
+        .order
+        .iter()
+        .map(|rule| match rule {
+            RankRule::Priority => "priority",
+            RankRule::Milestone => "milestone",
+            RankRule::Author => "author",
+            RankRule::Age => "age",
+        })
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+/// Short summary of the values an issue was ranked on, e.g.
+/// "priority:high, milestone due in 3d, opened 12d ago"
+pub fn describe_issue(issue: &Issue, config: &SchedulingConfig, now: u64) -> String {
+    let mut parts = Vec::new();
+
+    for rule in &config.order {
+        match rule {
+            RankRule::Priority => {
+                let rank = priority_rank(issue, config);
+                match config.priority_labels.get(rank) {
+                    Some(label) => parts.push(label.clone()),
+                    None => parts.push("no priority".to_string()),
+                }
+            }
+            RankRule::Milestone => match issue.milestone_due_on {
+                Some(due) if due >= now => {
+                    parts.push(format!("milestone due in {}d", (due - now) / 86400))
+                }
+                Some(due) => parts.push(format!("milestone overdue {}d", (now - due) / 86400)),
+                None => parts.push("no milestone".to_string()),
+            },
+            RankRule::Author => parts.push(format!("by {}", issue.author)),
+            RankRule::Age => parts.push(format!(
+                "opened {}d ago",
+                now.saturating_sub(issue.created_at) / 86400
+            )),
+        }
+    }
+
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::IssueState;
+
+    fn make_issue(number: u64, labels: &[&str], author: &str, created_at: u64, due: Option<u64>) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {}", number),
+            body: String::new(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            state: IssueState::Open,
+            html_url: format!("https://github.com/owner/repo/issues/{}", number),
+            author: author.to_string(),
+            created_at,
+            milestone_due_on: due,
+        }
+    }
+
+    fn numbers(issues: &[Issue]) -> Vec<u64> {
+        issues.iter().map(|i| i.number).collect()
+    }
+
+    #[test]
+    fn test_priority_labels_rank_first() {
+        let config = SchedulingConfig::default();
+        let mut issues = vec![
+            make_issue(1, &["pleb:ready"], "a", 100, None),
+            make_issue(2, &["pleb:ready", "priority:low"], "a", 100, None),
+            make_issue(3, &["pleb:ready", "priority:high"], "a", 100, None),
+        ];
+
+        issues.sort_by(|a, b| compare(a, b, &config));
+        assert_eq!(numbers(&issues), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_milestone_then_age_break_ties() {
+        let config = SchedulingConfig::default();
+        let mut issues = vec![
+            make_issue(1, &[], "a", 300, None),
+            make_issue(2, &[], "a", 200, Some(5000)),
+            make_issue(3, &[], "a", 100, None),
+            make_issue(4, &[], "a", 400, Some(1000)),
+        ];
+
+        issues.sort_by(|a, b| compare(a, b, &config));
+        // Earliest milestone first, then no milestone ordered by age
+        assert_eq!(numbers(&issues), vec![4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_preferred_authors() {
+        let config = SchedulingConfig {
+            order: vec![RankRule::Author],
+            priority_labels: Vec::new(),
+            preferred_authors: vec!["alice".to_string(), "bob".to_string()],
+        };
+        let mut issues = vec![
+            make_issue(1, &[], "carol", 100, None),
+            make_issue(2, &[], "bob", 100, None),
+            make_issue(3, &[], "alice", 100, None),
+        ];
+
+        issues.sort_by(|a, b| compare(a, b, &config));
+        assert_eq!(numbers(&issues), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_ties_keep_input_order() {
+        let config = SchedulingConfig {
+            order: Vec::new(),
+            priority_labels: Vec::new(),
+            preferred_authors: Vec::new(),
+        };
+        let mut issues = vec![
+            make_issue(5, &[], "a", 500, None),
+            make_issue(1, &[], "a", 100, None),
+            make_issue(3, &[], "a", 300, None),
+        ];
+
+        issues.sort_by(|a, b| compare(a, b, &config));
+        assert_eq!(numbers(&issues), vec![5, 1, 3]);
+    }
+
+    #[test]
+    fn test_describe() {
+        let config = SchedulingConfig::default();
+        assert_eq!(describe_order(&config), "priority > milestone > age");
+
+        let issue = make_issue(1, &["priority:high"], "a", 0, Some(3 * 86400));
+        assert_eq!(
+            describe_issue(&issue, &config, 86400),
+            "priority:high, milestone due in 2d, opened 1d ago"
+        );
+    }
+}
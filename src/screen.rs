@@ -0,0 +1,303 @@
+//! GNU screen: each session window is a screen window in the configured
+//! session, addressed by its number and found by its title.
+//!
+//! screen windows have no panes, so pane selection is a no-op.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::Path;
+use std::process::Output;
+use tokio::process::Command;
+
+use crate::config::TmuxConfig;
+use crate::github::IssueRef;
+use crate::session::{self, SessionBackend, WindowTarget};
+
+pub struct ScreenManager {
+    session_name: String,
+    /// Environment variables to pass to screen sessions (name -> value)
+    env_vars: Vec<(String, String)>,
+}
+
+impl ScreenManager {
+    pub fn new(config: &TmuxConfig) -> Self {
+        Self {
+            session_name: config.session_name.clone(),
+            env_vars: Vec::new(),
+        }
+    }
+
+    /// Add environment variables to be passed to screen sessions
+    pub fn with_envs(mut self, env: Vec<(String, String)>) -> Self {
+        self.env_vars.extend(env);
+        self
+    }
+
+    /// Run a screen command in the session, in window `window` if given
+    async fn command(&self, window: Option<u32>, args: &[&str]) -> Result<Output> {
+        let mut cmd = Command::new("screen");
+        cmd.args(["-S", &self.session_name]);
+        if let Some(window) = window {
+            cmd.args(["-p", &window.to_string()]);
+        }
+        let output = cmd
+            .arg("-X")
+            .args(args)
+            .output()
+            .await
+            .context("Failed to execute screen command")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "screen {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stdout).trim()
+            );
+        }
+        Ok(output)
+    }
+
+    async fn session_exists(&self) -> Result<bool> {
+        // `screen -ls` exits with an error even when it lists sessions
+        let output = Command::new("screen")
+            .arg("-ls")
+            .output()
+            .await
+            .context("Failed to list screen sessions")?;
+        let suffix = format!(".{}", self.session_name);
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .any(|session| session.ends_with(&suffix)))
+    }
+
+    /// Numbers and titles of the session's windows (empty if it doesn't exist)
+    async fn windows(&self) -> Result<Vec<(u32, String)>> {
+        let output = Command::new("screen")
+            .args(["-S", &self.session_name, "-Q", "windows"])
+            .output()
+            .await
+            .context("Failed to list screen windows")?;
+
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        Ok(parse_windows(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Find the window for an issue, returning its number and title
+    async fn find_window(&self, issue: &dyn WindowTarget) -> Result<Option<(u32, String)>> {
+        Ok(self
+            .windows()
+            .await?
+            .into_iter()
+            .find(|(_, title)| issue.matches_window(title)))
+    }
+}
+
+#[async_trait]
+impl SessionBackend for ScreenManager {
+    async fn ensure_session(&self) -> Result<()> {
+        if !self.session_exists().await? {
+            tracing::info!("Creating screen session: {}", self.session_name);
+            let status = Command::new("screen")
+                .args(["-dmS", &self.session_name])
+                .status()
+                .await
+                .context("Failed to execute screen -dmS")?;
+
+            if !status.success() {
+                anyhow::bail!("screen -dmS failed for session '{}': {}", self.session_name, status);
+            }
+        }
+
+        // Set environment variables for new windows (always, to update existing sessions too)
+        for (name, value) in &self.env_vars {
+            tracing::debug!("Setting screen environment variable: {}", name);
+            self.command(None, &["setenv", name, value]).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn create_window(
+        &self,
+        issue: &dyn WindowTarget,
+        branch_name: &str,
+        working_dir: &Path,
+    ) -> Result<()> {
+        self.ensure_session().await?;
+
+        if self.window_exists(issue).await? {
+            tracing::info!("Window for issue {} already exists", issue);
+            return Ok(());
+        }
+
+        let title = issue.window_name(branch_name);
+        tracing::info!("Creating screen window {} in session {}", title, self.session_name);
+        // New windows start in the session's current directory
+        self.command(None, &["chdir", &working_dir.to_string_lossy()])
+            .await?;
+        self.command(None, &["screen", "-t", &title]).await?;
+        Ok(())
+    }
+
+    async fn window_exists(&self, issue: &dyn WindowTarget) -> Result<bool> {
+        Ok(self.find_window(issue).await?.is_some())
+    }
+
+    async fn list_windows(&self) -> Result<Vec<IssueRef>> {
+        Ok(self
+            .windows()
+            .await?
+            .iter()
+            .filter_map(|(_, title)| session::parse_window_name(title))
+            .collect())
+    }
+
+    async fn kill_window(&self, issue: &dyn WindowTarget) -> Result<()> {
+        let Some((number, title)) = self.find_window(issue).await? else {
+            tracing::warn!("No window found for issue {}", issue);
+            return Ok(());
+        };
+
+        tracing::info!("Killing screen window: {}", title);
+        self.command(Some(number), &["kill"]).await?;
+        Ok(())
+    }
+
+    async fn send_keys(&self, issue: &dyn WindowTarget, keys: &str) -> Result<()> {
+        let (number, title) = self
+            .find_window(issue)
+            .await?
+            .with_context(|| format!("No window found for issue {}", issue))?;
+
+        tracing::debug!("Sending keys to {}: {}", title, keys);
+        self.command(Some(number), &["stuff", &stuff_text(keys)])
+            .await?;
+        Ok(())
+    }
+
+    async fn rename_window(&self, issue: &dyn WindowTarget, state: &str) -> Result<()> {
+        let Some((number, title)) = self.find_window(issue).await? else {
+            tracing::warn!("No window found for issue {} to rename", issue);
+            return Ok(());
+        };
+
+        let new_title = session::state_window_name(&title, state);
+        tracing::debug!("Renaming window {} to {}", title, new_title);
+        self.command(Some(number), &["title", &new_title]).await?;
+        Ok(())
+    }
+
+    async fn select_pane(&self, issue: &dyn WindowTarget, pane_index: u32) -> Result<()> {
+        tracing::debug!("screen windows have no panes, not selecting pane {} for {}", pane_index, issue);
+        Ok(())
+    }
+
+    async fn capture_pane(&self, issue: &dyn WindowTarget, lines: u32) -> Result<Option<String>> {
+        let Some((number, _)) = self.find_window(issue).await? else {
+            return Ok(None);
+        };
+
+        let path = std::env::temp_dir().join(format!("pleb-screen-{}.txt", std::process::id()));
+        self.command(Some(number), &["hardcopy", "-h", &path.to_string_lossy()])
+            .await?;
+        let text = std::fs::read(&path);
+        std::fs::remove_file(&path).ok();
+        let text = text.with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Ok(Some(session::last_lines(&String::from_utf8_lossy(&text), lines)))
+    }
+
+    async fn log_output(&self, issue: &dyn WindowTarget, log_file: &Path) -> Result<()> {
+        let (number, _) = self
+            .find_window(issue)
+            .await?
+            .with_context(|| format!("No window found for issue {}", issue))?;
+
+        // screen appends to the log file; turning logging on again is harmless
+        self.command(Some(number), &["logfile", &log_file.to_string_lossy()])
+            .await?;
+        self.command(Some(number), &["log", "on"]).await?;
+        tracing::debug!("Logging window for issue {} to {}", issue, log_file.display());
+        Ok(())
+    }
+
+    fn attach_command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new("screen");
+        cmd.args(["-x", &self.session_name]);
+        cmd
+    }
+
+    async fn attach_window_command(&self, issue: &dyn WindowTarget) -> Result<Option<std::process::Command>> {
+        let Some((number, _)) = self.find_window(issue).await? else {
+            return Ok(None);
+        };
+
+        let mut cmd = std::process::Command::new("screen");
+        let inside = std::env::var("STY")
+            .is_ok_and(|sty| sty.ends_with(&format!(".{}", self.session_name)));
+        if inside {
+            cmd.args(["-S", &self.session_name, "-X", "select", &number.to_string()]);
+        } else {
+            cmd.args(["-x", &self.session_name, "-p", &number.to_string()]);
+        }
+        Ok(Some(cmd))
+    }
+}
+
+/// Parse `screen -Q windows`: "0$ bash  1-$ acme/api/12-fix  2*$ ..."
+/// (number, flags, title; entries separated by two spaces)
+fn parse_windows(output: &str) -> Vec<(u32, String)> {
+    output
+        .trim()
+        .split("  ")
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let digits = entry.chars().take_while(|c| c.is_ascii_digit()).count();
+            let number = entry[..digits].parse().ok()?;
+            let (_, title) = entry[digits..].split_once(' ')?;
+            Some((number, title.to_string()))
+        })
+        .collect()
+}
+
+/// Text for `stuff`, which interprets '\', '^' and '$', followed by Enter
+fn stuff_text(keys: &str) -> String {
+    let mut text = String::with_capacity(keys.len() + 1);
+    for c in keys.chars() {
+        if matches!(c, '\\' | '^' | '$') {
+            text.push('\\');
+        }
+        text.push(c);
+    }
+    text.push('\r');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_windows() {
+        let output = "0$ bash  1-$ acme/api/12-fix_user_pleb:working  2*$ acme/api/quick-typo\n";
+        assert_eq!(
+            parse_windows(output),
+            vec![
+                (0, "bash".to_string()),
+                (1, "acme/api/12-fix_user_pleb:working".to_string()),
+                (2, "acme/api/quick-typo".to_string()),
+            ]
+        );
+        assert!(parse_windows("").is_empty());
+    }
+
+    #[test]
+    fn test_stuff_text() {
+        assert_eq!(stuff_text("claude @/tmp/p.md"), "claude @/tmp/p.md\r");
+        assert_eq!(stuff_text("echo \"$HOME\" ^C \\"), "echo \"\\$HOME\" \\^C \\\\\r");
+        assert_eq!(stuff_text(""), "\r");
+    }
+}
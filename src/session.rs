@@ -0,0 +1,339 @@
+//! The terminal multiplexer sessions run in.
+//!
+//! Every issue (and quick session) gets its own window in one multiplexer
+//! session, named after the issue so it can be found again. `SessionBackend`
+//! is everything the daemon and the commands need from the multiplexer; tmux,
+//! zellij and GNU screen implement it, selected by `[tmux] multiplexer`.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::{Config, Multiplexer, TmuxConfig};
+use crate::github::IssueRef;
+use crate::screen::ScreenManager;
+use crate::tmux::TmuxManager;
+use crate::zellij::ZellijManager;
+
+/// What a window belongs to: an issue, or a `pleb quick` session
+pub trait WindowTarget: std::fmt::Display + Send + Sync {
+    /// Name of the window for a session on `branch_name`
+    fn window_name(&self, branch_name: &str) -> String;
+
+    /// Whether a window name (possibly with a ":state" suffix) belongs to this target
+    fn matches_window(&self, name: &str) -> bool;
+
+    /// Directory for the session's files (prompts, messages) under the repo state directory
+    fn state_dir_name(&self) -> String;
+}
+
+impl WindowTarget for IssueRef {
+    fn window_name(&self, branch_name: &str) -> String {
+        window_name(self, branch_name)
+    }
+
+    fn matches_window(&self, name: &str) -> bool {
+        parse_window_name(name).is_some_and(|window| window_matches(&window, self))
+    }
+
+    fn state_dir_name(&self) -> String {
+        self.number.to_string()
+    }
+}
+
+/// A terminal multiplexer holding one window per session
+#[async_trait]
+pub trait SessionBackend: Send + Sync {
+    /// Ensure the session exists, creating it if not
+    async fn ensure_session(&self) -> Result<()>;
+
+    /// Create the window for a target, named by `WindowTarget::window_name`
+    /// and starting in `working_dir`. Does nothing if it already exists.
+    async fn create_window(
+        &self,
+        target: &dyn WindowTarget,
+        branch_name: &str,
+        working_dir: &Path,
+    ) -> Result<()>;
+
+    /// Check if a window exists for a target
+    async fn window_exists(&self, target: &dyn WindowTarget) -> Result<bool>;
+
+    /// List all issue windows in the session
+    /// Returns the issues parsed from the window names (owner and repo are empty for legacy names)
+    async fn list_windows(&self) -> Result<Vec<IssueRef>>;
+
+    /// Close the window for a target
+    async fn kill_window(&self, target: &dyn WindowTarget) -> Result<()>;
+
+    /// Type `keys` into the target's first pane, followed by Enter
+    async fn send_keys(&self, target: &dyn WindowTarget, keys: &str) -> Result<()>;
+
+    /// Rename a window to include state indicator (e.g., "acme/api/2592-branch:waiting")
+    async fn rename_window(&self, target: &dyn WindowTarget, state: &str) -> Result<()>;
+
+    /// Focus a pane in the target's window (e.g., pane 0 after on_provision hooks)
+    async fn select_pane(&self, target: &dyn WindowTarget, pane_index: u32) -> Result<()>;
+
+    /// The last `lines` lines of the target's first pane as plain text.
+    /// Returns None if the target has no window.
+    async fn capture_pane(&self, target: &dyn WindowTarget, lines: u32) -> Result<Option<String>>;
+
+    /// Append everything the target's first pane prints to `log_file`, if the
+    /// multiplexer can. Does nothing if the pane is already being logged.
+    async fn log_output(&self, target: &dyn WindowTarget, log_file: &Path) -> Result<()>;
+
+    /// Command attaching the terminal to the session
+    fn attach_command(&self) -> std::process::Command;
+
+    /// Command that shows a target's window, switching to it when already
+    /// inside the session. Returns None if the target has no window.
+    async fn attach_window_command(
+        &self,
+        target: &dyn WindowTarget,
+    ) -> Result<Option<std::process::Command>>;
+}
+
+/// The multiplexer configured in `[tmux]`. `env` is set in the session when
+/// `ensure_session` runs, for the commands started in its windows.
+pub fn backend(config: &TmuxConfig, env: Vec<(String, String)>) -> Arc<dyn SessionBackend> {
+    match config.multiplexer {
+        Multiplexer::Tmux => Arc::new(TmuxManager::new(config).with_envs(env)),
+        Multiplexer::Zellij => Arc::new(ZellijManager::new(config).with_envs(env)),
+        Multiplexer::Screen => Arc::new(ScreenManager::new(config).with_envs(env)),
+    }
+}
+
+/// The multiplexer holding issue windows, or None when every agent issues
+/// can get runs headless and there are no windows
+pub fn issue_backend(config: &Config, env: Vec<(String, String)>) -> Option<Arc<dyn SessionBackend>> {
+    config
+        .uses_interactive_agents()
+        .then(|| backend(&config.tmux, env))
+}
+
+/// Window name for an issue: "{owner}/{repo}/{branch_name}"
+pub fn window_name(issue: &IssueRef, branch_name: &str) -> String {
+    format!("{}/{}/{}", issue.owner, issue.repo, branch_name)
+}
+
+/// Parse the issue a window belongs to from its name, ignoring any ":state" suffix.
+/// Legacy names without a repo prefix ("2592-branch") yield an empty owner and repo.
+pub fn parse_window_name(name: &str) -> Option<IssueRef> {
+    let base_name = name.split(':').next().unwrap_or(name);
+
+    let (owner, repo, branch) = match base_name.splitn(3, '/').collect::<Vec<_>>()[..] {
+        [owner, repo, branch] => (owner, repo, branch),
+        _ => ("", "", base_name),
+    };
+
+    let number = branch.split('-').next()?.parse::<u64>().ok()?;
+    Some(IssueRef::new(owner, repo, number))
+}
+
+/// Whether a parsed window belongs to `issue`. Legacy windows match on number alone.
+pub fn window_matches(window: &IssueRef, issue: &IssueRef) -> bool {
+    window.number == issue.number
+        && (window.owner.is_empty() || (window.owner == issue.owner && window.repo == issue.repo))
+}
+
+/// Name of a window with its state suffix replaced: "{base}:{state}"
+pub fn state_window_name(name: &str, state: &str) -> String {
+    let base_name = name.split(':').next().unwrap_or(name);
+    format!("{}:{}", base_name, state)
+}
+
+/// The last `lines` lines of captured text, without trailing blank lines
+pub fn last_lines(text: &str, lines: u32) -> String {
+    let text = text.trim_end();
+    let all: Vec<&str> = text.lines().collect();
+    let start = all.len().saturating_sub(lines as usize);
+    all[start..].join("\n")
+}
+
+/// In-memory backend for tests: windows are just names, and what is typed
+/// into them is recorded
+#[cfg(test)]
+pub mod fake {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone)]
+    pub struct FakeWindow {
+        pub name: String,
+        pub working_dir: PathBuf,
+        /// Everything passed to `send_keys`, in order
+        pub keys: Vec<String>,
+    }
+
+    #[derive(Default)]
+    pub struct FakeBackend {
+        windows: Mutex<Vec<FakeWindow>>,
+    }
+
+    impl FakeBackend {
+        pub fn windows(&self) -> Vec<FakeWindow> {
+            self.windows.lock().unwrap().clone()
+        }
+
+        /// Keys sent to a target's window, or None if it has no window
+        pub fn keys(&self, target: &dyn WindowTarget) -> Option<Vec<String>> {
+            self.windows
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|w| target.matches_window(&w.name))
+                .map(|w| w.keys.clone())
+        }
+
+        fn with_window<T>(
+            &self,
+            target: &dyn WindowTarget,
+            f: impl FnOnce(&mut FakeWindow) -> T,
+        ) -> Option<T> {
+            let mut windows = self.windows.lock().unwrap();
+            windows.iter_mut().find(|w| target.matches_window(&w.name)).map(f)
+        }
+    }
+
+    #[async_trait]
+    impl SessionBackend for FakeBackend {
+        async fn ensure_session(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn create_window(
+            &self,
+            target: &dyn WindowTarget,
+            branch_name: &str,
+            working_dir: &Path,
+        ) -> Result<()> {
+            if !self.window_exists(target).await? {
+                self.windows.lock().unwrap().push(FakeWindow {
+                    name: target.window_name(branch_name),
+                    working_dir: working_dir.to_path_buf(),
+                    keys: Vec::new(),
+                });
+            }
+            Ok(())
+        }
+
+        async fn window_exists(&self, target: &dyn WindowTarget) -> Result<bool> {
+            Ok(self.with_window(target, |_| ()).is_some())
+        }
+
+        async fn list_windows(&self) -> Result<Vec<IssueRef>> {
+            let windows = self.windows.lock().unwrap();
+            Ok(windows.iter().filter_map(|w| parse_window_name(&w.name)).collect())
+        }
+
+        async fn kill_window(&self, target: &dyn WindowTarget) -> Result<()> {
+            self.windows
+                .lock()
+                .unwrap()
+                .retain(|w| !target.matches_window(&w.name));
+            Ok(())
+        }
+
+        async fn send_keys(&self, target: &dyn WindowTarget, keys: &str) -> Result<()> {
+            self.with_window(target, |w| w.keys.push(keys.to_string()))
+                .ok_or_else(|| anyhow::anyhow!("No window found for issue {}", target))
+        }
+
+        async fn rename_window(&self, target: &dyn WindowTarget, state: &str) -> Result<()> {
+            self.with_window(target, |w| w.name = state_window_name(&w.name, state));
+            Ok(())
+        }
+
+        async fn select_pane(&self, _target: &dyn WindowTarget, _pane_index: u32) -> Result<()> {
+            Ok(())
+        }
+
+        async fn capture_pane(&self, target: &dyn WindowTarget, lines: u32) -> Result<Option<String>> {
+            Ok(self.with_window(target, |w| last_lines(&w.keys.join("\n"), lines)))
+        }
+
+        async fn log_output(&self, _target: &dyn WindowTarget, _log_file: &Path) -> Result<()> {
+            Ok(())
+        }
+
+        fn attach_command(&self) -> std::process::Command {
+            std::process::Command::new("true")
+        }
+
+        async fn attach_window_command(
+            &self,
+            target: &dyn WindowTarget,
+        ) -> Result<Option<std::process::Command>> {
+            Ok(self
+                .window_exists(target)
+                .await?
+                .then(|| std::process::Command::new("true")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::FakeBackend;
+    use super::*;
+
+    #[test]
+    fn test_window_name_roundtrip() {
+        let issue = IssueRef::new("acme", "api", 2592);
+        let name = window_name(&issue, "2592-add-invoices_user_pleb");
+        assert_eq!(name, "acme/api/2592-add-invoices_user_pleb");
+        assert_eq!(parse_window_name(&name), Some(issue.clone()));
+        assert_eq!(parse_window_name(&format!("{}:waiting", name)), Some(issue));
+    }
+
+    #[test]
+    fn test_window_matches_repo() {
+        let api = IssueRef::new("acme", "api", 12);
+        let web = IssueRef::new("acme", "web", 12);
+
+        let window = parse_window_name("acme/api/12-fix_user_pleb:working").unwrap();
+        assert!(window_matches(&window, &api));
+        assert!(!window_matches(&window, &web));
+
+        // Legacy names carry no repo and match on number alone
+        let legacy = parse_window_name("12-fix_user_pleb").unwrap();
+        assert!(window_matches(&legacy, &api));
+        assert!(window_matches(&legacy, &web));
+
+        assert_eq!(parse_window_name("zsh"), None);
+    }
+
+    #[test]
+    fn test_state_window_name_and_last_lines() {
+        assert_eq!(state_window_name("acme/api/12-fix", "waiting"), "acme/api/12-fix:waiting");
+        assert_eq!(state_window_name("acme/api/12-fix:waiting", "working"), "acme/api/12-fix:working");
+        assert_eq!(last_lines("a\nb\nc\n\n", 2), "b\nc");
+        assert_eq!(last_lines("a", 5), "a");
+    }
+
+    #[tokio::test]
+    async fn test_fake_backend() {
+        let backend = FakeBackend::default();
+        let issue = IssueRef::new("acme", "api", 12);
+
+        backend
+            .create_window(&issue, "12-fix_user_pleb", Path::new("/work/12-fix_user_pleb"))
+            .await
+            .unwrap();
+        assert!(backend.window_exists(&issue).await.unwrap());
+        assert_eq!(backend.list_windows().await.unwrap(), vec![issue.clone()]);
+
+        backend.send_keys(&issue, "claude").await.unwrap();
+        backend.rename_window(&issue, "working").await.unwrap();
+        assert_eq!(backend.windows()[0].name, "acme/api/12-fix_user_pleb:working");
+        assert_eq!(backend.windows()[0].working_dir, Path::new("/work/12-fix_user_pleb"));
+        assert_eq!(backend.capture_pane(&issue, 10).await.unwrap().as_deref(), Some("claude"));
+
+        backend.kill_window(&issue).await.unwrap();
+        assert!(!backend.window_exists(&issue).await.unwrap());
+        assert!(backend.send_keys(&issue, "claude").await.is_err());
+    }
+}
@@ -1,10 +1,14 @@
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::Instant;
+use nix::fcntl::{Flock, FlockArg};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Represents the lifecycle state of an issue being managed by pleb
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PlebState {
     Ready,
     Provisioning,
@@ -33,67 +37,303 @@ impl PlebState {
     pub fn is_terminal(&self) -> bool {
         self.valid_transitions().is_empty()
     }
+
+    /// Returns true if an issue in this state occupies a session slot
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            PlebState::Provisioning | PlebState::Waiting | PlebState::Working
+        )
+    }
+
+    /// Returns true if moving from this state to `to` is allowed
+    pub fn can_transition_to(&self, to: PlebState) -> bool {
+        self.valid_transitions().contains(&to)
+    }
+
+    /// Lowercase name used for display and tmux window suffixes
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlebState::Ready => "ready",
+            PlebState::Provisioning => "provisioning",
+            PlebState::Waiting => "waiting",
+            PlebState::Working => "working",
+            PlebState::Done => "done",
+            PlebState::Finished => "finished",
+        }
+    }
+}
+
+/// Error returned when a transition is not allowed by the state machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub issue_number: u64,
+    pub from: PlebState,
+    pub to: PlebState,
+}
+
+impl std::fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot transition issue #{} from {:?} to {:?}. Valid transitions from {:?} are: {:?}",
+            self.issue_number,
+            self.from,
+            self.to,
+            self.from,
+            self.from.valid_transitions()
+        )
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
+/// Check a transition against the state machine
+pub fn validate_transition(issue_number: u64, from: PlebState, to: PlebState) -> Result<()> {
+    if from.can_transition_to(to) {
+        Ok(())
+    } else {
+        Err(InvalidTransition {
+            issue_number,
+            from,
+            to,
+        }
+        .into())
+    }
+}
+
+/// Current wall-clock time as seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Format a duration into a human-readable string
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    let days = secs / 86400;
+    let hours = (secs % 86400) / 3600;
+    let minutes = (secs % 3600) / 60;
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
 }
 
 /// Represents a single tracked issue with its current state and metadata
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackedIssue {
     pub issue_number: u64,
     pub state: PlebState,
+    /// Issue title at the time it was provisioned
+    #[serde(default)]
+    pub title: String,
+    /// GitHub URL of the issue
+    #[serde(default)]
+    pub html_url: String,
     pub worktree_path: Option<PathBuf>,
-    pub started_at: Instant,
-    pub last_updated: Instant,
+    #[serde(default)]
+    pub branch_name: Option<String>,
+    /// Base name of the tmux window (without the ":state" suffix)
+    #[serde(default)]
+    pub tmux_window: Option<String>,
+    /// Claude Code session id, taken from the most recent hook payload
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Values the scheduler ranked this issue on while it was queued
+    #[serde(default)]
+    pub rank: Option<String>,
+    /// Newest issue comment id already delivered to the session
+    #[serde(default)]
+    pub last_comment_id: Option<u64>,
+    /// Id of the status comment pleb keeps on the issue
+    #[serde(default)]
+    pub status_comment_id: Option<u64>,
+    /// URL of the pull request for this issue, once one is found
+    #[serde(default)]
+    pub pr_url: Option<String>,
+    /// Sandbox instance the session runs in, if any
+    #[serde(default)]
+    pub sandbox: Option<String>,
+    /// Agent profile working on the issue (the repository's default if not recorded)
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// The agent's own transcript of the session, as reported by its hooks
+    #[serde(default)]
+    pub transcript_path: Option<PathBuf>,
+    /// Why Claude last stopped and handed control back (e.g. a permission request)
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    /// Unix timestamp (seconds) when the issue entered its current state
+    #[serde(default)]
+    pub state_entered_at: u64,
+    /// Seconds spent in each earlier state (the current one is not included)
+    #[serde(default)]
+    pub time_in_state: BTreeMap<PlebState, u64>,
+    /// Unix timestamp (seconds) when tracking started
+    pub started_at: u64,
+    /// Unix timestamp (seconds) of the last change to this record
+    pub last_updated: u64,
+}
+
+impl TrackedIssue {
+    /// When the issue entered its current state. Records written before this
+    /// was tracked fall back to the last update.
+    pub fn entered_current_state_at(&self) -> u64 {
+        if self.state_entered_at > 0 {
+            self.state_entered_at
+        } else {
+            self.last_updated
+        }
+    }
+
+    /// Seconds spent in each state so far, including the current one, in lifecycle order
+    pub fn time_in_states(&self, now: u64) -> Vec<(PlebState, u64)> {
+        let mut times = self.time_in_state.clone();
+        *times.entry(self.state).or_default() +=
+            now.saturating_sub(self.entered_current_state_at());
+        times.into_iter().collect()
+    }
 }
 
 /// Manages the state of all issues being tracked by pleb
-#[derive(Debug)]
-#[allow(dead_code)]
+///
+/// The tracker is persisted as JSON in the daemon directory (see
+/// `Config::state_file`) so the daemon and CLI commands share one view of
+/// every managed issue across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IssueTracker {
     tracked: HashMap<u64, TrackedIssue>,
+    /// Ready issues waiting for a free session slot, in FIFO order
+    #[serde(default)]
+    queue: VecDeque<u64>,
 }
 
 impl IssueTracker {
     /// Create a new empty issue tracker
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
             tracked: HashMap::new(),
+            queue: VecDeque::new(),
         }
     }
 
+    /// Load a tracker from disk, returning an empty tracker if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json(path, "state file")
+    }
+
+    /// Load the tracker, apply a change and save it, holding an advisory lock
+    /// on the state file throughout so the daemon and CLI commands don't lose
+    /// each other's writes. Every writer goes through here.
+    pub fn update<T>(path: &Path, f: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        update_json(path, "state file", f)
+    }
+
     /// Start tracking an issue with the given state
-    #[allow(dead_code)]
-    pub fn track(&mut self, issue_number: u64, state: PlebState) {
-        let now = Instant::now();
+    /// Returns the new record so callers can fill in metadata
+    pub fn track(&mut self, issue_number: u64, state: PlebState) -> &mut TrackedIssue {
+        let now = unix_now();
         let tracked_issue = TrackedIssue {
             issue_number,
             state,
+            title: String::new(),
+            html_url: String::new(),
             worktree_path: None,
+            branch_name: None,
+            tmux_window: None,
+            session_id: None,
+            rank: None,
+            last_comment_id: None,
+            status_comment_id: None,
+            pr_url: None,
+            stop_reason: None,
+            sandbox: None,
+            agent: None,
+            transcript_path: None,
+            state_entered_at: now,
+            time_in_state: BTreeMap::new(),
             started_at: now,
             last_updated: now,
         };
         self.tracked.insert(issue_number, tracked_issue);
+        self.tracked.get_mut(&issue_number).unwrap()
     }
 
-    /// Stop tracking an issue
-    #[allow(dead_code)]
+    /// Stop tracking an issue (also removes it from the queue)
     pub fn untrack(&mut self, issue_number: u64) -> Option<TrackedIssue> {
+        self.dequeue(issue_number);
         self.tracked.remove(&issue_number)
     }
 
+    /// Add an issue to the back of the provisioning queue (no-op if already queued)
+    /// Returns the issue's 0-based queue position
+    pub fn enqueue(&mut self, issue_number: u64) -> usize {
+        if let Some(position) = self.queue_position(issue_number) {
+            return position;
+        }
+        self.queue.push_back(issue_number);
+        self.queue.len() - 1
+    }
+
+    /// Remove an issue from the provisioning queue
+    pub fn dequeue(&mut self, issue_number: u64) -> bool {
+        let before = self.queue.len();
+        self.queue.retain(|n| *n != issue_number);
+        self.queue.len() != before
+    }
+
+    /// Get an issue's 0-based position in the provisioning queue
+    pub fn queue_position(&self, issue_number: u64) -> Option<usize> {
+        self.queue.iter().position(|n| *n == issue_number)
+    }
+
+    /// Reorder the queue to follow `order`; queued issues missing from `order` keep
+    /// their relative order at the back
+    pub fn reorder_queue(&mut self, order: &[u64]) {
+        let mut queue: Vec<u64> = self.queue.iter().copied().collect();
+        queue.sort_by_key(|n| order.iter().position(|o| o == n).unwrap_or(usize::MAX));
+        self.queue = queue.into();
+    }
+
+    /// Get all queued issue numbers in queue order
+    pub fn queued(&self) -> Vec<u64> {
+        self.queue.iter().copied().collect()
+    }
+
+    /// Number of issues currently occupying a session slot
+    pub fn active_count(&self) -> usize {
+        self.tracked.values().filter(|issue| issue.state.is_active()).count()
+    }
+
     /// Get a tracked issue by number
-    #[allow(dead_code)]
     pub fn get(&self, issue_number: u64) -> Option<&TrackedIssue> {
         self.tracked.get(&issue_number)
     }
 
     /// Get a mutable reference to a tracked issue by number
-    #[allow(dead_code)]
     pub fn get_mut(&mut self, issue_number: u64) -> Option<&mut TrackedIssue> {
         self.tracked.get_mut(&issue_number)
     }
 
+    /// Get all tracked issues, sorted by issue number
+    pub fn all(&self) -> Vec<&TrackedIssue> {
+        let mut issues: Vec<&TrackedIssue> = self.tracked.values().collect();
+        issues.sort_by_key(|issue| issue.issue_number);
+        issues
+    }
+
+    /// Returns true if no issues are tracked
+    pub fn is_empty(&self) -> bool {
+        self.tracked.is_empty()
+    }
+
     /// Get all issues in a specific state
     #[allow(dead_code)]
     pub fn get_by_state(&self, state: PlebState) -> Vec<&TrackedIssue> {
@@ -103,15 +343,20 @@ impl IssueTracker {
             .collect()
     }
 
-    /// Update the state of a tracked issue
-    #[allow(dead_code)]
+    /// Update the state of a tracked issue (no validation; see `transition`)
     pub fn update_state(&mut self, issue_number: u64, new_state: PlebState) -> Result<()> {
         let issue = self.tracked.get_mut(&issue_number).with_context(|| {
             format!("Issue #{} is not being tracked", issue_number)
         })?;
 
+        let now = unix_now();
+        if issue.state != new_state {
+            let spent = now.saturating_sub(issue.entered_current_state_at());
+            *issue.time_in_state.entry(issue.state).or_default() += spent;
+            issue.state_entered_at = now;
+        }
         issue.state = new_state;
-        issue.last_updated = Instant::now();
+        issue.last_updated = now;
         Ok(())
     }
 
@@ -123,7 +368,55 @@ impl IssueTracker {
         })?;
 
         issue.worktree_path = Some(path);
-        issue.last_updated = Instant::now();
+        issue.last_updated = unix_now();
+        Ok(())
+    }
+
+    /// Record the Claude Code session id for a tracked issue
+    pub fn set_session_id(&mut self, issue_number: u64, session_id: &str) -> Result<()> {
+        let issue = self.tracked.get_mut(&issue_number).with_context(|| {
+            format!("Issue #{} is not being tracked", issue_number)
+        })?;
+
+        if issue.session_id.as_deref() != Some(session_id) {
+            issue.session_id = Some(session_id.to_string());
+            issue.last_updated = unix_now();
+        }
+        Ok(())
+    }
+
+    /// Record where the agent keeps the session's transcript
+    pub fn set_transcript_path(&mut self, issue_number: u64, path: &Path) -> Result<()> {
+        let issue = self.tracked.get_mut(&issue_number).with_context(|| {
+            format!("Issue #{} is not being tracked", issue_number)
+        })?;
+
+        if issue.transcript_path.as_deref() != Some(path) {
+            issue.transcript_path = Some(path.to_path_buf());
+            issue.last_updated = unix_now();
+        }
+        Ok(())
+    }
+
+    /// Record the newest issue comment delivered to the session
+    pub fn set_last_comment_id(&mut self, issue_number: u64, comment_id: u64) -> Result<()> {
+        let issue = self.tracked.get_mut(&issue_number).with_context(|| {
+            format!("Issue #{} is not being tracked", issue_number)
+        })?;
+
+        issue.last_comment_id = Some(comment_id);
+        issue.last_updated = unix_now();
+        Ok(())
+    }
+
+    /// Record why Claude last stopped working on an issue
+    pub fn set_stop_reason(&mut self, issue_number: u64, reason: &str) -> Result<()> {
+        let issue = self.tracked.get_mut(&issue_number).with_context(|| {
+            format!("Issue #{} is not being tracked", issue_number)
+        })?;
+
+        issue.stop_reason = Some(reason.to_string());
+        issue.last_updated = unix_now();
         Ok(())
     }
 
@@ -134,19 +427,7 @@ impl IssueTracker {
             format!("Issue #{} is not being tracked", issue_number)
         })?;
 
-        let current_state = issue.state;
-        let valid_transitions = current_state.valid_transitions();
-
-        if !valid_transitions.contains(&to_state) {
-            anyhow::bail!(
-                "Cannot transition issue #{} from {:?} to {:?}. Valid transitions from {:?} are: {:?}",
-                issue_number,
-                current_state,
-                to_state,
-                current_state,
-                valid_transitions
-            );
-        }
+        validate_transition(issue_number, issue.state, to_state)?;
 
         // Transition is valid, update the state
         self.update_state(issue_number, to_state)
@@ -159,6 +440,68 @@ impl Default for IssueTracker {
     }
 }
 
+/// Read a JSON state file (`what` names it in errors), returning the default
+/// value if it doesn't exist
+pub fn load_json<S: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<S> {
+    if !path.exists() {
+        return Ok(S::default());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}: {}", what, path.display()))?;
+
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}: {}", what, path.display()))
+}
+
+/// Write a JSON state file.
+/// Writes to a temporary file first and renames it so readers never see a partial file
+pub fn save_json<S: Serialize>(value: &S, path: &Path, what: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory: {}", parent.display()))?;
+    }
+
+    let json = serde_json::to_string_pretty(value).with_context(|| format!("Failed to serialize {}", what))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, json)
+        .with_context(|| format!("Failed to write {}: {}", what, tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}: {}", what, path.display()))?;
+
+    Ok(())
+}
+
+/// Load a JSON state file, apply a change and save it, holding an advisory
+/// lock on the file throughout so concurrent writers don't lose each other's changes
+pub fn update_json<S, T>(path: &Path, what: &str, f: impl FnOnce(&mut S) -> T) -> Result<T>
+where
+    S: Serialize + DeserializeOwned + Default,
+{
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory: {}", parent.display()))?;
+    }
+
+    // The file itself is replaced on save, so the lock lives next to it
+    let lock_path = path.with_extension("json.lock");
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file: {}", lock_path.display()))?;
+    let _lock = Flock::lock(lock_file, FlockArg::LockExclusive)
+        .map_err(|(_, errno)| errno)
+        .with_context(|| format!("Failed to lock {}: {}", what, lock_path.display()))?;
+
+    let mut value = load_json(path, what)?;
+    let result = f(&mut value);
+    save_json(&value, path, what)?;
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,4 +693,179 @@ mod tests {
 
         assert_eq!(tracker.get(123).unwrap().state, PlebState::Working);
     }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("pleb-state-test-{}", std::process::id()));
+        let path = dir.join("state.json");
+
+        let mut tracker = IssueTracker::new();
+        let issue = tracker.track(123, PlebState::Working);
+        issue.title = "Fix the bug".to_string();
+        issue.branch_name = Some("123-fix-the-bug_user_pleb".to_string());
+        tracker
+            .set_worktree_path(123, PathBuf::from("/tmp/worktrees/123-fix-the-bug_user_pleb"))
+            .unwrap();
+        tracker.set_session_id(123, "session-abc").unwrap();
+        save_json(&tracker, &path, "state file").unwrap();
+
+        let loaded = IssueTracker::load(&path).unwrap();
+        let issue = loaded.get(123).unwrap();
+        assert_eq!(issue.state, PlebState::Working);
+        assert_eq!(issue.title, "Fix the bug");
+        assert_eq!(issue.branch_name.as_deref(), Some("123-fix-the-bug_user_pleb"));
+        assert_eq!(issue.session_id.as_deref(), Some("session-abc"));
+        assert!(issue.started_at > 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let dir = std::env::temp_dir().join(format!("pleb-state-lock-test-{}", std::process::id()));
+        let path = dir.join("state.json");
+
+        let writers: Vec<_> = (0..8u64)
+            .map(|n| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    IssueTracker::update(&path, |t| {
+                        t.track(n, PlebState::Ready);
+                    })
+                    .unwrap()
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(IssueTracker::load(&path).unwrap().all().len(), 8);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let path = std::env::temp_dir().join("pleb-state-test-missing/state.json");
+        let tracker = IssueTracker::load(&path).unwrap();
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_state_serializes_lowercase() {
+        let json = serde_json::to_string(&PlebState::Waiting).unwrap();
+        assert_eq!(json, "\"waiting\"");
+        assert_eq!(PlebState::Finished.name(), "finished");
+    }
+
+    #[test]
+    fn test_can_transition_to() {
+        assert!(PlebState::Working.can_transition_to(PlebState::Done));
+        assert!(!PlebState::Finished.can_transition_to(PlebState::Waiting));
+        assert!(!PlebState::Done.can_transition_to(PlebState::Waiting));
+    }
+
+    #[test]
+    fn test_invalid_transition_error_downcasts() {
+        let err = validate_transition(42, PlebState::Finished, PlebState::Waiting).unwrap_err();
+        let invalid = err.downcast_ref::<InvalidTransition>().expect("Should be InvalidTransition");
+        assert_eq!(invalid.issue_number, 42);
+        assert_eq!(invalid.from, PlebState::Finished);
+        assert_eq!(invalid.to, PlebState::Waiting);
+        assert!(err.to_string().contains("Cannot transition"));
+    }
+
+    #[test]
+    fn test_queue_fifo_order() {
+        let mut tracker = IssueTracker::new();
+        assert_eq!(tracker.enqueue(10), 0);
+        assert_eq!(tracker.enqueue(20), 1);
+        assert_eq!(tracker.enqueue(30), 2);
+
+        // Enqueueing again keeps the original position
+        assert_eq!(tracker.enqueue(10), 0);
+        assert_eq!(tracker.queued(), vec![10, 20, 30]);
+
+        assert!(tracker.dequeue(10));
+        assert!(!tracker.dequeue(10));
+        assert_eq!(tracker.queue_position(20), Some(0));
+        assert_eq!(tracker.queue_position(30), Some(1));
+        assert_eq!(tracker.queue_position(10), None);
+    }
+
+    #[test]
+    fn test_untrack_removes_from_queue() {
+        let mut tracker = IssueTracker::new();
+        tracker.track(10, PlebState::Ready);
+        tracker.enqueue(10);
+
+        tracker.untrack(10);
+        assert!(tracker.queued().is_empty());
+    }
+
+    #[test]
+    fn test_active_count() {
+        let mut tracker = IssueTracker::new();
+        tracker.track(1, PlebState::Ready);
+        tracker.track(2, PlebState::Provisioning);
+        tracker.track(3, PlebState::Working);
+        tracker.track(4, PlebState::Waiting);
+        tracker.track(5, PlebState::Done);
+        tracker.track(6, PlebState::Finished);
+
+        // Done and Finished issues free their slot
+        assert_eq!(tracker.active_count(), 3);
+    }
+
+    #[test]
+    fn test_reorder_queue() {
+        let mut tracker = IssueTracker::new();
+        tracker.enqueue(10);
+        tracker.enqueue(20);
+        tracker.enqueue(30);
+        tracker.enqueue(40);
+
+        tracker.reorder_queue(&[30, 10, 99]);
+        assert_eq!(tracker.queued(), vec![30, 10, 20, 40]);
+    }
+
+    #[test]
+    fn test_time_in_states() {
+        let mut tracker = IssueTracker::new();
+        tracker.track(7, PlebState::Provisioning);
+
+        // Pretend provisioning started 5 minutes ago
+        let entered = unix_now() - 300;
+        tracker.get_mut(7).unwrap().state_entered_at = entered;
+        tracker.update_state(7, PlebState::Working).unwrap();
+
+        // Re-entering a state adds to its total
+        tracker.get_mut(7).unwrap().time_in_state.insert(PlebState::Waiting, 60);
+
+        let issue = tracker.get(7).unwrap();
+        let provisioning = issue.time_in_state[&PlebState::Provisioning];
+        assert!((300..310).contains(&provisioning));
+
+        let now = issue.state_entered_at + 120;
+        assert_eq!(
+            issue.time_in_states(now),
+            vec![
+                (PlebState::Provisioning, provisioning),
+                (PlebState::Waiting, 60),
+                (PlebState::Working, 120),
+            ]
+        );
+
+        // The state file stores the totals by state name
+        let json = serde_json::to_value(issue).unwrap();
+        assert_eq!(json["time_in_state"]["waiting"], 60);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        use std::time::Duration;
+        assert_eq!(format_duration(Duration::from_secs(59)), "0m");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 120)), "3h 2m");
+        assert_eq!(format_duration(Duration::from_secs(2 * 86400 + 3600)), "2d 1h 0m");
+    }
 }
@@ -0,0 +1,227 @@
+//! The status comment pleb keeps on each issue it manages.
+//!
+//! Label changes are all a reporter sees otherwise. On every transition the
+//! comment is re-rendered from the `prompts.status_comment` template and
+//! edited in place; the first transition creates it and its id is stored in
+//! the state file. A hidden marker identifies it so it is never fed back into
+//! the session as a new comment.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::CostsConfig;
+use crate::github::GitHubClient;
+use crate::state::{self, IssueTracker, TrackedIssue};
+use crate::templates::{StateTime, StatusContext, TemplateEngine};
+use crate::usage::{self, IssueUsage, ModelTokens};
+
+/// Hidden marker at the top of every status comment
+pub const MARKER: &str = "<!-- pleb:status -->";
+
+/// Whether a comment body is a pleb status comment
+pub fn is_status_comment(body: &str) -> bool {
+    body.trim_start().starts_with(MARKER)
+}
+
+/// Name of the machine the daemon (and the worktrees) run on
+pub fn hostname() -> String {
+    nix::unistd::gethostname()
+        .ok()
+        .and_then(|name| name.into_string().ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl StatusContext {
+    /// Build the template context from a tracked issue
+    pub fn from_tracked(tracked: &TrackedIssue, worktree_host: &str, now: u64) -> Self {
+        Self {
+            issue_number: tracked.issue_number,
+            title: tracked.title.clone(),
+            state: tracked.state.name().to_string(),
+            branch_name: tracked.branch_name.clone(),
+            worktree_host: worktree_host.to_string(),
+            worktree_path: tracked
+                .worktree_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            pr_url: tracked.pr_url.clone(),
+            time_in_state: tracked
+                .time_in_states(now)
+                .into_iter()
+                .map(|(state, secs)| StateTime {
+                    state: state.name().to_string(),
+                    duration: state::format_duration(std::time::Duration::from_secs(secs)),
+                })
+                .collect(),
+            stop_reason: tracked.stop_reason.clone(),
+            tokens: None,
+            cost: None,
+        }
+    }
+
+    /// Add the issue's token usage and its cost
+    pub fn with_usage(mut self, models: &ModelTokens, costs: &CostsConfig) -> Self {
+        if !models.is_empty() {
+            self.tokens = Some(usage::describe(&usage::total(models)));
+            self.cost = Some(usage::format_cost(usage::cost(models, costs)));
+        }
+        self
+    }
+}
+
+/// Creates and edits the status comment on an issue
+pub struct StatusCommenter<'a> {
+    github: &'a GitHubClient,
+    templates: &'a TemplateEngine,
+    template: &'a str,
+    /// Prices for showing usage, if the comment shows it
+    costs: Option<&'a CostsConfig>,
+}
+
+impl<'a> StatusCommenter<'a> {
+    pub fn new(github: &'a GitHubClient, templates: &'a TemplateEngine, template: &'a str) -> Self {
+        Self {
+            github,
+            templates,
+            template,
+            costs: None,
+        }
+    }
+
+    /// Also show the issue's tokens and cost
+    pub fn with_costs(mut self, costs: &'a CostsConfig) -> Self {
+        self.costs = Some(costs);
+        self
+    }
+
+    /// Render the issue's status from the state file and post or edit its comment
+    pub async fn update(&self, state_file: &Path, issue_number: u64) -> Result<()> {
+        let tracker = IssueTracker::load(state_file)?;
+        let Some(tracked) = tracker.get(issue_number).cloned() else {
+            return Ok(());
+        };
+
+        // Look the PR up (one query, by the tracked branch) until one is found;
+        // after that the URL doesn't change
+        let pr_url = match &tracked.pr_url {
+            Some(url) => Some(url.clone()),
+            None => self
+                .github
+                .get_pull_request_for_issue(issue_number, tracked.branch_name.as_deref())
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::debug!("Failed to look up PR for issue #{}: {:#}", issue_number, e);
+                    None
+                }),
+        };
+
+        let mut context = StatusContext::from_tracked(
+            &TrackedIssue {
+                pr_url: pr_url.clone(),
+                ..tracked.clone()
+            },
+            &hostname(),
+            state::unix_now(),
+        );
+        if let (Some(costs), Some(state_dir)) = (self.costs, state_file.parent()) {
+            let issue_dir = state_dir.join(issue_number.to_string());
+            match IssueUsage::load(&issue_dir) {
+                Ok(usage) => context = context.with_usage(&usage.by_model(None), costs),
+                Err(e) => tracing::warn!("Failed to load usage of issue #{}: {}", issue_number, e),
+            }
+        }
+        let body = format!(
+            "{}\n{}",
+            MARKER,
+            self.templates.render_status(self.template, &context)?
+        );
+
+        let comment_id = match tracked.status_comment_id {
+            Some(id) => match self.github.update_comment(id, &body).await {
+                Ok(()) => id,
+                Err(e) => {
+                    // Most likely deleted by someone; start a new one
+                    tracing::debug!("{:#}, posting a new status comment", e);
+                    self.github.create_comment(issue_number, &body).await?
+                }
+            },
+            None => self.github.create_comment(issue_number, &body).await?,
+        };
+
+        if tracked.status_comment_id != Some(comment_id) || tracked.pr_url != pr_url {
+            IssueTracker::update(state_file, |t| {
+                if let Some(issue) = t.get_mut(issue_number) {
+                    issue.status_comment_id = Some(comment_id);
+                    issue.pr_url = pr_url;
+                }
+            })?;
+        }
+
+        tracing::debug!(
+            "Updated status comment on issue {}",
+            self.github.issue_ref(issue_number)
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PromptsConfig;
+    use crate::state::PlebState;
+
+    #[test]
+    fn test_render_status_comment() {
+        let mut tracker = IssueTracker::new();
+        let tracked = tracker.track(42, PlebState::Provisioning);
+        tracked.title = "Add invoices table".to_string();
+        tracked.branch_name = Some("42-add-invoices-table_alice_pleb".to_string());
+        tracked.worktree_path = Some("/work/42-add-invoices-table_alice_pleb".into());
+        tracked.time_in_state.insert(PlebState::Provisioning, 90);
+        tracked.state = PlebState::Waiting;
+        tracked.stop_reason = Some("Needs permission to use Bash".to_string());
+        let now = tracked.state_entered_at + 3600;
+
+        let context = StatusContext::from_tracked(tracked, "buildbox", now);
+
+        let prompts = PromptsConfig {
+            dir: "/nonexistent/prompts".into(),
+            ..Default::default()
+        };
+        let mut templates = TemplateEngine::new(&prompts).unwrap();
+        templates
+            .load_status_comment_template(&prompts.status_comment)
+            .unwrap();
+        let rendered = templates
+            .render_status(&prompts.status_comment, &context)
+            .unwrap();
+
+        assert!(rendered.contains("| State | `waiting` |"));
+        assert!(rendered.contains("`42-add-invoices-table_alice_pleb`"));
+        assert!(rendered.contains("`buildbox:/work/42-add-invoices-table_alice_pleb`"));
+        assert!(rendered.contains("Needs permission to use Bash"));
+        assert!(rendered.contains("provisioning 1m, waiting 1h 0m"));
+        // No PR yet, so no PR row
+        assert!(!rendered.contains("Pull request"));
+        assert!(!rendered.contains("| Tokens |"));
+
+        let costs: CostsConfig = toml::from_str("[prices.claude-opus-4]\ninput = 15.0\noutput = 75.0").unwrap();
+        let models = ModelTokens::from([(
+            "claude-opus-4-1".to_string(),
+            usage::Tokens { input: 20_000, output: 4_000, cache_write: 0, cache_read: 0 },
+        )]);
+        let rendered = templates
+            .render_status(&prompts.status_comment, &context.with_usage(&models, &costs))
+            .unwrap();
+        assert!(rendered.contains("| Tokens | 20.0k in, 4.0k out, 0 cache read, 0 cache write |"));
+        assert!(rendered.contains("| Cost | $0.60 |"));
+    }
+
+    #[test]
+    fn test_is_status_comment() {
+        assert!(is_status_comment(&format!("{}\n**pleb** is handling this issue.", MARKER)));
+        assert!(!is_status_comment("Please also handle refunds"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Prometheus metrics for the daemon.
+//!
+//! Call sites record through the `metrics` macros right next to their
+//! existing log lines, using the names below. Without `[metrics] listen` no
+//! recorder is installed and those calls are no-ops. With it, a localhost
+//! listener serves the text exposition format at `GET /metrics`.
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::routing::get;
+use axum::Router;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::net::SocketAddr;
+
+use crate::state::{IssueTracker, PlebState};
+
+/// Gauge: managed issues per repo and state
+pub const ISSUES: &str = "pleb_issues";
+/// Histogram: seconds from picking up a ready issue to Claude working on it
+pub const PROVISIONING_DURATION: &str = "pleb_provisioning_duration_seconds";
+/// Counter: errors in the poll loop, by stage
+pub const POLL_ERRORS: &str = "pleb_poll_errors_total";
+/// Counter: GitHub API requests, by API (rest or graphql)
+pub const GITHUB_API_CALLS: &str = "pleb_github_api_calls_total";
+/// Gauge: remaining GitHub rate limit, by resource
+pub const GITHUB_RATE_LIMIT_REMAINING: &str = "pleb_github_rate_limit_remaining";
+/// Counter: Claude Code hook messages received, by event name
+pub const HOOK_MESSAGES: &str = "pleb_hook_messages_total";
+/// Counter: issue attachments that could not be downloaded
+pub const MEDIA_DOWNLOAD_FAILURES: &str = "pleb_media_download_failures_total";
+/// Counter: failed IPC connections from hooks
+pub const IPC_CONNECTION_ERRORS: &str = "pleb_ipc_connection_errors_total";
+
+const ALL_STATES: [PlebState; 6] = [
+    PlebState::Ready,
+    PlebState::Provisioning,
+    PlebState::Waiting,
+    PlebState::Working,
+    PlebState::Done,
+    PlebState::Finished,
+];
+
+/// Build the recorder with pleb's histogram buckets
+fn builder() -> Result<PrometheusBuilder> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(PROVISIONING_DURATION.to_string()),
+            &[5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0],
+        )
+        .context("Failed to configure metric buckets")
+}
+
+fn describe() {
+    metrics::describe_gauge!(ISSUES, "Managed issues per repository and state");
+    metrics::describe_histogram!(
+        PROVISIONING_DURATION,
+        metrics::Unit::Seconds,
+        "Time from picking up a ready issue to Claude working on it"
+    );
+    metrics::describe_counter!(POLL_ERRORS, "Errors in the poll loop, by stage");
+    metrics::describe_counter!(GITHUB_API_CALLS, "GitHub API requests, by API");
+    metrics::describe_gauge!(
+        GITHUB_RATE_LIMIT_REMAINING,
+        "Remaining GitHub rate limit, by resource"
+    );
+    metrics::describe_counter!(HOOK_MESSAGES, "Claude Code hook messages received, by event");
+    metrics::describe_counter!(
+        MEDIA_DOWNLOAD_FAILURES,
+        "Issue attachments that could not be downloaded"
+    );
+    metrics::describe_counter!(IPC_CONNECTION_ERRORS, "Failed IPC connections from hooks");
+}
+
+/// Install the global recorder. Metrics recorded before this are lost.
+pub fn install() -> Result<PrometheusHandle> {
+    let handle = builder()?
+        .install_recorder()
+        .context("Failed to install metrics recorder")?;
+    describe();
+    Ok(handle)
+}
+
+/// Set the per-state issue gauges for one repository
+pub fn record_issue_states(repo: &str, tracker: &IssueTracker) {
+    for state in ALL_STATES {
+        let count = tracker.get_by_state(state).len();
+        metrics::gauge!(ISSUES, "repo" => repo.to_string(), "state" => state.name())
+            .set(count as f64);
+    }
+}
+
+/// HTTP listener serving `GET /metrics`
+pub struct MetricsServer {
+    listen: String,
+    handle: PrometheusHandle,
+}
+
+impl MetricsServer {
+    /// Create a new metrics server (doesn't start listening yet)
+    pub fn new(listen: &str, handle: PrometheusHandle) -> Self {
+        Self {
+            listen: listen.to_string(),
+            handle,
+        }
+    }
+
+    /// Start listening and return the bound address
+    pub async fn start(&self) -> Result<SocketAddr> {
+        let listener = tokio::net::TcpListener::bind(&self.listen)
+            .await
+            .with_context(|| format!("Failed to bind metrics listener to {}", self.listen))?;
+        let addr = listener
+            .local_addr()
+            .context("Failed to get metrics listener address")?;
+
+        let app = Router::new()
+            .route("/metrics", get(render))
+            .with_state(self.handle.clone());
+
+        tracing::info!("Metrics on http://{}/metrics", addr);
+        if !addr.ip().is_loopback() {
+            tracing::warn!(
+                "Metrics are reachable from the network on {} with no authentication",
+                addr
+            );
+        }
+
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("Metrics listener stopped: {}", e);
+            }
+        });
+
+        Ok(addr)
+    }
+}
+
+async fn render(State(handle): State<PrometheusHandle>) -> String {
+    // Histograms are only drained into the output during upkeep
+    handle.run_upkeep();
+    handle.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_serve_metrics() {
+        let recorder = builder().unwrap().build_recorder();
+        let handle = recorder.handle();
+
+        metrics::with_local_recorder(&recorder, || {
+            describe();
+            metrics::counter!(HOOK_MESSAGES, "event" => "Stop").increment(2);
+            metrics::histogram!(PROVISIONING_DURATION).record(42.0);
+
+            let mut tracker = IssueTracker::new();
+            tracker.track(1, PlebState::Working);
+            tracker.track(2, PlebState::Working);
+            tracker.track(3, PlebState::Waiting);
+            record_issue_states("acme/api", &tracker);
+        });
+
+        let server = MetricsServer::new("127.0.0.1:0", handle);
+        let addr = server.start().await.unwrap();
+        let body = reqwest::get(format!("http://{}/metrics", addr))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert!(body.contains("pleb_hook_messages_total{event=\"Stop\"} 2"));
+        assert!(body.contains("pleb_issues{repo=\"acme/api\",state=\"working\"} 2"));
+        assert!(body.contains("pleb_issues{repo=\"acme/api\",state=\"done\"} 0"));
+        assert!(body.contains("pleb_provisioning_duration_seconds_bucket{le=\"60\"} 1"));
+    }
+}
@@ -36,6 +36,54 @@ impl IssueContext {
     }
 }
 
+/// A single issue comment, as seen by the comment template
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentContext {
+    pub author: String,
+    pub body: String,
+    pub html_url: String,
+}
+
+/// Context data for rendering new issue comments delivered to a session
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentsContext {
+    pub issue_number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub comments: Vec<CommentContext>,
+}
+
+/// Time spent in one state, as seen by the status comment template
+#[derive(Debug, Clone, Serialize)]
+pub struct StateTime {
+    pub state: String,
+    pub duration: String,
+}
+
+/// Context data for rendering the status comment pleb keeps on an issue
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusContext {
+    pub issue_number: u64,
+    pub title: String,
+    pub state: String,
+    pub branch_name: Option<String>,
+    /// Host the worktree (and the session) lives on
+    pub worktree_host: String,
+    pub worktree_path: Option<String>,
+    pub pr_url: Option<String>,
+    pub time_in_state: Vec<StateTime>,
+    pub stop_reason: Option<String>,
+    /// Tokens used so far, when `costs.in_status_comment` is on
+    pub tokens: Option<String>,
+    pub cost: Option<String>,
+}
+
+/// Comment template used when the prompts directory doesn't provide one
+const DEFAULT_COMMENT_TEMPLATE: &str = include_str!("../prompts/comment.md");
+
+/// Status comment template used when the prompts directory doesn't provide one
+const DEFAULT_STATUS_COMMENT_TEMPLATE: &str = include_str!("../prompts/status_comment.md");
+
 /// Template engine for rendering prompts with issue context
 #[allow(dead_code)]
 pub struct TemplateEngine {
@@ -78,6 +126,56 @@ impl TemplateEngine {
         Ok(())
     }
 
+    /// Load a template from the templates directory, or register `default` under
+    /// the same name if the file doesn't exist
+    pub fn load_template_or(&mut self, name: &str, default: &str) -> Result<()> {
+        if self.templates_dir.join(name).exists() {
+            return self.load_template(name);
+        }
+
+        self.handlebars
+            .register_template_string(name, default)
+            .with_context(|| format!("Failed to register bundled template '{}'", name))?;
+
+        tracing::debug!("Using bundled template for '{}'", name);
+
+        Ok(())
+    }
+
+    /// Load the comment template, falling back to the bundled one
+    pub fn load_comment_template(&mut self, name: &str) -> Result<()> {
+        self.load_template_or(name, DEFAULT_COMMENT_TEMPLATE)
+    }
+
+    /// Load the status comment template, falling back to the bundled one
+    pub fn load_status_comment_template(&mut self, name: &str) -> Result<()> {
+        self.load_template_or(name, DEFAULT_STATUS_COMMENT_TEMPLATE)
+    }
+
+    /// Render a registered template with an issue's status
+    pub fn render_status(&self, template_name: &str, context: &StatusContext) -> Result<String> {
+        self.handlebars
+            .render(template_name, context)
+            .with_context(|| {
+                format!(
+                    "Failed to render template '{}' with status of issue #{}",
+                    template_name, context.issue_number
+                )
+            })
+    }
+
+    /// Render a registered template with new comments on an issue
+    pub fn render_comments(&self, template_name: &str, context: &CommentsContext) -> Result<String> {
+        self.handlebars
+            .render(template_name, context)
+            .with_context(|| {
+                format!(
+                    "Failed to render template '{}' with comments on issue #{}",
+                    template_name, context.issue_number
+                )
+            })
+    }
+
     /// Render a registered template with the given issue context
     #[allow(dead_code)]
     pub fn render(&self, template_name: &str, context: &IssueContext) -> Result<String> {
@@ -118,6 +216,9 @@ mod tests {
             labels: vec!["pleb:ready".to_string()],
             state: IssueState::Open,
             html_url: format!("https://github.com/owner/repo/issues/{}", number),
+            author: "someone".to_string(),
+            created_at: 0,
+            milestone_due_on: None,
         }
     }
 
@@ -185,6 +286,8 @@ mod tests {
         let config = crate::config::PromptsConfig {
             dir: PathBuf::from("/tmp"),
             new_issue: "test.md".to_string(),
+            comment: "comment.md".to_string(),
+            status_comment: "status_comment.md".to_string(),
         };
         let engine = TemplateEngine::new(&config).expect("Should create engine");
 
@@ -223,6 +326,8 @@ mod tests {
         let config = crate::config::PromptsConfig {
             dir: PathBuf::from("/tmp"),
             new_issue: "test.md".to_string(),
+            comment: "comment.md".to_string(),
+            status_comment: "status_comment.md".to_string(),
         };
         let engine = TemplateEngine::new(&config).expect("Should create engine");
 
@@ -240,6 +345,8 @@ mod tests {
         let config = crate::config::PromptsConfig {
             dir: PathBuf::from("/tmp"),
             new_issue: "test.md".to_string(),
+            comment: "comment.md".to_string(),
+            status_comment: "status_comment.md".to_string(),
         };
         let engine = TemplateEngine::new(&config).expect("Should create engine");
 
@@ -251,4 +358,35 @@ mod tests {
         let result = engine.render_string(cmd, &ctx);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_render_comments_with_bundled_template() {
+        let config = crate::config::PromptsConfig {
+            dir: PathBuf::from("/nonexistent/prompts"),
+            new_issue: "test.md".to_string(),
+            comment: "comment.md".to_string(),
+            status_comment: "status_comment.md".to_string(),
+        };
+        let mut engine = TemplateEngine::new(&config).expect("Should create engine");
+        engine
+            .load_comment_template(&config.comment)
+            .expect("Should fall back to the bundled template");
+
+        let ctx = CommentsContext {
+            issue_number: 42,
+            title: "Add invoices table".to_string(),
+            html_url: "https://github.com/acme/api/issues/42".to_string(),
+            comments: vec![CommentContext {
+                author: "alice".to_string(),
+                body: "Store amounts as integer cents, not `f64` & friends.".to_string(),
+                html_url: "https://github.com/acme/api/issues/42#issuecomment-1".to_string(),
+            }],
+        };
+
+        let rendered = engine.render_comments(&config.comment, &ctx).expect("Should render");
+        assert!(rendered.contains("#42"));
+        assert!(rendered.contains("@alice"));
+        // Comment bodies are passed through unescaped
+        assert!(rendered.contains("not `f64` & friends."));
+    }
 }
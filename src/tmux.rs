@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use std::path::Path;
 use tokio::process::Command;
 
 use crate::config::TmuxConfig;
+use crate::github::IssueRef;
+use crate::sandbox::shell_quote;
+use crate::session::{self, SessionBackend, WindowTarget};
 
+/// tmux: each session window is a tmux window in the configured session
 pub struct TmuxManager {
     session_name: String,
     /// Environment variables to pass to tmux sessions (name -> value)
@@ -18,19 +23,90 @@ impl TmuxManager {
         }
     }
 
-    /// Add an environment variable to be passed to tmux sessions
-    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.env_vars.push((name.into(), value.into()));
+    /// Add environment variables to be passed to tmux sessions
+    pub fn with_envs(mut self, env: Vec<(String, String)>) -> Self {
+        self.env_vars.extend(env);
         self
     }
 
-    /// Get the session name
-    pub fn session_name(&self) -> &str {
-        &self.session_name
+    /// Get the next available window index in the session
+    async fn next_available_window_index(&self) -> Result<u32> {
+        let output = Command::new("tmux")
+            .args([
+                "list-windows",
+                "-t",
+                &self.session_name,
+                "-F",
+                "#{window_index}",
+            ])
+            .output()
+            .await
+            .context("Failed to list tmux windows")?;
+
+        if !output.status.success() {
+            // Session doesn't exist yet, start at index 0
+            return Ok(0);
+        }
+
+        let windows_output = String::from_utf8_lossy(&output.stdout);
+        let mut indices: Vec<u32> = windows_output
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .collect();
+
+        if indices.is_empty() {
+            return Ok(0);
+        }
+
+        indices.sort_unstable();
+
+        // Find the first gap or return max + 1
+        for (i, &index) in indices.iter().enumerate() {
+            if index != i as u32 {
+                return Ok(i as u32);
+            }
+        }
+
+        Ok(indices.len() as u32)
+    }
+
+    /// Find the window for an issue, returning its window id and name
+    async fn find_window(&self, issue: &dyn WindowTarget) -> Result<Option<(String, String)>> {
+        let output = Command::new("tmux")
+            .args([
+                "list-windows",
+                "-t",
+                &self.session_name,
+                "-F",
+                "#{window_id} #{window_name}",
+            ])
+            .output()
+            .await
+            .context("Failed to list tmux windows")?;
+
+        if !output.status.success() {
+            // Session might not exist yet
+            return Ok(None);
+        }
+
+        let windows_output = String::from_utf8_lossy(&output.stdout);
+        for line in windows_output.lines() {
+            let Some((id, name)) = line.split_once(' ') else {
+                continue;
+            };
+            if issue.matches_window(name) {
+                return Ok(Some((id.to_string(), name.to_string())));
+            }
+        }
+
+        Ok(None)
     }
+}
 
+#[async_trait]
+impl SessionBackend for TmuxManager {
     /// Ensure the pleb session exists, create if not
-    pub async fn ensure_session(&self) -> Result<()> {
+    async fn ensure_session(&self) -> Result<()> {
         // Check if session exists
         let status = Command::new("tmux")
             .args(["has-session", "-t", &self.session_name])
@@ -80,67 +156,23 @@ impl TmuxManager {
         Ok(())
     }
 
-    /// Get the next available window index in the session
-    async fn next_available_window_index(&self) -> Result<u32> {
-        let output = Command::new("tmux")
-            .args([
-                "list-windows",
-                "-t",
-                &self.session_name,
-                "-F",
-                "#{window_index}",
-            ])
-            .output()
-            .await
-            .context("Failed to list tmux windows")?;
-
-        if !output.status.success() {
-            // Session doesn't exist yet, start at index 0
-            return Ok(0);
-        }
-
-        let windows_output = String::from_utf8_lossy(&output.stdout);
-        let mut indices: Vec<u32> = windows_output
-            .lines()
-            .filter_map(|line| line.parse().ok())
-            .collect();
-
-        if indices.is_empty() {
-            return Ok(0);
-        }
-
-        indices.sort_unstable();
-
-        // Find the first gap or return max + 1
-        for (i, &index) in indices.iter().enumerate() {
-            if index != i as u32 {
-                return Ok(i as u32);
-            }
-        }
-
-        Ok(indices.len() as u32)
-    }
-
     /// Create a new window for an issue in the pleb session
-    /// Window name: "{branch_name}" (e.g., "2592-add-invoices-table_acron_pleb")
+    /// Window name: "{owner}/{repo}/{branch_name}" (e.g., "acme/api/2592-add-invoices-table_acron_pleb")
     /// Working directory: the worktree path
-    #[allow(dead_code)]
-    pub async fn create_window(&self, branch_name: &str, working_dir: &Path) -> Result<()> {
+    async fn create_window(
+        &self,
+        issue: &dyn WindowTarget,
+        branch_name: &str,
+        working_dir: &Path,
+    ) -> Result<()> {
         // Ensure session exists first
         self.ensure_session().await?;
 
-        let window_name = branch_name.to_string();
-
-        // Extract issue number from branch name (first part before '-')
-        let issue_number = branch_name
-            .split('-')
-            .next()
-            .and_then(|s| s.parse::<u64>().ok())
-            .with_context(|| format!("Failed to extract issue number from branch name: {}", branch_name))?;
+        let window_name = issue.window_name(branch_name);
 
         // Check if window already exists
-        if self.window_exists(issue_number).await? {
-            tracing::info!("Window for issue #{} already exists", issue_number);
+        if self.window_exists(issue).await? {
+            tracing::info!("Window for issue {} already exists", issue);
             return Ok(());
         }
 
@@ -174,10 +206,10 @@ impl TmuxManager {
 
             // If window creation failed due to index being in use, but our window exists,
             // this is a race condition or manual window creation - just continue
-            if stderr.contains("in use") && self.window_exists(issue_number).await? {
+            if stderr.contains("in use") && self.window_exists(issue).await? {
                 tracing::debug!(
-                    "Window creation failed with 'in use' but window #{} exists, continuing",
-                    issue_number
+                    "Window creation failed with 'in use' but window for {} exists, continuing",
+                    issue
                 );
                 return Ok(());
             }
@@ -188,12 +220,11 @@ impl TmuxManager {
         Ok(())
     }
 
-    /// Check if a window exists for an issue
-    /// Searches for windows with names starting with "{issue_number}-"
-    #[allow(dead_code)]
-    pub async fn window_exists(&self, issue_number: u64) -> Result<bool> {
-        let window_prefix = format!("{}-", issue_number);
+    async fn window_exists(&self, issue: &dyn WindowTarget) -> Result<bool> {
+        Ok(self.find_window(issue).await?.is_some())
+    }
 
+    async fn list_windows(&self) -> Result<Vec<IssueRef>> {
         let output = Command::new("tmux")
             .args([
                 "list-windows",
@@ -207,238 +238,168 @@ impl TmuxManager {
             .context("Failed to list tmux windows")?;
 
         if !output.status.success() {
-            // Session might not exist yet
-            return Ok(false);
+            // Session doesn't exist, return empty list
+            return Ok(Vec::new());
         }
 
         let windows_output = String::from_utf8_lossy(&output.stdout);
-        // Strip any state suffix (e.g., ":waiting") before checking prefix
-        Ok(windows_output.lines().any(|line| {
-            let base_name = line.split(':').next().unwrap_or(line);
-            base_name.starts_with(&window_prefix)
-        }))
+        Ok(windows_output.lines().filter_map(session::parse_window_name).collect())
     }
 
-    /// List all issue windows in the session
-    /// Returns issue numbers extracted from branch-name windows
-    pub async fn list_windows(&self) -> Result<Vec<u64>> {
-        let output = Command::new("tmux")
-            .args([
-                "list-windows",
-                "-t",
-                &self.session_name,
-                "-F",
-                "#{window_name}",
-            ])
-            .output()
+    async fn kill_window(&self, issue: &dyn WindowTarget) -> Result<()> {
+        let Some((id, name)) = self.find_window(issue).await? else {
+            tracing::warn!("No window found for issue {}", issue);
+            return Ok(());
+        };
+
+        tracing::info!("Killing tmux window: {}", name);
+        let status = Command::new("tmux")
+            .args(["kill-window", "-t", &id])
+            .status()
             .await
-            .context("Failed to list tmux windows")?;
+            .context("Failed to kill tmux window")?;
 
-        if !output.status.success() {
-            // Session doesn't exist, return empty list
-            return Ok(Vec::new());
+        if !status.success() {
+            tracing::warn!("Window {} may not exist or was already killed", name);
         }
+        Ok(())
+    }
 
-        let windows_output = String::from_utf8_lossy(&output.stdout);
-        let mut issue_numbers = Vec::new();
+    /// Send keys to a window (for starting Claude, etc.)
+    /// Sends keys to pane 0 by default
+    async fn send_keys(&self, issue: &dyn WindowTarget, keys: &str) -> Result<()> {
+        let (id, _) = self
+            .find_window(issue)
+            .await?
+            .with_context(|| format!("No window found for issue {}", issue))?;
+
+        // Target pane 0 explicitly to ensure Claude runs in the left pane
+        let target = format!("{}.0", id);
+        tracing::debug!("Sending keys to {}: {}", target, keys);
+        let status = Command::new("tmux")
+            .args(["send-keys", "-t", &target, keys, "Enter"])
+            .status()
+            .await
+            .context("Failed to execute tmux send-keys command")?;
 
-        for line in windows_output.lines() {
-            // Strip state suffix if present (e.g., "2592-branch:waiting" -> "2592-branch")
-            let base_name = line.split(':').next().unwrap_or(line);
-
-            // Extract issue number from branch name (first part before '-')
-            if let Some(first_part) = base_name.split('-').next() {
-                if let Ok(number) = first_part.parse::<u64>() {
-                    issue_numbers.push(number);
-                }
-            }
+        if !status.success() {
+            anyhow::bail!("tmux send-keys command failed for target '{}': {}", target, status);
         }
-
-        Ok(issue_numbers)
+        Ok(())
     }
 
-    /// Kill a window for an issue
-    /// Finds the window by searching for names starting with "{issue_number}-"
-    #[allow(dead_code)]
-    pub async fn kill_window(&self, issue_number: u64) -> Result<()> {
-        // Find the window name by listing windows
-        let output = Command::new("tmux")
-            .args([
-                "list-windows",
-                "-t",
-                &self.session_name,
-                "-F",
-                "#{window_name}",
-            ])
-            .output()
-            .await
-            .context("Failed to list tmux windows")?;
-
-        if !output.status.success() {
-            tracing::warn!("Session {} doesn't exist, nothing to kill", self.session_name);
+    async fn rename_window(&self, issue: &dyn WindowTarget, state: &str) -> Result<()> {
+        let Some((id, name)) = self.find_window(issue).await? else {
+            tracing::warn!("No window found for issue {} to rename", issue);
             return Ok(());
-        }
+        };
 
-        let windows_output = String::from_utf8_lossy(&output.stdout);
-        let window_prefix = format!("{}-", issue_number);
+        let new_name = session::state_window_name(&name, state);
 
-        // Find the window matching this issue
-        for line in windows_output.lines() {
-            let base_name = line.split(':').next().unwrap_or(line);
-            if base_name.starts_with(&window_prefix) {
-                let target = format!("{}:{}", self.session_name, line);
-                tracing::info!("Killing tmux window: {}", target);
-                let status = Command::new("tmux")
-                    .args(["kill-window", "-t", &target])
-                    .status()
-                    .await
-                    .context("Failed to kill tmux window")?;
-
-                if !status.success() {
-                    tracing::warn!("Window {} may not exist or was already killed", target);
-                }
-                return Ok(());
-            }
-        }
+        tracing::debug!("Renaming window {} to {}", name, new_name);
+        let status = Command::new("tmux")
+            .args(["rename-window", "-t", &id, &new_name])
+            .status()
+            .await
+            .context("Failed to rename tmux window")?;
 
-        tracing::warn!("No window found for issue #{}", issue_number);
+        if !status.success() {
+            tracing::warn!("Failed to rename window to {}", new_name);
+        }
         Ok(())
     }
 
-    /// Send keys to a window (for starting Claude, etc.)
-    /// Finds the window by searching for names starting with "{issue_number}-"
-    /// Sends keys to pane 0 by default
-    pub async fn send_keys(&self, issue_number: u64, keys: &str) -> Result<()> {
-        // Find the window name by listing windows
-        let output = Command::new("tmux")
-            .args([
-                "list-windows",
-                "-t",
-                &self.session_name,
-                "-F",
-                "#{window_name}",
-            ])
-            .output()
-            .await
-            .context("Failed to list tmux windows")?;
+    async fn select_pane(&self, issue: &dyn WindowTarget, pane_index: u32) -> Result<()> {
+        let (id, _) = self
+            .find_window(issue)
+            .await?
+            .with_context(|| format!("No window found for issue {} to select pane", issue))?;
 
-        let windows_output = String::from_utf8_lossy(&output.stdout);
-        let window_prefix = format!("{}-", issue_number);
+        let target = format!("{}.{}", id, pane_index);
 
-        // Find the window matching this issue
-        for line in windows_output.lines() {
-            let base_name = line.split(':').next().unwrap_or(line);
-            if base_name.starts_with(&window_prefix) {
-                // Target pane 0 explicitly to ensure Claude runs in the left pane
-                let target = format!("{}:{}.0", self.session_name, base_name);
-                tracing::debug!("Sending keys to {}: {}", target, keys);
-                let status = Command::new("tmux")
-                    .args(["send-keys", "-t", &target, keys, "Enter"])
-                    .status()
-                    .await
-                    .context("Failed to execute tmux send-keys command")?;
-
-                if !status.success() {
-                    anyhow::bail!("tmux send-keys command failed for target '{}': {}", target, status);
-                }
-                return Ok(());
-            }
-        }
+        tracing::debug!("Selecting pane {}", target);
+        let status = Command::new("tmux")
+            .args(["select-pane", "-t", &target])
+            .status()
+            .await
+            .context("Failed to execute tmux select-pane command")?;
 
-        anyhow::bail!("No window found for issue #{}", issue_number)
+        if !status.success() {
+            anyhow::bail!("tmux select-pane command failed for target '{}': {}", target, status);
+        }
+        Ok(())
     }
 
-    /// Rename a window to include state indicator (e.g., "2592-branch:waiting")
-    /// Finds the window by searching for names starting with "{issue_number}-"
-    pub async fn rename_window(&self, issue_number: u64, state: &str) -> Result<()> {
-        // Find the window name by listing windows
+    async fn capture_pane(&self, issue: &dyn WindowTarget, lines: u32) -> Result<Option<String>> {
+        let Some((id, _)) = self.find_window(issue).await? else {
+            return Ok(None);
+        };
+
+        let target = format!("{}.0", id);
+        let start = format!("-{}", lines);
         let output = Command::new("tmux")
-            .args([
-                "list-windows",
-                "-t",
-                &self.session_name,
-                "-F",
-                "#{window_name}",
-            ])
+            .args(["capture-pane", "-p", "-J", "-t", &target, "-S", &start])
             .output()
             .await
-            .context("Failed to list tmux windows")?;
+            .context("Failed to execute tmux capture-pane command")?;
 
-        let windows_output = String::from_utf8_lossy(&output.stdout);
-        let window_prefix = format!("{}-", issue_number);
-
-        // Find the window matching this issue
-        for line in windows_output.lines() {
-            let base_name = line.split(':').next().unwrap_or(line);
-            if base_name.starts_with(&window_prefix) {
-                let target = format!("{}:{}", self.session_name, line);
-                let new_name = format!("{}:{}", base_name, state);
-
-                tracing::debug!("Renaming window {} to {}", target, new_name);
-                let status = Command::new("tmux")
-                    .args(["rename-window", "-t", &target, &new_name])
-                    .status()
-                    .await
-                    .context("Failed to rename tmux window")?;
-
-                if !status.success() {
-                    tracing::warn!("Failed to rename window to {}", new_name);
-                }
-                return Ok(());
-            }
+        if !output.status.success() {
+            anyhow::bail!(
+                "tmux capture-pane command failed for target '{}': {}",
+                target,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
         }
 
-        tracing::warn!("No window found for issue #{} to rename", issue_number);
-        Ok(())
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(Some(text.trim_end().to_string()))
     }
 
-    /// Select a specific pane in a window (e.g., pane 0 after on_provision hooks)
-    /// Finds the window by searching for names starting with "{issue_number}-"
-    pub async fn select_pane(&self, issue_number: u64, pane_index: u32) -> Result<()> {
-        // Find the window name by listing windows
+    async fn log_output(&self, issue: &dyn WindowTarget, log_file: &Path) -> Result<()> {
+        let (id, _) = self
+            .find_window(issue)
+            .await?
+            .with_context(|| format!("No window found for issue {}", issue))?;
+
+        // -o only opens a pipe if the pane doesn't have one yet
+        let target = format!("{}.0", id);
+        let command = format!("cat >> {}", shell_quote(&log_file.to_string_lossy()));
         let output = Command::new("tmux")
-            .args([
-                "list-windows",
-                "-t",
-                &self.session_name,
-                "-F",
-                "#{window_name}",
-            ])
+            .args(["pipe-pane", "-o", "-t", &target, &command])
             .output()
             .await
-            .context("Failed to list tmux windows")?;
+            .context("Failed to execute tmux pipe-pane command")?;
 
-        let windows_output = String::from_utf8_lossy(&output.stdout);
-        let window_prefix = format!("{}-", issue_number);
-
-        // Find the window matching this issue
-        for line in windows_output.lines() {
-            let base_name = line.split(':').next().unwrap_or(line);
-            if base_name.starts_with(&window_prefix) {
-                let target = format!("{}:{}.{}", self.session_name, base_name, pane_index);
-
-                tracing::debug!("Selecting pane {}", target);
-                let status = Command::new("tmux")
-                    .args(["select-pane", "-t", &target])
-                    .status()
-                    .await
-                    .context("Failed to execute tmux select-pane command")?;
-
-                if !status.success() {
-                    anyhow::bail!("tmux select-pane command failed for target '{}': {}", target, status);
-                }
-                return Ok(());
-            }
+        if !output.status.success() {
+            anyhow::bail!(
+                "tmux pipe-pane command failed for target '{}': {}",
+                target,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
         }
-
-        anyhow::bail!("No window found for issue #{} to select pane", issue_number)
+        tracing::debug!("Logging window for issue {} to {}", issue, log_file.display());
+        Ok(())
     }
 
-    /// Attach to the pleb session (blocking - replaces current terminal)
-    /// This returns a std::process::Command that the caller can exec() or status()
-    pub fn attach_command(&self) -> std::process::Command {
+    fn attach_command(&self) -> std::process::Command {
         let mut cmd = std::process::Command::new("tmux");
         cmd.args(["attach", "-t", &self.session_name]);
         cmd
     }
+
+    /// Switches to the window when already inside tmux, otherwise attaches to
+    /// the session with that window selected
+    async fn attach_window_command(&self, issue: &dyn WindowTarget) -> Result<Option<std::process::Command>> {
+        let Some((id, _)) = self.find_window(issue).await? else {
+            return Ok(None);
+        };
+
+        let mut cmd = std::process::Command::new("tmux");
+        if std::env::var_os("TMUX").is_some() {
+            cmd.args(["switch-client", "-t", &id]);
+        } else {
+            cmd.args(["select-window", "-t", &id, ";", "attach", "-t", &self.session_name]);
+        }
+        Ok(Some(cmd))
+    }
 }
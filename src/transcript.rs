@@ -0,0 +1,365 @@
+//! Session transcripts kept in each issue's directory.
+//!
+//! Claude Code writes its own transcript (JSONL, one message per line) and
+//! names it in every hook payload. pleb links it into the issue directory as
+//! `transcript.jsonl` when the session stops and replaces the link with a copy
+//! when the issue finishes, so the conversation survives Claude cleaning up its
+//! files. Headless runs write the same file themselves (stream-json events use
+//! the same message shapes). Interactive windows are also logged raw to
+//! `session.log` where the multiplexer supports it.
+//!
+//! `pleb transcript` renders either file for review.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Transcript in the issue directory, one JSON message or event per line
+pub const TRANSCRIPT_FILE: &str = "transcript.jsonl";
+
+/// Raw terminal output of the issue's window, in the issue directory
+pub const RAW_LOG_FILE: &str = "session.log";
+
+/// Lines of each tool result shown in the plain text rendering
+const RESULT_LINES: usize = 10;
+
+/// Longest tool input summary shown in the plain text rendering
+const INPUT_CHARS: usize = 120;
+
+/// Point the issue's transcript at the agent's own transcript file. An
+/// archived copy, or a transcript written by a headless run, is left alone.
+pub fn link(issue_dir: &Path, source: &Path) -> Result<()> {
+    let dest = issue_dir.join(TRANSCRIPT_FILE);
+    match std::fs::symlink_metadata(&dest) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            if std::fs::read_link(&dest).is_ok_and(|target| target == source) {
+                return Ok(());
+            }
+            std::fs::remove_file(&dest)
+                .with_context(|| format!("Failed to remove {}", dest.display()))?;
+        }
+        Ok(_) => {
+            tracing::debug!("Keeping {}, which isn't a link", dest.display());
+            return Ok(());
+        }
+        Err(_) => {}
+    }
+
+    std::fs::create_dir_all(issue_dir)
+        .with_context(|| format!("Failed to create issue directory: {}", issue_dir.display()))?;
+    std::os::unix::fs::symlink(source, &dest)
+        .with_context(|| format!("Failed to link {} to {}", dest.display(), source.display()))?;
+    tracing::debug!("Linked {} to {}", dest.display(), source.display());
+    Ok(())
+}
+
+/// Replace the issue's transcript link with a copy of `source`
+pub fn archive(issue_dir: &Path, source: &Path) -> Result<()> {
+    if !source.exists() {
+        tracing::warn!("Transcript {} is gone, nothing to archive", source.display());
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(issue_dir)
+        .with_context(|| format!("Failed to create issue directory: {}", issue_dir.display()))?;
+    let dest = issue_dir.join(TRANSCRIPT_FILE);
+    let partial = issue_dir.join(format!("{}.partial", TRANSCRIPT_FILE));
+    std::fs::copy(source, &partial)
+        .with_context(|| format!("Failed to copy {}", source.display()))?;
+    // Renaming over the link replaces the link, not its target
+    std::fs::rename(&partial, &dest)
+        .with_context(|| format!("Failed to write {}", dest.display()))?;
+    tracing::info!("Archived transcript {} to {}", source.display(), dest.display());
+    Ok(())
+}
+
+/// How `pleb transcript` prints a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Markdown,
+}
+
+/// One step of a conversation
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Message { role: Role, text: String },
+    ToolUse { name: String, input: Value },
+    ToolResult { text: String, is_error: bool },
+    /// The end of a headless run
+    RunResult { summary: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    User,
+    Assistant,
+}
+
+impl Role {
+    fn name(self) -> &'static str {
+        match self {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+        }
+    }
+}
+
+/// Render a JSONL transcript. Lines that aren't messages (summaries, hook
+/// output, malformed lines) are skipped.
+pub fn render(jsonl: &str, format: Format) -> String {
+    let entries: Vec<Entry> = jsonl
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .flat_map(|value| entries(&value))
+        .collect();
+
+    let mut out = String::new();
+    let mut role = None;
+    for entry in &entries {
+        if let Entry::Message { role: next, .. } = entry {
+            if role != Some(*next) {
+                role = Some(*next);
+                match format {
+                    Format::Text => {
+                        let _ = writeln!(out, "── {} ──", next.name());
+                    }
+                    Format::Markdown => {
+                        let _ = writeln!(out, "## {}\n", next.name());
+                    }
+                }
+            }
+        }
+        match format {
+            Format::Text => render_text(&mut out, entry),
+            Format::Markdown => render_markdown(&mut out, entry),
+        }
+    }
+    out
+}
+
+/// The entries in one transcript line
+fn entries(value: &Value) -> Vec<Entry> {
+    let role = match value["type"].as_str() {
+        Some("user") => Role::User,
+        Some("assistant") => Role::Assistant,
+        Some("result") => {
+            let mut summary = value["subtype"].as_str().unwrap_or("finished").to_string();
+            if let Some(turns) = value["num_turns"].as_u64() {
+                summary.push_str(&format!(", {} turns", turns));
+            }
+            if let Some(cost) = value["total_cost_usd"].as_f64() {
+                summary.push_str(&format!(", ${:.2}", cost));
+            }
+            return vec![Entry::RunResult { summary }];
+        }
+        _ => return Vec::new(),
+    };
+    // Claude's own bookkeeping (command caveats and the like)
+    if value["isMeta"].as_bool() == Some(true) {
+        return Vec::new();
+    }
+
+    let content = &value["message"]["content"];
+    if let Some(text) = content.as_str() {
+        return vec![Entry::Message {
+            role,
+            text: text.trim().to_string(),
+        }];
+    }
+
+    content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|block| match block["type"].as_str()? {
+            "text" => Some(Entry::Message {
+                role,
+                text: block["text"].as_str()?.trim().to_string(),
+            }),
+            "tool_use" => Some(Entry::ToolUse {
+                name: block["name"].as_str()?.to_string(),
+                input: block["input"].clone(),
+            }),
+            "tool_result" => Some(Entry::ToolResult {
+                text: result_text(&block["content"]),
+                is_error: block["is_error"].as_bool() == Some(true),
+            }),
+            _ => None,
+        })
+        .filter(|entry| !matches!(entry, Entry::Message { text, .. } if text.is_empty()))
+        .collect()
+}
+
+/// Text of a tool result, which is a string or a list of content blocks
+fn result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| match block["type"].as_str() {
+                Some("text") => block["text"].as_str().map(str::to_string),
+                Some(other) => Some(format!("[{}]", other)),
+                None => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// One line describing a tool call: its main argument, or the whole input
+fn input_summary(input: &Value) -> String {
+    let summary = ["command", "file_path", "path", "pattern", "url", "description", "prompt"]
+        .iter()
+        .find_map(|key| input[key].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| input.to_string());
+    let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+    match summary.char_indices().nth(INPUT_CHARS) {
+        Some((end, _)) => format!("{}…", &summary[..end]),
+        None => summary,
+    }
+}
+
+fn render_text(out: &mut String, entry: &Entry) {
+    match entry {
+        Entry::Message { text, .. } => {
+            let _ = writeln!(out, "{}\n", text);
+        }
+        Entry::ToolUse { name, input } => {
+            let _ = writeln!(out, "  > {}: {}", name, input_summary(input));
+        }
+        Entry::ToolResult { text, is_error } => {
+            let lines: Vec<&str> = text.lines().collect();
+            let marker = if *is_error { "  ! " } else { "  < " };
+            for (i, line) in lines.iter().take(RESULT_LINES).enumerate() {
+                let _ = writeln!(out, "{}{}", if i == 0 { marker } else { "    " }, line);
+            }
+            if lines.len() > RESULT_LINES {
+                let _ = writeln!(out, "    … ({} more lines)", lines.len() - RESULT_LINES);
+            }
+            out.push('\n');
+        }
+        Entry::RunResult { summary } => {
+            let _ = writeln!(out, "── Run ended: {} ──\n", summary);
+        }
+    }
+}
+
+fn render_markdown(out: &mut String, entry: &Entry) {
+    match entry {
+        Entry::Message { text, .. } => {
+            let _ = writeln!(out, "{}\n", text);
+        }
+        Entry::ToolUse { name, input } => {
+            let input = serde_json::to_string_pretty(input).unwrap_or_default();
+            let _ = writeln!(out, "**Tool: {}**\n\n{}\n", name, fenced("json", &input));
+        }
+        Entry::ToolResult { text, is_error } => {
+            let summary = if *is_error { "Error" } else { "Output" };
+            let _ = writeln!(
+                out,
+                "<details><summary>{}</summary>\n\n{}\n\n</details>\n",
+                summary,
+                fenced("", text)
+            );
+        }
+        Entry::RunResult { summary } => {
+            let _ = writeln!(out, "---\n\n*Run ended: {}*\n", summary);
+        }
+    }
+}
+
+/// A fenced code block long enough not to be closed by backticks in `text`
+fn fenced(lang: &str, text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, lang, text.trim_end(), fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = r#"{"type":"summary","summary":"Fix login"}
+{"type":"user","isMeta":true,"message":{"role":"user","content":"<local-command-caveat>"}}
+{"type":"user","message":{"role":"user","content":"Fix the login bug"}}
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"thinking","thinking":"hmm"},{"type":"text","text":"Let me look."}]}}
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"grep -rn login src","description":"Find login"}}]}}
+{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"src/a.rs:1\nsrc/b.rs:2"}]}}
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Fixed it."}]}}
+not json
+{"type":"result","subtype":"success","num_turns":3,"total_cost_usd":0.1234}
+"#;
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render(TRANSCRIPT, Format::Text),
+            "── User ──\nFix the login bug\n\n\
+             ── Assistant ──\nLet me look.\n\n\
+             \x20 > Bash: grep -rn login src\n\
+             \x20 < src/a.rs:1\n    src/b.rs:2\n\n\
+             Fixed it.\n\n\
+             ── Run ended: success, 3 turns, $0.12 ──\n\n"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let markdown = render(TRANSCRIPT, Format::Markdown);
+        assert!(markdown.starts_with("## User\n\nFix the login bug\n\n## Assistant\n\nLet me look.\n"));
+        assert!(markdown.contains("**Tool: Bash**\n\n```json\n{\n  \"command\": \"grep -rn login src\","));
+        assert!(markdown.contains("<details><summary>Output</summary>\n\n```\nsrc/a.rs:1\nsrc/b.rs:2\n```"));
+        assert!(markdown.contains("*Run ended: success, 3 turns, $0.12*"));
+        assert_eq!(markdown.matches("## Assistant").count(), 1);
+    }
+
+    #[test]
+    fn test_long_results_and_fences() {
+        let output: Vec<String> = (1..=15).map(|i| format!("line {}", i)).collect();
+        let mut out = String::new();
+        render_text(
+            &mut out,
+            &Entry::ToolResult {
+                text: output.join("\n"),
+                is_error: true,
+            },
+        );
+        assert!(out.starts_with("  ! line 1\n    line 2\n"));
+        assert!(out.ends_with("    line 10\n    … (5 more lines)\n\n"));
+
+        assert_eq!(fenced("", "a ``` b"), "````\na ``` b\n````");
+    }
+
+    #[test]
+    fn test_link_then_archive() {
+        let dir = std::env::temp_dir().join(format!("pleb-transcript-test-{}", std::process::id()));
+        let issue_dir = dir.join("12");
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("session.jsonl");
+        std::fs::write(&source, "{}\n").unwrap();
+
+        link(&issue_dir, &source).unwrap();
+        let dest = issue_dir.join(TRANSCRIPT_FILE);
+        assert_eq!(std::fs::read_link(&dest).unwrap(), source);
+        link(&issue_dir, &source).unwrap();
+
+        std::fs::write(&source, "{}\n{}\n").unwrap();
+        archive(&issue_dir, &source).unwrap();
+        assert!(!std::fs::symlink_metadata(&dest).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "{}\n{}\n");
+
+        // The archived copy is kept when the session stops again
+        link(&issue_dir, &source).unwrap();
+        assert!(!std::fs::symlink_metadata(&dest).unwrap().file_type().is_symlink());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
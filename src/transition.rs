@@ -0,0 +1,151 @@
+//! Single entry point for pleb state transitions.
+//!
+//! Every path that changes an issue's state (provisioning, hook messages,
+//! merged PR detection and `pleb transition`) goes through `TransitionEngine`,
+//! which validates the move against `PlebState::valid_transitions`, updates the
+//! GitHub labels and mirrors the new state into the local state file. When
+//! enabled, it also refreshes the issue's status comment.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::config::LabelConfig;
+use crate::github::{GitHubClient, Issue};
+use crate::state::{self, IssueTracker, PlebState};
+use crate::status_comment::StatusCommenter;
+use crate::transcript;
+
+pub struct TransitionEngine<'a> {
+    github: &'a GitHubClient,
+    labels: &'a LabelConfig,
+    state_file: PathBuf,
+    status_comment: Option<StatusCommenter<'a>>,
+    stop_reason: Option<String>,
+}
+
+impl<'a> TransitionEngine<'a> {
+    pub fn new(github: &'a GitHubClient, labels: &'a LabelConfig, state_file: &Path) -> Self {
+        Self {
+            github,
+            labels,
+            state_file: state_file.to_path_buf(),
+            status_comment: None,
+            stop_reason: None,
+        }
+    }
+
+    /// Also update the issue's status comment after each transition
+    pub fn with_status_comment(mut self, commenter: StatusCommenter<'a>) -> Self {
+        self.status_comment = Some(commenter);
+        self
+    }
+
+    /// Record why the agent stopped along with the transition, so a rejected
+    /// late hook can't overwrite the reason of an issue that has moved on
+    pub fn with_stop_reason(mut self, reason: Option<String>) -> Self {
+        self.stop_reason = reason;
+        self
+    }
+
+    /// Move an issue from `from` to `to`.
+    ///
+    /// `from` is the issue's current state, or None if it has no pleb label.
+    /// Illegal transitions are logged and rejected with `state::InvalidTransition`
+    /// unless `force` is set. Returns false if the issue was already in `to`.
+    pub async fn transition(
+        &self,
+        issue: &Issue,
+        from: Option<PlebState>,
+        to: PlebState,
+        force: bool,
+    ) -> Result<bool> {
+        let issue_ref = self.github.issue_ref(issue.number);
+
+        if from == Some(to) {
+            tracing::debug!(
+                "Issue {} already in {:?} state, skipping transition",
+                issue_ref,
+                to
+            );
+            return Ok(false);
+        }
+
+        match from {
+            Some(from) => {
+                if let Err(e) = state::validate_transition(issue.number, from, to) {
+                    if !force {
+                        tracing::warn!("Rejected transition: {}", e);
+                        return Err(e);
+                    }
+                    tracing::warn!("Forcing transition: {}", e);
+                }
+
+                self.github
+                    .transition_state(issue.number, from, to, self.labels)
+                    .await?;
+            }
+            None => {
+                // Unmanaged issues enter the state machine at Ready
+                if to != PlebState::Ready && !force {
+                    tracing::warn!(
+                        "Rejected transition: issue {} is not managed by pleb and cannot move to {:?}",
+                        issue_ref,
+                        to
+                    );
+                    anyhow::bail!(
+                        "Issue #{} is not managed by pleb; it can only be moved to Ready",
+                        issue.number
+                    );
+                }
+
+                let label = self.github.state_to_label(to, self.labels);
+                self.github.add_label(issue.number, &label).await?;
+
+                tracing::info!("Transitioned issue {} from (none) to {:?}", issue_ref, to);
+            }
+        }
+
+        self.record(issue, to);
+
+        // The labels are the source of truth; a failed comment update isn't fatal
+        if let Some(commenter) = &self.status_comment {
+            if let Err(e) = commenter.update(&self.state_file, issue.number).await {
+                tracing::warn!("Failed to update status comment on issue {}: {:#}", issue_ref, e);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Mirror a completed transition into the state file
+    fn record(&self, issue: &Issue, to: PlebState) {
+        let updated = IssueTracker::update(&self.state_file, |tracker| {
+            if tracker.update_state(issue.number, to).is_err() {
+                let tracked = tracker.track(issue.number, to);
+                tracked.title = issue.title.clone();
+                tracked.html_url = issue.html_url.clone();
+            }
+            if let Some(reason) = &self.stop_reason {
+                let _ = tracker.set_stop_reason(issue.number, reason);
+            }
+            tracker.get(issue.number).and_then(|t| t.transcript_path.clone())
+        });
+        let transcript = match updated {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                tracing::error!("Failed to save issue state for issue #{}: {}", issue.number, e);
+                return;
+            }
+        };
+
+        // Keep a copy of the session's transcript once the issue is over
+        if to == PlebState::Finished {
+            if let (Some(transcript), Some(state_dir)) = (transcript, self.state_file.parent()) {
+                let issue_dir = state_dir.join(issue.number.to_string());
+                if let Err(e) = transcript::archive(&issue_dir, &transcript) {
+                    tracing::warn!("Failed to archive transcript for issue #{}: {}", issue.number, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,634 @@
+//! Interactive terminal UI (`pleb ui`).
+//!
+//! Shows the same data as `pleb status`: daemon status and the managed issues,
+//! grouped by state, with a preview of the selected issue's tmux pane. While
+//! the daemon is running its state is streamed over the IPC socket; otherwise
+//! the state files are re-read every few seconds.
+//!
+//! Actions that print output or take over the terminal (attach, transition,
+//! cleanup, restore) are returned to the caller as an `Action`, which runs
+//! them with the terminal restored and then shows the UI again.
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::config::{Config, RepoConfig};
+use crate::github::IssueRef;
+use crate::ipc::{IpcClient, RepoSnapshot, StatusSnapshot};
+use crate::state::{self, IssueTracker, PlebState};
+use crate::session::{self, SessionBackend};
+
+/// How often state files are re-read while the daemon is not running
+const LOCAL_REFRESH: Duration = Duration::from_secs(2);
+
+/// How often the pane preview is captured
+const PREVIEW_REFRESH: Duration = Duration::from_secs(1);
+
+/// Lines of pane scrollback captured for the preview
+const PREVIEW_LINES: u32 = 200;
+
+/// Order of the state groups in the issue list
+const GROUP_ORDER: [PlebState; 6] = [
+    PlebState::Waiting,
+    PlebState::Working,
+    PlebState::Provisioning,
+    PlebState::Ready,
+    PlebState::Done,
+    PlebState::Finished,
+];
+
+/// States offered by the transition picker (as accepted by `pleb transition`)
+const TRANSITION_TARGETS: [&str; 6] = ["ready", "provisioning", "waiting", "working", "done", "none"];
+
+/// Something the caller runs with the terminal restored
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    /// Attach to (or switch to) the issue's tmux window
+    Attach(IssueRef),
+    /// Transition the issue to a state, as `pleb transition` would
+    Transition { issue: IssueRef, state: String },
+    /// Clean up the issue, as `pleb cleanup` would
+    Cleanup(IssueRef),
+    /// Restore sessions and worktrees of the issue's repository, as `pleb restore` would
+    Restore(IssueRef),
+}
+
+/// Where the shown state comes from
+#[derive(Debug, Clone)]
+enum Feed {
+    Daemon(StatusSnapshot),
+    Local(Vec<RepoSnapshot>),
+}
+
+impl Feed {
+    fn repos(&self) -> &[RepoSnapshot] {
+        match self {
+            Feed::Daemon(snapshot) => &snapshot.repos,
+            Feed::Local(repos) => repos,
+        }
+    }
+}
+
+/// One issue in the list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub issue: IssueRef,
+    pub title: String,
+    pub state: PlebState,
+    /// Position in the queue, for ready issues waiting for a free session
+    pub queued: Option<usize>,
+    pub state_age: String,
+    pub html_url: String,
+    pub pr_url: Option<String>,
+    pub worktree_path: Option<String>,
+}
+
+/// Group the issues of every repository by state, in `GROUP_ORDER`.
+/// Empty groups are left out.
+pub fn group_by_state(repos: &[RepoSnapshot], now: u64) -> Vec<(PlebState, Vec<Entry>)> {
+    GROUP_ORDER
+        .iter()
+        .map(|&state| {
+            let mut entries: Vec<Entry> = repos
+                .iter()
+                .flat_map(|repo| {
+                    repo.issues.iter().filter(|t| t.state == state).map(|tracked| Entry {
+                        issue: IssueRef::new(&repo.owner, &repo.repo, tracked.issue_number),
+                        title: tracked.title.clone(),
+                        state,
+                        queued: repo.queued.iter().position(|&n| n == tracked.issue_number),
+                        state_age: state::format_duration(Duration::from_secs(
+                            now.saturating_sub(tracked.entered_current_state_at()),
+                        )),
+                        html_url: tracked.html_url.clone(),
+                        pr_url: tracked.pr_url.clone(),
+                        worktree_path: tracked
+                            .worktree_path
+                            .as_ref()
+                            .map(|path| path.display().to_string()),
+                    })
+                })
+                .collect();
+            // Queued issues in queue order, everything else by issue number
+            entries.sort_by_key(|e| (e.queued.unwrap_or(usize::MAX), e.issue.number));
+            (state, entries)
+        })
+        .filter(|(_, entries)| !entries.is_empty())
+        .collect()
+}
+
+/// Read the state files of the given repositories
+fn local_snapshot(repos: &[RepoConfig]) -> Vec<RepoSnapshot> {
+    repos
+        .iter()
+        .map(|repo| {
+            let tracker = repo
+                .state_file()
+                .and_then(|path| IssueTracker::load(&path))
+                .unwrap_or_else(|e| {
+                    tracing::debug!("Failed to load state for {}: {:#}", repo.full_name(), e);
+                    IssueTracker::new()
+                });
+            RepoSnapshot {
+                owner: repo.github.owner.clone(),
+                repo: repo.github.repo.clone(),
+                issues: tracker.all().into_iter().cloned().collect(),
+                queued: tracker.queued(),
+            }
+        })
+        .collect()
+}
+
+/// Keep `tx` updated from the daemon socket, or from the state files while the
+/// daemon can't be reached
+async fn run_feed(client: IpcClient, repos: Vec<RepoConfig>, tx: watch::Sender<Feed>) {
+    loop {
+        if let Ok(mut stream) = client.watch_status().await {
+            while let Ok(Some(mut snapshot)) = stream.next().await {
+                // Only show the repositories selected with --repo
+                snapshot.repos.retain(|r| {
+                    repos
+                        .iter()
+                        .any(|c| c.github.owner == r.owner && c.github.repo == r.repo)
+                });
+                if tx.send(Feed::Daemon(snapshot)).is_err() {
+                    return;
+                }
+            }
+        }
+
+        if tx.send(Feed::Local(local_snapshot(&repos))).is_err() {
+            return;
+        }
+        tokio::time::sleep(LOCAL_REFRESH).await;
+    }
+}
+
+/// Open a URL in the default browser
+fn open_url(url: &str) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+    std::process::Command::new(opener)
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", opener))?;
+    Ok(())
+}
+
+/// Modal input the UI is waiting for
+enum Prompt {
+    /// Picking a target state; holds the highlighted index into `TRANSITION_TARGETS`
+    Transition(usize),
+    /// Waiting for y/n before cleaning up
+    ConfirmCleanup,
+}
+
+pub struct Ui {
+    repos: Vec<RepoConfig>,
+    tmux: Arc<dyn SessionBackend>,
+    feed: watch::Receiver<Feed>,
+    /// State files read right after an action, shown until the feed changes
+    local_override: Option<Vec<RepoSnapshot>>,
+    /// Selected issue; kept across refreshes so the selection follows the issue
+    selected: Option<IssueRef>,
+    prompt: Option<Prompt>,
+    preview: String,
+    preview_at: Option<Instant>,
+    message: Option<String>,
+}
+
+impl Ui {
+    /// Start following the daemon (or the state files) for the selected repositories
+    pub fn new(config: &Config, repo_selector: Option<&str>) -> Result<Self> {
+        let repos = match repo_selector {
+            Some(_) => vec![config.select_repo(repo_selector)?],
+            None => config.repos(),
+        };
+
+        let (tx, feed) = watch::channel(Feed::Local(local_snapshot(&repos)));
+        tokio::spawn(run_feed(IpcClient::new(&config.daemon_dir()?), repos.clone(), tx));
+
+        Ok(Self {
+            repos,
+            tmux: session::backend(&config.tmux, Vec::new()),
+            feed,
+            local_override: None,
+            selected: None,
+            prompt: None,
+            preview: String::new(),
+            preview_at: None,
+            message: None,
+        })
+    }
+
+    /// Show the UI until the user picks an action
+    pub async fn run(&mut self) -> Result<Action> {
+        let mut terminal = ratatui::init();
+        let result = self.event_loop(&mut terminal).await;
+        ratatui::restore();
+
+        // Whatever the action changes is shown when the UI comes back
+        self.local_override = Some(local_snapshot(&self.repos));
+        self.feed.mark_unchanged();
+        self.preview_at = None;
+
+        result
+    }
+
+    async fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<Action> {
+        loop {
+            if self.feed.has_changed().unwrap_or(false) {
+                self.feed.mark_unchanged();
+                self.local_override = None;
+            }
+
+            let groups = group_by_state(self.repos_shown().as_slice(), state::unix_now());
+            let entries: Vec<&Entry> = groups.iter().flat_map(|(_, e)| e).collect();
+            self.keep_selection(&entries);
+            let selected = self.selected_entry(&entries).cloned();
+
+            if self.preview_at.is_none_or(|at| at.elapsed() >= PREVIEW_REFRESH) {
+                self.preview = match &selected {
+                    Some(entry) => match self.tmux.capture_pane(&entry.issue, PREVIEW_LINES).await {
+                        Ok(Some(text)) => text,
+                        Ok(None) => "No tmux window for this issue.".to_string(),
+                        Err(e) => format!("{:#}", e),
+                    },
+                    None => String::new(),
+                };
+                self.preview_at = Some(Instant::now());
+            }
+
+            terminal.draw(|frame| self.draw(frame, &groups, selected.as_ref()))?;
+
+            let ready = tokio::task::block_in_place(|| event::poll(Duration::from_millis(250)))?;
+            if !ready {
+                continue;
+            }
+            let Event::Key(key) = tokio::task::block_in_place(event::read)? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(Action::Quit);
+            }
+
+            if let Some(action) = self.handle_key(key.code, &entries, selected.as_ref()) {
+                return Ok(action);
+            }
+        }
+    }
+
+    fn repos_shown(&self) -> Vec<RepoSnapshot> {
+        match &self.local_override {
+            Some(repos) => repos.clone(),
+            None => self.feed.borrow().repos().to_vec(),
+        }
+    }
+
+    /// Select the first issue if nothing (or an issue that disappeared) is selected
+    fn keep_selection(&mut self, entries: &[&Entry]) {
+        let present = self
+            .selected
+            .as_ref()
+            .is_some_and(|issue| entries.iter().any(|e| &e.issue == issue));
+        if !present {
+            self.selected = entries.first().map(|e| e.issue.clone());
+            self.preview_at = None;
+        }
+    }
+
+    fn selected_entry<'a>(&self, entries: &[&'a Entry]) -> Option<&'a Entry> {
+        let issue = self.selected.as_ref()?;
+        entries.iter().find(|e| &e.issue == issue).copied()
+    }
+
+    fn move_selection(&mut self, entries: &[&Entry], delta: isize) {
+        if entries.is_empty() {
+            return;
+        }
+        let current = self
+            .selected
+            .as_ref()
+            .and_then(|issue| entries.iter().position(|e| &e.issue == issue))
+            .unwrap_or(0);
+        let next = (current as isize + delta).clamp(0, entries.len() as isize - 1) as usize;
+        self.selected = Some(entries[next].issue.clone());
+        self.preview_at = None;
+    }
+
+    fn handle_key(&mut self, code: KeyCode, entries: &[&Entry], selected: Option<&Entry>) -> Option<Action> {
+        match self.prompt.take() {
+            Some(Prompt::Transition(index)) => {
+                match code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.prompt = Some(Prompt::Transition(index.saturating_sub(1)))
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.prompt = Some(Prompt::Transition((index + 1).min(TRANSITION_TARGETS.len() - 1)))
+                    }
+                    KeyCode::Enter => {
+                        return selected.map(|entry| Action::Transition {
+                            issue: entry.issue.clone(),
+                            state: TRANSITION_TARGETS[index].to_string(),
+                        })
+                    }
+                    KeyCode::Esc => {}
+                    _ => self.prompt = Some(Prompt::Transition(index)),
+                }
+                return None;
+            }
+            Some(Prompt::ConfirmCleanup) => {
+                if code == KeyCode::Char('y') {
+                    return selected.map(|entry| Action::Cleanup(entry.issue.clone()));
+                }
+                return None;
+            }
+            None => {}
+        }
+
+        self.message = None;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(entries, -1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(entries, 1),
+            KeyCode::Enter | KeyCode::Char('a') => {
+                return selected.map(|entry| Action::Attach(entry.issue.clone()))
+            }
+            KeyCode::Char('t') if selected.is_some() => {
+                let current = selected
+                    .and_then(|entry| TRANSITION_TARGETS.iter().position(|s| *s == entry.state.name()))
+                    .unwrap_or(0);
+                self.prompt = Some(Prompt::Transition(current));
+            }
+            KeyCode::Char('c') if selected.is_some() => self.prompt = Some(Prompt::ConfirmCleanup),
+            KeyCode::Char('r') => return selected.map(|entry| Action::Restore(entry.issue.clone())),
+            KeyCode::Char('o') | KeyCode::Char('i') => {
+                if let Some(entry) = selected {
+                    let url = match (code, &entry.pr_url) {
+                        (KeyCode::Char('o'), Some(pr_url)) => pr_url,
+                        _ => &entry.html_url,
+                    };
+                    self.message = Some(match open_url(url) {
+                        Ok(()) => format!("Opened {}", url),
+                        Err(e) => format!("{:#}", e),
+                    });
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn draw(&self, frame: &mut Frame, groups: &[(PlebState, Vec<Entry>)], selected: Option<&Entry>) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+
+        frame.render_widget(Paragraph::new(self.header_line()), header);
+
+        // Issue list with a header row per state
+        let mut items = Vec::new();
+        let mut selected_row = None;
+        for (state, entries) in groups {
+            items.push(ListItem::new(Line::from(
+                format!("{} ({})", state.name(), entries.len()).bold(),
+            )));
+            for entry in entries {
+                if selected.is_some_and(|s| s.issue == entry.issue) {
+                    selected_row = Some(items.len());
+                }
+                let id = if self.repos.len() > 1 {
+                    format!("{}#{}", entry.issue.repo, entry.issue.number)
+                } else {
+                    format!("#{}", entry.issue.number)
+                };
+                let age = match entry.queued {
+                    Some(position) => format!("queued #{}", position + 1),
+                    None => entry.state_age.clone(),
+                };
+                items.push(ListItem::new(Line::from(vec![
+                    Span::raw(format!("  {:<8} ", id)),
+                    Span::raw(entry.title.clone()),
+                    Span::raw(format!("  {}", age)).dim(),
+                ])));
+            }
+        }
+        if items.is_empty() {
+            items.push(ListItem::new("No managed issues found.".dim()));
+        }
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(" Issues "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default().with_selected(selected_row);
+        frame.render_stateful_widget(list, list_area, &mut list_state);
+
+        // Details and pane preview of the selected issue
+        let [info_area, preview_area] =
+            Layout::vertical([Constraint::Length(6), Constraint::Min(0)]).areas(detail_area);
+        let info = match selected {
+            Some(entry) => vec![
+                Line::from(format!("{} {}", entry.issue, entry.title).bold()),
+                Line::from(format!("State: {} for {}", entry.state.name(), entry.state_age)),
+                Line::from(format!("PR: {}", entry.pr_url.as_deref().unwrap_or("-"))),
+                Line::from(format!("Worktree: {}", entry.worktree_path.as_deref().unwrap_or("-"))),
+            ],
+            None => Vec::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(info).block(Block::default().borders(Borders::ALL).title(" Issue ")),
+            info_area,
+        );
+
+        // Show the end of the pane, like the terminal would
+        let visible = preview_area.height.saturating_sub(2) as usize;
+        let lines: Vec<&str> = self.preview.lines().collect();
+        let tail = lines[lines.len().saturating_sub(visible)..].join("\n");
+        frame.render_widget(
+            Paragraph::new(tail).block(Block::default().borders(Borders::ALL).title(" Pane ")),
+            preview_area,
+        );
+
+        let footer_text = match (&self.prompt, &self.message) {
+            (Some(Prompt::ConfirmCleanup), _) => Line::from(
+                format!(
+                    "Clean up {}? Removes its worktree and tmux window. (y/n)",
+                    selected.map(|e| e.issue.to_string()).unwrap_or_default()
+                )
+                .yellow(),
+            ),
+            (_, Some(message)) => Line::from(message.clone()),
+            _ => Line::from(
+                "↑/↓ select  a attach  t transition  c cleanup  r restore  o open PR  i open issue  q quit"
+                    .dim(),
+            ),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+
+        if let Some(Prompt::Transition(index)) = &self.prompt {
+            let area = centered(frame.area(), 30, TRANSITION_TARGETS.len() as u16 + 2);
+            let items: Vec<ListItem> = TRANSITION_TARGETS.iter().map(|s| ListItem::new(*s)).collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Transition to "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_widget(Clear, area);
+            frame.render_stateful_widget(list, area, &mut ListState::default().with_selected(Some(*index)));
+        }
+    }
+
+    fn header_line(&self) -> Line<'static> {
+        if self.local_override.is_some() {
+            return Line::from("Refreshing...".dim());
+        }
+        match &*self.feed.borrow() {
+            Feed::Daemon(snapshot) => {
+                let uptime = elapsed(snapshot.started_at);
+                let mut text = format!("Daemon: running (PID: {}, uptime: {})", snapshot.pid, uptime);
+                if !snapshot.rate_budget.is_empty() {
+                    text.push_str(&format!("  GitHub API: {}", snapshot.rate_budget));
+                }
+                Line::from(text)
+            }
+            Feed::Local(_) => Line::from("Daemon: stopped (showing state files)".yellow()),
+        }
+    }
+}
+
+/// Time elapsed since a Unix timestamp, formatted for display
+fn elapsed(timestamp: u64) -> String {
+    state::format_duration(Duration::from_secs(state::unix_now().saturating_sub(timestamp)))
+}
+
+/// A `width` x `height` rectangle in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Wait for Enter after an action printed its output
+pub fn wait_for_enter() -> Result<()> {
+    use std::io::Write;
+
+    print!("\nPress Enter to return to pleb ui...");
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_by_state() {
+        let mut tracker = IssueTracker::new();
+        tracker.track(5, PlebState::Working).title = "Add invoices".to_string();
+        tracker.track(9, PlebState::Ready);
+        tracker.track(3, PlebState::Ready);
+        tracker.track(2, PlebState::Waiting);
+        tracker.enqueue(9);
+        tracker.enqueue(3);
+
+        let repos = vec![RepoSnapshot {
+            owner: "acme".to_string(),
+            repo: "api".to_string(),
+            issues: tracker.all().into_iter().cloned().collect(),
+            queued: tracker.queued(),
+        }];
+        let groups = group_by_state(&repos, state::unix_now());
+
+        let summary: Vec<(PlebState, Vec<u64>)> = groups
+            .iter()
+            .map(|(state, entries)| (*state, entries.iter().map(|e| e.issue.number).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (PlebState::Waiting, vec![2]),
+                (PlebState::Working, vec![5]),
+                // Queue order, not issue number
+                (PlebState::Ready, vec![9, 3]),
+            ]
+        );
+
+        let working = &groups[1].1[0];
+        assert_eq!(working.issue, IssueRef::new("acme", "api", 5));
+        assert_eq!(working.title, "Add invoices");
+        assert_eq!(groups[2].1[1].queued, Some(1));
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut tracker = IssueTracker::new();
+        tracker.track(5, PlebState::Working).title = "Add invoices".to_string();
+        tracker.track(2, PlebState::Waiting).title = "Fix login redirect".to_string();
+        let repos = vec![RepoSnapshot {
+            owner: "acme".to_string(),
+            repo: "api".to_string(),
+            issues: tracker.all().into_iter().cloned().collect(),
+            queued: Vec::new(),
+        }];
+
+        let (_tx, feed) = watch::channel(Feed::Daemon(StatusSnapshot {
+            pid: 4242,
+            started_at: state::unix_now(),
+            rate_budget: "core 4890/5000".to_string(),
+            repos: repos.clone(),
+        }));
+        let ui = Ui {
+            repos: Vec::new(),
+            tmux: Arc::new(session::fake::FakeBackend::default()),
+            feed,
+            local_override: None,
+            selected: Some(IssueRef::new("acme", "api", 5)),
+            prompt: None,
+            preview: "> claude\nWorking on invoices...".to_string(),
+            preview_at: None,
+            message: None,
+        };
+
+        let groups = group_by_state(&repos, state::unix_now());
+        let selected = groups[1].1.first();
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| ui.draw(frame, &groups, selected)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(screen.contains("Daemon: running (PID: 4242"));
+        assert!(screen.contains("waiting (1)"));
+        assert!(screen.contains("#5       Add invoices"));
+        assert!(screen.contains("Working on invoices..."));
+    }
+}
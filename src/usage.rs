@@ -0,0 +1,469 @@
+//! Token usage and cost per issue.
+//!
+//! Every assistant message in a Claude Code transcript (and in a headless
+//! run's stream-json output) carries the model and its token usage. pleb adds
+//! them up by day and model into `usage.json` in the issue directory, which
+//! outlives `pleb cleanup` so `pleb report costs` still sees finished issues.
+//! Transcripts only grow, so the ledger remembers how far each one was counted
+//! and later counts only read the lines added since; counting the same one
+//! twice is harmless. It also remembers the last message counted, since the
+//! lines of a message still streaming can fall on both sides of that point. Lines without a timestamp (headless output) go on the day
+//! they were counted, which keeps earlier runs of a resumed headless session on
+//! their own days. Costs are worked out from `[costs]` prices when shown, so
+//! changing a price applies to past usage too.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{AddAssign, SubAssign};
+use std::path::Path;
+
+use crate::config::CostsConfig;
+
+/// Usage ledger in the issue directory
+pub const USAGE_FILE: &str = "usage.json";
+
+/// Tokens used by one model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Tokens {
+    pub input: u64,
+    pub output: u64,
+    /// Input tokens written to the prompt cache
+    pub cache_write: u64,
+    /// Input tokens read from the prompt cache
+    pub cache_read: u64,
+}
+
+impl Tokens {
+    /// Read a message's `usage` object
+    fn from_usage(usage: &Value) -> Self {
+        let count = |key| usage.get(key).and_then(Value::as_u64).unwrap_or(0);
+        Self {
+            input: count("input_tokens"),
+            output: count("output_tokens"),
+            cache_write: count("cache_creation_input_tokens"),
+            cache_read: count("cache_read_input_tokens"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl AddAssign for Tokens {
+    fn add_assign(&mut self, other: Self) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_write += other.cache_write;
+        self.cache_read += other.cache_read;
+    }
+}
+
+impl SubAssign for Tokens {
+    fn sub_assign(&mut self, other: Self) {
+        self.input = self.input.saturating_sub(other.input);
+        self.output = self.output.saturating_sub(other.output);
+        self.cache_write = self.cache_write.saturating_sub(other.cache_write);
+        self.cache_read = self.cache_read.saturating_sub(other.cache_read);
+    }
+}
+
+/// Tokens by model name
+pub type ModelTokens = BTreeMap<String, Tokens>;
+
+/// Everything an issue's sessions have used
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IssueUsage {
+    /// Issue title when usage was last recorded, for reports after cleanup
+    #[serde(default)]
+    pub title: String,
+    /// Usage by transcript file
+    #[serde(default)]
+    pub transcripts: BTreeMap<String, TranscriptUsage>,
+}
+
+/// What has been counted from one transcript
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TranscriptUsage {
+    /// Bytes of the transcript counted so far (whole lines only)
+    #[serde(default)]
+    pub counted: u64,
+    /// Usage by (local) day
+    #[serde(default)]
+    pub days: BTreeMap<NaiveDate, ModelTokens>,
+    /// The last message counted, which lines added later may repeat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_message: Option<Message>,
+}
+
+/// One assistant message's usage
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Message {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub day: NaiveDate,
+    pub model: String,
+    pub tokens: Tokens,
+}
+
+impl IssueUsage {
+    /// Load the ledger from an issue directory (empty if there is none)
+    pub fn load(issue_dir: &Path) -> Result<Self> {
+        let path = issue_dir.join(USAGE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read usage file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse usage file: {}", path.display()))
+    }
+
+    /// Save the ledger to an issue directory
+    pub fn save(&self, issue_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(issue_dir)
+            .with_context(|| format!("Failed to create issue directory: {}", issue_dir.display()))?;
+        let path = issue_dir.join(USAGE_FILE);
+        let tmp_path = issue_dir.join(format!("{}.tmp", USAGE_FILE));
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize usage")?;
+        std::fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write usage file: {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to rename usage file to: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Tokens by model, counting only days on or after `since`
+    pub fn by_model(&self, since: Option<NaiveDate>) -> ModelTokens {
+        let mut totals = ModelTokens::new();
+        for transcript in self.transcripts.values() {
+            for (day, models) in &transcript.days {
+                if since.is_some_and(|since| *day < since) {
+                    continue;
+                }
+                for (model, tokens) in models {
+                    *totals.entry(model.clone()).or_default() += *tokens;
+                }
+            }
+        }
+        totals
+    }
+}
+
+/// The assistant messages of a transcript with their usage, in order.
+/// Messages without a timestamp (stream-json output has none) count on `fallback_day`.
+pub fn parse(jsonl: &str, fallback_day: NaiveDate) -> Vec<Message> {
+    // A message split over several lines repeats its usage on each; the last one counts
+    let mut messages: Vec<Message> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for line in jsonl.lines() {
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if value.get("type").and_then(Value::as_str) != Some("assistant") {
+            continue;
+        }
+        let Some(message) = value.get("message") else {
+            continue;
+        };
+        let Some(usage) = message.get("usage") else {
+            continue;
+        };
+        let tokens = Tokens::from_usage(usage);
+        let model = message.get("model").and_then(Value::as_str).unwrap_or("unknown");
+        if tokens.is_empty() {
+            // Claude Code's own "<synthetic>" messages, for instance
+            continue;
+        }
+
+        let day = value
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&chrono::Local).date_naive())
+            .unwrap_or(fallback_day);
+        let id = message.get("id").and_then(Value::as_str);
+        match id.and_then(|id| seen.get(id)) {
+            Some(&index) => messages[index].tokens = tokens,
+            None => {
+                if let Some(id) = id {
+                    seen.insert(id.to_string(), messages.len());
+                }
+                messages.push(Message { id: id.map(str::to_string), day, model: model.to_string(), tokens });
+            }
+        }
+    }
+    messages
+}
+
+/// Add up messages' usage by day and model
+pub fn by_day(messages: &[Message]) -> BTreeMap<NaiveDate, ModelTokens> {
+    let mut days: BTreeMap<NaiveDate, ModelTokens> = BTreeMap::new();
+    for message in messages {
+        *days.entry(message.day).or_default().entry(message.model.clone()).or_default() += message.tokens;
+    }
+    days
+}
+
+/// Count the lines added to a transcript since it was last counted into the
+/// issue's ledger. A transcript that is gone keeps what was counted before; one
+/// that shrank was replaced and is counted again from the start.
+pub fn record(issue_dir: &Path, title: &str, transcript: &Path) -> Result<IssueUsage> {
+    let mut usage = IssueUsage::load(issue_dir)?;
+    if !title.is_empty() {
+        usage.title = title.to_string();
+    }
+
+    let jsonl = match std::fs::read_to_string(transcript) {
+        Ok(jsonl) => jsonl,
+        Err(e) => {
+            tracing::debug!("Can't read transcript {}: {}", transcript.display(), e);
+            return Ok(usage);
+        }
+    };
+    let modified = std::fs::metadata(transcript)
+        .and_then(|meta| meta.modified())
+        .map(chrono::DateTime::<chrono::Local>::from)
+        .unwrap_or_else(|_| chrono::Local::now());
+
+    let entry = usage.transcripts.entry(transcript.display().to_string()).or_default();
+    if entry.counted as usize > jsonl.len() {
+        *entry = TranscriptUsage::default();
+    }
+    // A line still being written is counted next time
+    let end = jsonl.rfind('\n').map_or(0, |i| i + 1).max(entry.counted as usize);
+    let added = jsonl.get(entry.counted as usize..end).unwrap_or_default();
+    let messages = parse(added, modified.date_naive());
+
+    // The last message counted may have gone on streaming; its later lines replace what was counted
+    if let Some(last) = entry.last_message.as_ref().filter(|last| last.id.is_some()) {
+        if messages.iter().any(|m| m.id == last.id) {
+            if let Some(tokens) = entry.days.get_mut(&last.day).and_then(|models| models.get_mut(&last.model)) {
+                *tokens -= last.tokens;
+            }
+        }
+    }
+
+    for (day, models) in by_day(&messages) {
+        let counted = entry.days.entry(day).or_default();
+        for (model, tokens) in models {
+            *counted.entry(model).or_default() += tokens;
+        }
+    }
+    if let Some(last) = messages.last() {
+        entry.last_message = Some(last.clone());
+    }
+    entry.counted = end as u64;
+
+    usage.save(issue_dir)?;
+    Ok(usage)
+}
+
+/// Cost of the tokens in USD, or None if a model that was used has no price
+pub fn cost(models: &ModelTokens, costs: &CostsConfig) -> Option<f64> {
+    models.iter().try_fold(0.0, |total, (model, tokens)| {
+        let price = costs.price(model)?;
+        Some(
+            total
+                + (tokens.input as f64 * price.input
+                    + tokens.output as f64 * price.output
+                    + tokens.cache_write as f64 * price.cache_write
+                    + tokens.cache_read as f64 * price.cache_read)
+                    / 1_000_000.0,
+        )
+    })
+}
+
+/// Tokens of all models together
+pub fn total(models: &ModelTokens) -> Tokens {
+    let mut total = Tokens::default();
+    for tokens in models.values() {
+        total += *tokens;
+    }
+    total
+}
+
+/// e.g. "12.3k in, 4.1k out, 1.2M cache read, 80.2k cache write"
+pub fn describe(tokens: &Tokens) -> String {
+    format!(
+        "{} in, {} out, {} cache read, {} cache write",
+        format_count(tokens.input),
+        format_count(tokens.output),
+        format_count(tokens.cache_read),
+        format_count(tokens.cache_write)
+    )
+}
+
+/// A cost for display, "unknown" when a model has no price
+pub fn format_cost(cost: Option<f64>) -> String {
+    match cost {
+        Some(cost) => format!("${:.2}", cost),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Parse `--since`: a day (YYYY-MM-DD) or a number of days or weeks ago ("7d", "2w")
+pub fn parse_since(value: &str) -> Result<NaiveDate, String> {
+    if let Ok(day) = value.parse::<NaiveDate>() {
+        return Ok(day);
+    }
+    let invalid = || format!("expected a day like 2026-10-01 or a span like 7d or 2w, got '{}'", value);
+    let (count, unit) = value.split_at(value.len().saturating_sub(1));
+    let count: u64 = count.parse().map_err(|_| invalid())?;
+    let days = match unit {
+        "d" => count,
+        "w" => count * 7,
+        _ => return Err(invalid()),
+    };
+    Ok(chrono::Local::now().date_naive() - chrono::Days::new(days))
+}
+
+/// A token count with a k/M suffix
+fn format_count(count: u64) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    const TRANSCRIPT: &str = r#"{"type":"user","message":{"role":"user","content":"Fix it"},"timestamp":"2026-10-01T10:00:00Z"}
+{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","content":[{"type":"text","text":"On it"}],"usage":{"input_tokens":10,"output_tokens":2,"cache_creation_input_tokens":1000,"cache_read_input_tokens":0}},"timestamp":"2026-10-01T12:00:00Z"}
+{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","content":[{"type":"tool_use","id":"t","name":"Bash","input":{}}],"usage":{"input_tokens":10,"output_tokens":40,"cache_creation_input_tokens":1000,"cache_read_input_tokens":0}},"timestamp":"2026-10-01T12:00:01Z"}
+{"type":"assistant","message":{"id":"msg_2","model":"claude-haiku-4-5","content":[],"usage":{"input_tokens":5,"output_tokens":5,"cache_read_input_tokens":2000}},"timestamp":"2026-10-05T12:00:00Z"}
+{"type":"assistant","message":{"id":"msg_3","model":"<synthetic>","content":[],"usage":{"input_tokens":0,"output_tokens":0}},"timestamp":"2026-10-05T12:00:00Z"}
+not json
+"#;
+
+    #[test]
+    fn test_parse_dedupes_messages() {
+        let days = by_day(&parse(TRANSCRIPT, day("2026-01-01")));
+        assert_eq!(days.len(), 2);
+        let first = days.values().next().unwrap();
+        assert_eq!(
+            first["claude-sonnet-4-5"],
+            Tokens { input: 10, output: 40, cache_write: 1000, cache_read: 0 }
+        );
+        assert!(!days.values().any(|models| models.contains_key("<synthetic>")));
+
+        // stream-json events have no timestamps
+        let headless = r#"{"type":"assistant","message":{"id":"m","model":"claude-opus-4-1","usage":{"input_tokens":3,"output_tokens":4}},"session_id":"s"}"#;
+        let days = by_day(&parse(headless, day("2026-02-02")));
+        assert_eq!(days[&day("2026-02-02")]["claude-opus-4-1"].output, 4);
+    }
+
+    #[test]
+    fn test_cost_and_since() {
+        let costs: CostsConfig = toml::from_str(
+            "[prices.claude-sonnet-4]\ninput = 3.0\noutput = 15.0\ncache_write = 3.75\ncache_read = 0.3\n\
+             [prices.claude-haiku-4]\ninput = 1.0\noutput = 5.0\ncache_read = 0.1\n",
+        )
+        .unwrap();
+        let mut usage = IssueUsage::default();
+        usage.transcripts.insert(
+            "/t.jsonl".to_string(),
+            TranscriptUsage { days: by_day(&parse(TRANSCRIPT, day("2026-01-01"))), ..Default::default() },
+        );
+
+        let all = usage.by_model(None);
+        // 10*3 + 40*15 + 1000*3.75, then 5*1 + 5*5 + 2000*0.1, per million
+        let expected = (30.0 + 600.0 + 3750.0 + 5.0 + 25.0 + 200.0) / 1_000_000.0;
+        assert!((cost(&all, &costs).unwrap() - expected).abs() < 1e-12);
+        assert_eq!(total(&all).cache_read, 2000);
+
+        let late = usage.by_model(Some(day("2026-10-03")));
+        assert_eq!(late.keys().collect::<Vec<_>>(), ["claude-haiku-4-5"]);
+
+        let unpriced = ModelTokens::from([("gpt-5".to_string(), Tokens { input: 1, ..Default::default() })]);
+        assert_eq!(format_cost(cost(&unpriced, &costs)), "unknown");
+        assert_eq!(format_cost(Some(1.234)), "$1.23");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("2026-10-01"), Ok(day("2026-10-01")));
+        let today = chrono::Local::now().date_naive();
+        assert_eq!(parse_since("0d"), Ok(today));
+        assert_eq!(parse_since("2w"), Ok(today - chrono::Days::new(14)));
+        assert!(parse_since("3m").is_err());
+        assert!(parse_since("d").is_err());
+        assert!(parse_since("").is_err());
+    }
+
+    #[test]
+    fn test_record_counts_added_lines() {
+        let dir = std::env::temp_dir().join(format!("pleb-usage-test-{}", std::process::id()));
+        let issue_dir = dir.join("7");
+        std::fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("session.jsonl");
+        std::fs::write(&transcript, TRANSCRIPT).unwrap();
+
+        record(&issue_dir, "Fix it", &transcript).unwrap();
+        let usage = record(&issue_dir, "", &transcript).unwrap();
+        assert_eq!(usage.title, "Fix it");
+        assert_eq!(total(&usage.by_model(None)).output, 45);
+
+        // Claude Code removed the transcript; the counts stay
+        std::fs::remove_file(&transcript).unwrap();
+        record(&issue_dir, "Fix it", &transcript).unwrap();
+        assert_eq!(total(&IssueUsage::load(&issue_dir).unwrap().by_model(None)).output, 45);
+
+        // A resumed headless run appends lines without timestamps; the earlier
+        // run's tokens stay on the day they were counted
+        let transcript = dir.join("headless.jsonl");
+        let set_day = |date: &str| {
+            let time = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().and_hms_opt(12, 0, 0).unwrap();
+            let time = time.and_local_timezone(chrono::Local).unwrap();
+            let file = std::fs::File::options().append(true).open(&transcript).unwrap();
+            file.set_modified(time.into()).unwrap();
+        };
+        let run = r#"{"type":"assistant","message":{"id":"RUN","model":"claude-opus-4-1","usage":{"input_tokens":1,"output_tokens":100}}}"#;
+        std::fs::write(&transcript, format!("{}\n", run.replace("RUN", "a"))).unwrap();
+        set_day("2026-10-01");
+        record(&issue_dir, "Fix it", &transcript).unwrap();
+        let mut file = std::fs::File::options().append(true).open(&transcript).unwrap();
+        std::io::Write::write_all(&mut file, format!("{}\n", run.replace("RUN", "b")).as_bytes()).unwrap();
+        set_day("2026-10-05");
+        let usage = record(&issue_dir, "Fix it", &transcript).unwrap();
+        assert_eq!(total(&usage.by_model(None)).output, 245);
+        // The second run plus msg_2 from the interactive transcript
+        assert_eq!(total(&usage.by_model(Some(day("2026-10-03")))).output, 105);
+
+        assert_eq!(describe(&Tokens { input: 999, output: 1500, cache_write: 0, cache_read: 2_500_000 }),
+            "999 in, 1.5k out, 2.5M cache read, 0 cache write");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_record_message_streamed_across_counts() {
+        let dir = std::env::temp_dir().join(format!("pleb-usage-stream-test-{}", std::process::id()));
+        let issue_dir = dir.join("7");
+        std::fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("session.jsonl");
+        let lines: Vec<&str> = TRANSCRIPT.lines().collect();
+
+        // Counted while msg_1 was still streaming, then again once it was done
+        std::fs::write(&transcript, format!("{}\n{}\n", lines[0], lines[1])).unwrap();
+        let usage = record(&issue_dir, "Fix it", &transcript).unwrap();
+        assert_eq!(total(&usage.by_model(None)).output, 2);
+        std::fs::write(&transcript, TRANSCRIPT).unwrap();
+        let usage = record(&issue_dir, "Fix it", &transcript).unwrap();
+        assert_eq!(total(&usage.by_model(None)).output, 45);
+        assert_eq!(total(&usage.by_model(None)).cache_write, 1000);
+
+        let usage = record(&issue_dir, "Fix it", &transcript).unwrap();
+        assert_eq!(total(&usage.by_model(None)).output, 45);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,355 @@
+//! GitHub webhook receiver.
+//!
+//! An optional HTTP listener that accepts webhook deliveries at `POST /webhook`,
+//! checks the `X-Hub-Signature-256` HMAC against the configured secret and
+//! forwards the events pleb cares about to the orchestrator over a channel,
+//! the same way `IpcServer` forwards hook messages.
+//!
+//! Recorded payloads live in `fixtures/webhooks/`. To replay one by hand:
+//!
+//! ```text
+//! body=fixtures/webhooks/issues_labeled.json
+//! sig=$(openssl dgst -sha256 -hmac "$PLEB_WEBHOOK_SECRET" < $body | awk '{print $2}')
+//! curl -X POST http://127.0.0.1:8787/webhook -H 'X-GitHub-Event: issues' \
+//!      -H "X-Hub-Signature-256: sha256=$sig" --data-binary @$body
+//! ```
+
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::github::IssueRef;
+
+/// A webhook delivery pleb acts on
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookEvent {
+    /// `issues.labeled`
+    IssueLabeled { issue: IssueRef, label: String },
+    /// `issues.closed`
+    IssueClosed { issue: IssueRef },
+    /// `pull_request.closed` (merged or not)
+    PullRequestClosed { pull_request: IssueRef, merged: bool },
+    /// `issue_comment.created`
+    IssueCommentCreated {
+        issue: IssueRef,
+        comment_id: u64,
+        author: String,
+        body: String,
+    },
+}
+
+impl WebhookEvent {
+    /// The issue (or pull request) the event is about
+    pub fn target(&self) -> &IssueRef {
+        match self {
+            WebhookEvent::IssueLabeled { issue, .. }
+            | WebhookEvent::IssueClosed { issue }
+            | WebhookEvent::IssueCommentCreated { issue, .. } => issue,
+            WebhookEvent::PullRequestClosed { pull_request, .. } => pull_request,
+        }
+    }
+}
+
+/// Check a `X-Hub-Signature-256` header ("sha256=<hex>") against the request body
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(hex_signature) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(hex_signature) else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Parse a delivery into an event. Returns None for events and actions pleb ignores.
+pub fn parse_event(event: &str, payload: &Value) -> Result<Option<WebhookEvent>> {
+    let action = payload["action"].as_str().unwrap_or_default();
+
+    let repository = &payload["repository"];
+    let owner = repository["owner"]["login"]
+        .as_str()
+        .context("Missing repository.owner.login")?;
+    let repo = repository["name"].as_str().context("Missing repository.name")?;
+
+    let issue_ref = |object: &Value| -> Result<IssueRef> {
+        let number = object["number"].as_u64().context("Missing issue number")?;
+        Ok(IssueRef::new(owner, repo, number))
+    };
+
+    let parsed = match (event, action) {
+        ("issues", "labeled") => Some(WebhookEvent::IssueLabeled {
+            issue: issue_ref(&payload["issue"])?,
+            label: payload["label"]["name"]
+                .as_str()
+                .context("Missing label.name")?
+                .to_string(),
+        }),
+        ("issues", "closed") => Some(WebhookEvent::IssueClosed {
+            issue: issue_ref(&payload["issue"])?,
+        }),
+        ("pull_request", "closed") => Some(WebhookEvent::PullRequestClosed {
+            pull_request: issue_ref(&payload["pull_request"])?,
+            merged: payload["pull_request"]["merged"].as_bool().unwrap_or(false),
+        }),
+        ("issue_comment", "created") => Some(WebhookEvent::IssueCommentCreated {
+            issue: issue_ref(&payload["issue"])?,
+            comment_id: payload["comment"]["id"].as_u64().context("Missing comment.id")?,
+            author: payload["comment"]["user"]["login"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            body: payload["comment"]["body"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        _ => None,
+    };
+
+    Ok(parsed)
+}
+
+struct WebhookState {
+    secret: Vec<u8>,
+    tx: mpsc::Sender<WebhookEvent>,
+}
+
+/// HTTP listener for GitHub webhook deliveries
+pub struct WebhookServer {
+    listen: String,
+    secret: Vec<u8>,
+}
+
+impl WebhookServer {
+    /// Create a new webhook server (doesn't start listening yet)
+    pub fn new(listen: &str, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            listen: listen.to_string(),
+            secret: secret.into(),
+        }
+    }
+
+    /// Start listening and return the bound address and a channel of accepted events
+    pub async fn start(&self) -> Result<(SocketAddr, mpsc::Receiver<WebhookEvent>)> {
+        let listener = tokio::net::TcpListener::bind(&self.listen)
+            .await
+            .with_context(|| format!("Failed to bind webhook listener to {}", self.listen))?;
+        let addr = listener
+            .local_addr()
+            .context("Failed to get webhook listener address")?;
+
+        let (tx, rx) = mpsc::channel(32);
+        let state = Arc::new(WebhookState {
+            secret: self.secret.clone(),
+            tx,
+        });
+        let app = Router::new()
+            .route("/webhook", post(handle_delivery))
+            .with_state(state);
+
+        tracing::info!("Webhook listener on http://{}/webhook", addr);
+
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("Webhook listener stopped: {}", e);
+            }
+        });
+
+        Ok((addr, rx))
+    }
+}
+
+async fn handle_delivery(
+    State(state): State<Arc<WebhookState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, &'static str) {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let signature = header("x-hub-signature-256");
+    if !verify_signature(&state.secret, &body, &signature) {
+        tracing::warn!("Rejected webhook delivery with missing or invalid signature");
+        return (StatusCode::UNAUTHORIZED, "invalid signature");
+    }
+
+    let event = header("x-github-event");
+    if event == "ping" {
+        return (StatusCode::OK, "pong");
+    }
+
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Rejected webhook delivery with invalid JSON: {}", e);
+            return (StatusCode::BAD_REQUEST, "invalid JSON");
+        }
+    };
+
+    match parse_event(&event, &payload) {
+        Ok(Some(parsed)) => {
+            tracing::debug!("Received webhook event: {:?}", parsed);
+            if state.tx.send(parsed).await.is_err() {
+                // Channel closed, daemon is shutting down
+                return (StatusCode::SERVICE_UNAVAILABLE, "shutting down");
+            }
+            (StatusCode::ACCEPTED, "accepted")
+        }
+        Ok(None) => {
+            tracing::debug!(
+                "Ignoring webhook event '{}' ({})",
+                event,
+                payload["action"].as_str().unwrap_or("no action")
+            );
+            (StatusCode::ACCEPTED, "ignored")
+        }
+        Err(e) => {
+            tracing::warn!("Rejected malformed webhook event '{}': {}", event, e);
+            (StatusCode::BAD_REQUEST, "malformed event")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn fixture(name: &str) -> String {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/webhooks")
+            .join(name);
+        std::fs::read_to_string(&path).expect("fixture should exist")
+    }
+
+    fn sign(secret: &[u8], body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = b"{\"zen\":\"Keep it logically awesome.\"}";
+        let signature = sign(SECRET, body);
+
+        assert!(verify_signature(SECRET, body, &signature));
+        assert!(!verify_signature(b"other-secret", body, &signature));
+        assert!(!verify_signature(SECRET, b"tampered", &signature));
+        assert!(!verify_signature(SECRET, body, ""));
+        assert!(!verify_signature(SECRET, body, "sha256=not-hex"));
+    }
+
+    #[test]
+    fn test_parse_recorded_events() {
+        let issue = IssueRef::new("acme", "api", 42);
+
+        let payload: Value = serde_json::from_str(&fixture("issues_labeled.json")).unwrap();
+        assert_eq!(
+            parse_event("issues", &payload).unwrap(),
+            Some(WebhookEvent::IssueLabeled {
+                issue: issue.clone(),
+                label: "pleb:ready".to_string()
+            })
+        );
+
+        let payload: Value = serde_json::from_str(&fixture("issues_closed.json")).unwrap();
+        assert_eq!(
+            parse_event("issues", &payload).unwrap(),
+            Some(WebhookEvent::IssueClosed { issue: issue.clone() })
+        );
+
+        let payload: Value = serde_json::from_str(&fixture("pull_request_closed.json")).unwrap();
+        assert_eq!(
+            parse_event("pull_request", &payload).unwrap(),
+            Some(WebhookEvent::PullRequestClosed {
+                pull_request: IssueRef::new("acme", "api", 57),
+                merged: true
+            })
+        );
+
+        let payload: Value = serde_json::from_str(&fixture("issue_comment_created.json")).unwrap();
+        match parse_event("issue_comment", &payload).unwrap() {
+            Some(WebhookEvent::IssueCommentCreated { issue: target, comment_id, author, body }) => {
+                assert_eq!(target, issue);
+                assert_eq!(comment_id, 2145678901);
+                assert_eq!(author, "alice");
+                assert!(body.contains("integer cents"));
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+
+        // Actions pleb doesn't act on are ignored
+        let mut payload: Value = serde_json::from_str(&fixture("issues_labeled.json")).unwrap();
+        payload["action"] = Value::from("unlabeled");
+        assert_eq!(parse_event("issues", &payload).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_post_recorded_payloads() {
+        let server = WebhookServer::new("127.0.0.1:0", SECRET);
+        let (addr, mut rx) = server.start().await.unwrap();
+        let url = format!("http://{}/webhook", addr);
+        let client = reqwest::Client::new();
+
+        // Signed delivery is accepted and forwarded
+        let body = fixture("pull_request_closed.json");
+        let response = client
+            .post(&url)
+            .header("X-GitHub-Event", "pull_request")
+            .header("X-Hub-Signature-256", sign(SECRET, body.as_bytes()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.target(), &IssueRef::new("acme", "api", 57));
+
+        // Bad signature is rejected and nothing is forwarded
+        let body = fixture("issues_labeled.json");
+        let response = client
+            .post(&url)
+            .header("X-GitHub-Event", "issues")
+            .header("X-Hub-Signature-256", sign(b"wrong-secret", body.as_bytes()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert!(rx.try_recv().is_err());
+
+        // Ping (sent when the webhook is created) is answered
+        let body = "{\"zen\":\"Design for failure.\"}";
+        let response = client
+            .post(&url)
+            .header("X-GitHub-Event", "ping")
+            .header("X-Hub-Signature-256", sign(SECRET, body.as_bytes()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
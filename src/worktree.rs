@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::process::Command;
 
 use crate::config::PathConfig;
@@ -30,28 +30,23 @@ impl WorktreeManager {
         }
     }
 
-    /// Create a worktree for an issue (idempotent)
+    /// Create a worktree (idempotent)
     /// Creates the specified branch and worktree at `worktree_base/{worktree_name}`
     /// Handles edge cases: orphaned directories, stale git tracking, existing branches
-    pub async fn create_worktree(
-        &self,
-        issue_number: u64,
-        branch_name: &str,
-        worktree_name: &str,
-    ) -> Result<PathBuf> {
+    pub async fn create_worktree(&self, branch_name: &str, worktree_name: &str) -> Result<PathBuf> {
         let worktree_path = self.worktree_base.join(worktree_name);
         let branch_name = branch_name.to_string();
 
         // 1. Check git's worktree tracking (not just filesystem)
-        let is_registered = self.is_worktree_registered(issue_number).await?;
+        let is_registered = self.is_worktree_registered(&worktree_path).await?;
         let path_exists = worktree_path.exists();
 
         match (is_registered, path_exists) {
             // Already exists and registered - return it
             (true, true) => {
                 tracing::debug!(
-                    "Worktree for issue #{} already exists at {}",
-                    issue_number,
+                    "Worktree {} already exists at {}",
+                    worktree_name,
                     worktree_path.display()
                 );
                 return Ok(worktree_path);
@@ -59,8 +54,8 @@ impl WorktreeManager {
             // Registered but path missing - clean up stale git tracking
             (true, false) => {
                 tracing::debug!(
-                    "Cleaning up stale worktree registration for issue #{}",
-                    issue_number
+                    "Cleaning up stale worktree registration for {}",
+                    worktree_name
                 );
                 let _ = Command::new("git")
                     .arg("-C")
@@ -83,8 +78,8 @@ impl WorktreeManager {
             // Not registered but path exists - remove orphaned directory
             (false, true) => {
                 tracing::debug!(
-                    "Removing orphaned worktree directory for issue #{}",
-                    issue_number
+                    "Removing orphaned worktree directory {}",
+                    worktree_path.display()
                 );
                 tokio::fs::remove_dir_all(&worktree_path).await.with_context(|| {
                     format!(
@@ -159,15 +154,15 @@ impl WorktreeManager {
         if !worktree_output.status.success() {
             let stderr = String::from_utf8_lossy(&worktree_output.stderr);
             anyhow::bail!(
-                "Failed to create worktree for issue #{}: {}",
-                issue_number,
+                "Failed to create worktree {}: {}",
+                worktree_name,
                 stderr
             );
         }
 
         tracing::info!(
-            "Created worktree for issue #{} at {}",
-            issue_number,
+            "Created worktree {} at {}",
+            worktree_name,
             worktree_path.display()
         );
 
@@ -212,13 +207,18 @@ impl WorktreeManager {
             .unwrap_or("");
         let branch_name = worktree_name.to_string();
 
+        self.remove_worktree_at(&worktree_path, &branch_name).await
+    }
+
+    /// Remove the worktree at `worktree_path` and delete its branch
+    pub async fn remove_worktree_at(&self, worktree_path: &Path, branch_name: &str) -> Result<()> {
         // 2. Run: git worktree remove {path} --force
         let remove_output = Command::new("git")
             .arg("-C")
             .arg(&self.repo_dir)
             .arg("worktree")
             .arg("remove")
-            .arg(&worktree_path)
+            .arg(worktree_path)
             .arg("--force")
             .output()
             .await
@@ -232,17 +232,13 @@ impl WorktreeManager {
         if !remove_output.status.success() {
             let stderr = String::from_utf8_lossy(&remove_output.stderr);
             anyhow::bail!(
-                "Failed to remove worktree for issue #{}: {}",
-                issue_number,
+                "Failed to remove worktree at {}: {}",
+                worktree_path.display(),
                 stderr
             );
         }
 
-        tracing::info!(
-            "Removed worktree for issue #{} at {}",
-            issue_number,
-            worktree_path.display()
-        );
+        tracing::info!("Removed worktree at {}", worktree_path.display());
 
         // 3. Optionally delete the branch: git branch -D pleb/issue-{number}
         let branch_output = Command::new("git")
@@ -250,7 +246,7 @@ impl WorktreeManager {
             .arg(&self.repo_dir)
             .arg("branch")
             .arg("-D")
-            .arg(&branch_name)
+            .arg(branch_name)
             .output()
             .await
             .with_context(|| {
@@ -334,10 +330,32 @@ impl WorktreeManager {
         Ok(issue_numbers)
     }
 
-    /// Check if a worktree for an issue is registered with git
-    async fn is_worktree_registered(&self, issue_number: u64) -> Result<bool> {
-        let worktrees = self.list_worktrees().await?;
-        Ok(worktrees.contains(&issue_number))
+    /// Check if a worktree path is registered with git
+    async fn is_worktree_registered(&self, worktree_path: &Path) -> Result<bool> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_dir)
+            .arg("worktree")
+            .arg("list")
+            .arg("--porcelain")
+            .output()
+            .await
+            .context("Failed to list worktrees")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to list worktrees: {}", stderr);
+        }
+
+        // Compare resolved paths where they exist (git reports them without symlinks)
+        let resolve = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let target = resolve(worktree_path);
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .any(|path| resolve(Path::new(path.trim())) == target))
     }
 
     /// Check if repo_dir exists and is a git repo, clone if needed
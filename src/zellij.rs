@@ -0,0 +1,279 @@
+//! zellij: each session window is a tab in the configured zellij session.
+//!
+//! zellij's CLI acts on the focused tab, so commands aimed at a window switch
+//! to its tab first; anyone attached to the session sees the switch.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::Path;
+use std::process::Output;
+use tokio::process::Command;
+
+use crate::config::TmuxConfig;
+use crate::github::IssueRef;
+use crate::session::{self, SessionBackend, WindowTarget};
+
+pub struct ZellijManager {
+    session_name: String,
+    /// Environment variables the session is started with (name -> value)
+    env_vars: Vec<(String, String)>,
+}
+
+impl ZellijManager {
+    pub fn new(config: &TmuxConfig) -> Self {
+        Self {
+            session_name: config.session_name.clone(),
+            env_vars: Vec::new(),
+        }
+    }
+
+    /// Add environment variables to be passed to the session
+    pub fn with_envs(mut self, env: Vec<(String, String)>) -> Self {
+        self.env_vars.extend(env);
+        self
+    }
+
+    /// Run `zellij action` against the session
+    async fn action(&self, args: &[&str]) -> Result<Output> {
+        let output = Command::new("zellij")
+            .args(["--session", &self.session_name, "action"])
+            .args(args)
+            .output()
+            .await
+            .context("Failed to execute zellij action")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "zellij action {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output)
+    }
+
+    async fn session_exists(&self) -> Result<bool> {
+        let output = Command::new("zellij")
+            .args(["list-sessions", "--short", "--no-formatting"])
+            .output()
+            .await
+            .context("Failed to list zellij sessions")?;
+
+        // zellij exits with an error when there are no sessions at all
+        Ok(output.status.success()
+            && parse_sessions(&String::from_utf8_lossy(&output.stdout))
+                .any(|name| name == self.session_name))
+    }
+
+    /// Names of the tabs in the session (empty if it doesn't exist)
+    async fn tab_names(&self) -> Result<Vec<String>> {
+        match self.action(&["query-tab-names"]).await {
+            Ok(output) => Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()),
+            Err(e) => {
+                tracing::debug!("Failed to list zellij tabs: {:#}", e);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Find the tab for an issue, returning its name
+    async fn find_tab(&self, issue: &dyn WindowTarget) -> Result<Option<String>> {
+        Ok(self
+            .tab_names()
+            .await?
+            .into_iter()
+            .find(|name| issue.matches_window(name)))
+    }
+
+    /// Switch to the tab for an issue, returning its name
+    async fn focus_tab(&self, issue: &dyn WindowTarget) -> Result<String> {
+        let name = self
+            .find_tab(issue)
+            .await?
+            .with_context(|| format!("No window found for issue {}", issue))?;
+        self.action(&["go-to-tab-name", &name]).await?;
+        Ok(name)
+    }
+}
+
+#[async_trait]
+impl SessionBackend for ZellijManager {
+    /// Start the session in the background if it isn't running. zellij can't
+    /// change the environment of a running session, so the variables only
+    /// reach sessions started here.
+    async fn ensure_session(&self) -> Result<()> {
+        if self.session_exists().await? {
+            return Ok(());
+        }
+
+        tracing::info!("Creating zellij session: {}", self.session_name);
+        let output = Command::new("zellij")
+            .args(["attach", "--create-background", &self.session_name])
+            .envs(self.env_vars.iter().map(|(name, value)| (name, value)))
+            .output()
+            .await
+            .context("Failed to execute zellij attach --create-background")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to create zellij session '{}': {}",
+                self.session_name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    async fn create_window(
+        &self,
+        issue: &dyn WindowTarget,
+        branch_name: &str,
+        working_dir: &Path,
+    ) -> Result<()> {
+        self.ensure_session().await?;
+
+        if self.window_exists(issue).await? {
+            tracing::info!("Window for issue {} already exists", issue);
+            return Ok(());
+        }
+
+        let tab_name = issue.window_name(branch_name);
+        tracing::info!("Creating zellij tab {} in session {}", tab_name, self.session_name);
+        self.action(&[
+            "new-tab",
+            "--name",
+            &tab_name,
+            "--cwd",
+            &working_dir.to_string_lossy(),
+        ])
+        .await?;
+        Ok(())
+    }
+
+    async fn window_exists(&self, issue: &dyn WindowTarget) -> Result<bool> {
+        Ok(self.find_tab(issue).await?.is_some())
+    }
+
+    async fn list_windows(&self) -> Result<Vec<IssueRef>> {
+        Ok(self
+            .tab_names()
+            .await?
+            .iter()
+            .filter_map(|name| session::parse_window_name(name))
+            .collect())
+    }
+
+    async fn kill_window(&self, issue: &dyn WindowTarget) -> Result<()> {
+        if self.find_tab(issue).await?.is_none() {
+            tracing::warn!("No window found for issue {}", issue);
+            return Ok(());
+        }
+
+        let name = self.focus_tab(issue).await?;
+        tracing::info!("Closing zellij tab: {}", name);
+        self.action(&["close-tab"]).await?;
+        Ok(())
+    }
+
+    /// Type into the focused pane of the issue's tab
+    async fn send_keys(&self, issue: &dyn WindowTarget, keys: &str) -> Result<()> {
+        let name = self.focus_tab(issue).await?;
+        tracing::debug!("Sending keys to {}: {}", name, keys);
+        if !keys.is_empty() {
+            self.action(&["write-chars", keys]).await?;
+        }
+        // Enter
+        self.action(&["write", "13"]).await?;
+        Ok(())
+    }
+
+    async fn rename_window(&self, issue: &dyn WindowTarget, state: &str) -> Result<()> {
+        if self.find_tab(issue).await?.is_none() {
+            tracing::warn!("No window found for issue {} to rename", issue);
+            return Ok(());
+        }
+
+        let name = self.focus_tab(issue).await?;
+        let new_name = session::state_window_name(&name, state);
+        tracing::debug!("Renaming tab {} to {}", name, new_name);
+        self.action(&["rename-tab", &new_name]).await?;
+        Ok(())
+    }
+
+    /// zellij has no pane indices; pane 0 is taken to be the leftmost one
+    async fn select_pane(&self, issue: &dyn WindowTarget, pane_index: u32) -> Result<()> {
+        if pane_index != 0 {
+            anyhow::bail!("zellij windows can only select their first pane");
+        }
+        self.focus_tab(issue).await?;
+        // Fails harmlessly when there is nothing to the left
+        if let Err(e) = self.action(&["move-focus", "left"]).await {
+            tracing::debug!("{:#}", e);
+        }
+        Ok(())
+    }
+
+    async fn capture_pane(&self, issue: &dyn WindowTarget, lines: u32) -> Result<Option<String>> {
+        if self.find_tab(issue).await?.is_none() {
+            return Ok(None);
+        }
+        self.focus_tab(issue).await?;
+
+        let path = std::env::temp_dir().join(format!("pleb-zellij-{}.txt", std::process::id()));
+        self.action(&["dump-screen", &path.to_string_lossy(), "--full"])
+            .await?;
+        let text = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).ok();
+        let text = text.with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Ok(Some(session::last_lines(&text, lines)))
+    }
+
+    /// zellij can't pipe a pane's output to a file
+    async fn log_output(&self, issue: &dyn WindowTarget, _log_file: &Path) -> Result<()> {
+        tracing::debug!("zellij can't log the window for {}", issue);
+        Ok(())
+    }
+
+    fn attach_command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new("zellij");
+        cmd.args(["attach", &self.session_name]);
+        cmd
+    }
+
+    async fn attach_window_command(&self, issue: &dyn WindowTarget) -> Result<Option<std::process::Command>> {
+        let Some(name) = self.find_tab(issue).await? else {
+            return Ok(None);
+        };
+
+        let inside = std::env::var("ZELLIJ_SESSION_NAME").is_ok_and(|s| s == self.session_name);
+        if inside {
+            let mut cmd = std::process::Command::new("zellij");
+            cmd.args(["action", "go-to-tab-name", &name]);
+            return Ok(Some(cmd));
+        }
+
+        self.action(&["go-to-tab-name", &name]).await?;
+        Ok(Some(self.attach_command()))
+    }
+}
+
+/// Session names from `zellij list-sessions --short`
+fn parse_sessions(output: &str) -> impl Iterator<Item = &str> {
+    output.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sessions() {
+        let sessions: Vec<&str> = parse_sessions("pleb\n  other \n\n").collect();
+        assert_eq!(sessions, vec!["pleb", "other"]);
+    }
+}